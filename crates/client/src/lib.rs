@@ -0,0 +1,197 @@
+//! Typed async client for the openvdb HTTP API.
+//!
+//! Requests and responses are the server's own types from `fastdb-types`,
+//! so the two can't drift apart.
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use fastdb_types::collection::{Metric, OutOfRange};
+pub use fastdb_types::models;
+
+use fastdb_types::models::{
+    CollectionStatsResponse, CountQueryRequest, CountResponse, CreateCollectionRequest,
+    CreateCollectionResponse, DeleteCollectionResponse, DeleteVectorResponse, ErrorResponse,
+    GetCollectionResponse, HealthResponse, ListCollectionsResponse, QueryRequest, QueryResponse,
+    UpsertRequest, UpsertResponse,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The request never got a response, or the body didn't decode.
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The server answered with a non-success status. `code` is the
+    /// server's machine-readable error kind (e.g. `not_found`), empty if the
+    /// body wasn't an error object.
+    #[error("server returned {status} ({code}): {message}")]
+    Api {
+        status: StatusCode,
+        code: String,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Client for one server, authenticating every call with one API key
+/// (which also selects the tenant).
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl Client {
+    /// `base_url` is the server root, e.g. `http://127.0.0.1:8080`.
+    pub fn new(base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url, api_key)
+    }
+
+    /// Like `new`, reusing a configured `reqwest::Client` (timeouts, TLS,
+    /// connection pool).
+    pub fn with_http_client(
+        http: reqwest::Client,
+        base_url: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        Self {
+            http,
+            base_url,
+            api_key: api_key.into(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.send(self.request(Method::GET, "/health")).await
+    }
+
+    // ---------- collections ----------
+
+    pub async fn create_collection(
+        &self,
+        req: &CreateCollectionRequest,
+    ) -> Result<CreateCollectionResponse> {
+        self.send_json(Method::POST, "/collections", req).await
+    }
+
+    pub async fn list_collections(&self) -> Result<ListCollectionsResponse> {
+        self.send(self.request(Method::GET, "/collections")).await
+    }
+
+    pub async fn get_collection(&self, name: &str) -> Result<GetCollectionResponse> {
+        self.send(self.request(Method::GET, &format!("/collections/{}", name)))
+            .await
+    }
+
+    pub async fn collection_stats(&self, name: &str) -> Result<CollectionStatsResponse> {
+        self.send(self.request(Method::GET, &format!("/collections/{}/stats", name)))
+            .await
+    }
+
+    pub async fn delete_collection(&self, name: &str) -> Result<DeleteCollectionResponse> {
+        self.send(self.request(Method::DELETE, &format!("/collections/{}", name)))
+            .await
+    }
+
+    // ---------- vectors ----------
+
+    /// Per-vector upsert: invalid vectors are reported in the response's
+    /// `results` (status 207) rather than failing the call.
+    pub async fn upsert(&self, collection: &str, req: &UpsertRequest) -> Result<UpsertResponse> {
+        let path = format!("/collections/{}/vectors/upsert", collection);
+        let resp = self.request(Method::POST, &path).json(req).send().await?;
+        // 207 carries a full body; only outright failures are errors.
+        if resp.status() == StatusCode::MULTI_STATUS {
+            return Ok(resp.json().await?);
+        }
+        decode(resp).await
+    }
+
+    /// All-or-nothing upsert (`?bulk=true`).
+    pub async fn bulk_upsert(
+        &self,
+        collection: &str,
+        req: &UpsertRequest,
+    ) -> Result<UpsertResponse> {
+        let path = format!("/collections/{}/vectors/upsert?bulk=true", collection);
+        self.send_json(Method::POST, &path, req).await
+    }
+
+    pub async fn delete_vector(&self, collection: &str, id: &str) -> Result<DeleteVectorResponse> {
+        let path = format!("/collections/{}/vectors/{}", collection, id);
+        self.send(self.request(Method::DELETE, &path)).await
+    }
+
+    // ---------- query ----------
+
+    /// A 204 (`empty_as_204`) comes back as an empty `QueryResponse`.
+    pub async fn query(&self, collection: &str, req: &QueryRequest) -> Result<QueryResponse> {
+        let path = format!("/collections/{}/query", collection);
+        let resp = self.request(Method::POST, &path).json(req).send().await?;
+        if resp.status() == StatusCode::NO_CONTENT {
+            return Ok(QueryResponse {
+                matches: Vec::new(),
+                exact: req.exact,
+                exact_fallback: false,
+                debug: None,
+                cursor: None,
+                metric: None,
+            });
+        }
+        decode(resp).await
+    }
+
+    pub async fn count(&self, collection: &str, req: &CountQueryRequest) -> Result<CountResponse> {
+        let path = format!("/collections/{}/query/count", collection);
+        self.send_json(Method::POST, &path, req).await
+    }
+
+    // ---------- plumbing ----------
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("x-api-key", &self.api_key)
+    }
+
+    async fn send_json<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(method, path).json(body)).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T> {
+        decode(req.send().await?).await
+    }
+}
+
+/// Decode a success body, or turn an error status into `Error::Api` with
+/// the server's error code and message.
+async fn decode<T: DeserializeOwned>(resp: Response) -> Result<T> {
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        let (code, message) = match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(e) => (e.error.code, e.error.message),
+            // Not from a handler, e.g. a malformed request body.
+            Err(_) => (String::new(), body),
+        };
+        return Err(Error::Api {
+            status,
+            code,
+            message,
+        });
+    }
+    Ok(resp.json().await?)
+}
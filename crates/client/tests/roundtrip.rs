@@ -0,0 +1,110 @@
+//! Drives a real server over TCP through the typed client.
+
+use std::collections::HashMap;
+
+use fastdb_client::models::{CreateCollectionRequest, QueryRequest, UpsertRequest, VectorData};
+use fastdb_client::{Client, Error, Metric, OutOfRange};
+use openvdb_server::{auth::Role, build_router, config::Config, state::AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+const API_KEY: &str = "test-key";
+
+async fn serve() -> (Client, TempDir) {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let config = Config {
+        data_dir: dir.path().to_path_buf(),
+        ..Config::default()
+    };
+    let keys = HashMap::from([(API_KEY.to_string(), Role::ReadWrite)]);
+    let state = AppState::load(config, keys).expect("load state");
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, build_router(state)).await.unwrap();
+    });
+    (Client::new(format!("http://{}", addr), API_KEY), dir)
+}
+
+fn collection(name: &str, dimension: usize) -> CreateCollectionRequest {
+    CreateCollectionRequest {
+        name: name.to_string(),
+        dimension,
+        default_filter: None,
+        metric: Some(Metric::Cosine),
+        value_range: None,
+        out_of_range: OutOfRange::Reject,
+        capacity: None,
+        mode: Default::default(),
+        zero_vectors: Default::default(),
+        dedup_threshold: None,
+        normalize: false,
+    }
+}
+
+#[tokio::test]
+async fn create_upsert_query() {
+    let (client, _dir) = serve().await;
+    assert_eq!(client.health().await.unwrap().status, "ok");
+
+    let created = client.create_collection(&collection("docs", 2)).await.unwrap();
+    assert_eq!(created.dimension, 2);
+
+    let upserted = client
+        .upsert(
+            "docs",
+            &UpsertRequest {
+                vectors: vec![
+                    VectorData {
+                        id: "a".into(),
+                        values: vec![1.0, 0.0],
+                        metadata: Some(json!({ "kind": "doc" })),
+                        if_version: None,
+                    },
+                    VectorData {
+                        id: "b".into(),
+                        values: vec![0.0, 1.0],
+                        metadata: None,
+                        if_version: None,
+                    },
+                ],
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(upserted.upserted, 2);
+
+    let resp = client
+        .query("docs", &QueryRequest::new(vec![1.0, 0.1], 1))
+        .await
+        .unwrap();
+    assert_eq!(resp.matches.len(), 1);
+    assert_eq!(resp.matches[0].id, "a");
+    assert_eq!(resp.matches[0].metadata, Some(json!({ "kind": "doc" })));
+
+    let stats = client.collection_stats("docs").await.unwrap();
+    assert_eq!(stats.vectors, 2);
+}
+
+#[tokio::test]
+async fn error_statuses_surface_as_api_errors() {
+    let (client, _dir) = serve().await;
+    match client.get_collection("missing").await {
+        Err(Error::Api {
+            status,
+            code,
+            message,
+        }) => {
+            assert_eq!(status.as_u16(), 404);
+            assert_eq!(code, "not_found");
+            assert!(message.contains("missing"));
+        }
+        other => panic!("expected a 404, got {:?}", other.map(|c| c.name)),
+    }
+
+    let bad_key = Client::new(client.base_url(), "nope");
+    assert!(matches!(
+        bad_key.list_collections().await,
+        Err(Error::Api { status, code, .. }) if status.as_u16() == 401 && code == "invalid_api_key"
+    ));
+}
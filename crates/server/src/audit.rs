@@ -0,0 +1,105 @@
+//! Access log for compliance audits, separate from the tracing output: one
+//! JSON line per data read or write, naming the tenant, collection,
+//! operation and how many ids it touched. Records go to a writer thread
+//! over a channel, so a request never waits on the disk.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::index::now_millis;
+
+#[derive(Serialize)]
+struct AuditRecord {
+    /// Milliseconds since the Unix epoch.
+    ts: u64,
+    tenant: String,
+    collection: String,
+    op: &'static str,
+    ids: usize,
+}
+
+pub struct AuditLog {
+    records: Sender<AuditRecord>,
+}
+
+impl AuditLog {
+    /// Append to `path` from a background thread, rotating the file to
+    /// `<path>.1` (replacing any older one) once it passes `max_bytes`. The
+    /// thread exits once the log is dropped, after writing what's queued.
+    pub fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = open_append(&path)?;
+        let (records, queue) = mpsc::channel();
+        thread::Builder::new()
+            .name("openvdb-audit".into())
+            .spawn(move || write_records(&path, file, max_bytes, queue))?;
+        Ok(Self { records })
+    }
+
+    /// Queue one record; `ids` is how many vectors the operation read or
+    /// wrote.
+    pub fn record(&self, tenant: &str, collection: &str, op: &'static str, ids: usize) {
+        // Only fails once the writer thread is gone, and it has logged why.
+        let _ = self.records.send(AuditRecord {
+            ts: now_millis(),
+            tenant: tenant.to_string(),
+            collection: collection.to_string(),
+            op,
+            ids,
+        });
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// `<path>.1`
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(".1");
+    rotated.into()
+}
+
+fn write_records(path: &Path, file: File, max_bytes: u64, queue: Receiver<AuditRecord>) {
+    let mut size = file.metadata().map_or(0, |m| m.len());
+    let mut out = BufWriter::new(file);
+    while let Ok(first) = queue.recv() {
+        // Write whatever queued up meanwhile, then flush once.
+        for record in std::iter::once(first).chain(queue.try_iter()) {
+            let mut line = serde_json::to_string(&record).expect("audit records serialize");
+            line.push('\n');
+            if size > 0 && size + line.len() as u64 > max_bytes {
+                let rotated = out
+                    .flush()
+                    .and_then(|_| fs::rename(path, rotated_path(path)))
+                    .and_then(|_| open_append(path));
+                match rotated {
+                    Ok(file) => {
+                        out = BufWriter::new(file);
+                        size = 0;
+                    }
+                    Err(e) => tracing::error!("failed to rotate audit log: {:?}", e),
+                }
+            }
+            if let Err(e) = out.write_all(line.as_bytes()) {
+                tracing::error!("failed to write audit log: {:?}", e);
+                continue;
+            }
+            size += line.len() as u64;
+        }
+        if let Err(e) = out.flush() {
+            tracing::error!("failed to flush audit log: {:?}", e);
+        }
+    }
+}
@@ -1,29 +1,124 @@
 use async_trait::async_trait;
 use axum::{
     extract::{FromRef, FromRequestParts},
-    http::{request::Parts, StatusCode},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
+use std::str::FromStr;
+
+use crate::error::ApiError;
 use crate::state::AppState;
 
+/// What a key may do, set per key in `OPENVDB_API_KEYS` (`key:role`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    /// Everything, including the admin-only endpoints.
+    Admin,
+    /// Create, write and delete, plus everything `ReadOnly` can. The role
+    /// of keys given without one.
+    #[default]
+    ReadWrite,
+    /// Query, list, get and stats only.
+    ReadOnly,
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "read_write" | "readwrite" | "rw" => Ok(Role::ReadWrite),
+            "read_only" | "readonly" | "ro" => Ok(Role::ReadOnly),
+            other => Err(format!("unknown role '{}'", other)),
+        }
+    }
+}
+
+/// A valid key: the tenant it names and the role it was given.
 #[allow(dead_code)]
-pub struct ApiKey(pub String);
+pub struct ApiKey(pub String, pub Role);
+
+/// `ApiKey` for handlers that write or delete; read-only keys get a 403,
+/// and every key a 503 while the server is in maintenance mode.
+pub struct WriteKey(pub String);
 
 #[derive(Debug)]
 pub enum AuthError {
     Missing,
     Invalid,
+    /// The tenant's data didn't finish loading in time; retry later.
+    Loading,
+    /// Not the admin key (or none is configured).
+    NotAdmin,
+    /// A read-only key on an endpoint that writes.
+    ReadOnly,
+    /// A write while the server is in maintenance mode.
+    Maintenance,
+    /// The key is over `Config::rate_limit_rps`; retry after this many
+    /// seconds.
+    RateLimited(u64),
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        let (status, code, msg) = match e {
+            AuthError::Missing => (
+                StatusCode::UNAUTHORIZED,
+                "missing_api_key",
+                "missing x-api-key header or bearer token",
+            ),
+            AuthError::Invalid => (StatusCode::UNAUTHORIZED, "invalid_api_key", "invalid API key"),
+            AuthError::Loading => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "tenant_loading",
+                "tenant data is still loading, retry shortly",
+            ),
+            AuthError::NotAdmin => (StatusCode::FORBIDDEN, "admin_required", "admin API key required"),
+            AuthError::ReadOnly => (StatusCode::FORBIDDEN, "read_only_key", "API key is read-only"),
+            AuthError::Maintenance => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "maintenance_mode",
+                "server is in maintenance mode, writes are disabled",
+            ),
+            AuthError::RateLimited(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                "API key is over its request rate limit",
+            ),
+        };
+        ApiError::with_code(status, code, msg)
+    }
 }
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let status = StatusCode::UNAUTHORIZED;
-        let msg = match self {
-            AuthError::Missing => "missing x-api-key header",
-            AuthError::Invalid => "invalid API key",
+        let retry_after = match self {
+            AuthError::RateLimited(secs) => Some(secs),
+            _ => None,
         };
-        (status, msg).into_response()
+        let mut resp = ApiError::from(self).into_response();
+        if let Some(secs) = retry_after {
+            resp.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        resp
+    }
+}
+
+/// The key from `x-api-key`, or else from an `Authorization: Bearer`
+/// header. `Missing` only when neither is usable.
+pub(crate) fn presented_key(headers: &HeaderMap) -> Result<&str, AuthError> {
+    if let Some(value) = headers.get("x-api-key") {
+        return value.to_str().map_err(|_| AuthError::Invalid);
     }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(AuthError::Missing)
 }
 
 #[async_trait]
@@ -32,22 +127,65 @@ where
     S: Send + Sync,
     AppState: FromRef<S>,
 {
-	type Rejection = AuthError;
+    type Rejection = AuthError;
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let app_state = AppState::from_ref(state);
 
-        let header_value = parts
-            .headers
-            .get("x-api-key")
-            .ok_or(AuthError::Missing)?;
+        let key = presented_key(&parts.headers)?.to_string();
+
+        let role = *app_state.api_keys.get(&key).ok_or(AuthError::Invalid)?;
+        app_state
+            .take_request_token(&key)
+            .map_err(|wait| AuthError::RateLimited(wait.as_secs_f64().ceil().max(1.0) as u64))?;
 
-        let key_str = header_value.to_str().map_err(|_| AuthError::Invalid)?;
-        let key = key_str.to_string();
+        // Every handler sees the tenant's collections, even after eviction.
+        app_state.touch_tenant(&key);
+        if !app_state.ensure_tenant_loaded(&key).await {
+            return Err(AuthError::Loading);
+        }
+
+        Ok(ApiKey(key, role))
+    }
+}
 
-        if !app_state.api_keys.contains(&key) {
-            return Err(AuthError::Invalid);
+#[async_trait]
+impl<S> FromRequestParts<S> for WriteKey
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = AuthError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match ApiKey::from_request_parts(parts, state).await? {
+            ApiKey(_, Role::ReadOnly) => Err(AuthError::ReadOnly),
+            _ if AppState::from_ref(state).in_maintenance() => Err(AuthError::Maintenance),
+            ApiKey(tenant, _) => Ok(WriteKey(tenant)),
         }
+    }
+}
 
-        Ok(ApiKey(key))
+/// Caller presented `Config::admin_api_key` or a key with `Role::Admin`.
+/// Not tied to a tenant.
+pub struct AdminKey;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminKey
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = AuthError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = AppState::from_ref(state);
+
+        let key = presented_key(&parts.headers)?;
+
+        if app_state.api_keys.get(key) == Some(&Role::Admin) {
+            return Ok(AdminKey);
+        }
+        match &app_state.config.admin_api_key {
+            Some(admin) if admin == key => Ok(AdminKey),
+            _ => Err(AuthError::NotAdmin),
+        }
     }
 }
@@ -0,0 +1,227 @@
+//! Built-in load generator behind `POST /admin/benchmark`: upserts random
+//! vectors into a throwaway index, queries it, and reports throughput and
+//! latency percentiles. With `bulk_batch` the same vectors are also loaded
+//! into a second index through `StagedBatch` + `merge`, the `?bulk=true`
+//! path. The indexes live only for the run; they are never registered with
+//! a tenant or written to the WAL.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::index::{CollectionConfig, EfBounds, InMemoryIndex, StagedBatch};
+use crate::models::{BenchmarkPhase, BenchmarkRequest, BenchmarkResponse};
+
+/// Largest `vectors` / `queries` a benchmark may ask for.
+pub const MAX_OPERATIONS: usize = 1_000_000;
+/// Largest `dimension` a benchmark may ask for.
+pub const MAX_DIMENSION: usize = 4096;
+
+/// At most one benchmark runs at a time; this holds its cancel flag.
+#[derive(Default)]
+pub struct BenchmarkSlot {
+    running: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl BenchmarkSlot {
+    /// Claim the slot, or `None` while another benchmark holds it.
+    pub fn start(self: &Arc<Self>) -> Option<BenchmarkRun> {
+        let mut running = self.running.lock().unwrap();
+        if running.is_some() {
+            return None;
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        *running = Some(cancel.clone());
+        Some(BenchmarkRun {
+            slot: self.clone(),
+            cancel,
+        })
+    }
+
+    /// Ask the running benchmark to stop. False if none is running.
+    pub fn cancel(&self) -> bool {
+        match &*self.running.lock().unwrap() {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Claim on the `BenchmarkSlot`. Dropping it (including when the client
+/// disconnects mid-run) cancels the benchmark and frees the slot.
+pub struct BenchmarkRun {
+    slot: Arc<BenchmarkSlot>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl BenchmarkRun {
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+}
+
+impl Drop for BenchmarkRun {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.slot.running.lock().unwrap().take();
+    }
+}
+
+/// Check `req` against the limits above.
+pub fn validate(req: &BenchmarkRequest) -> Result<(), String> {
+    if req.dimension == 0 || req.dimension > MAX_DIMENSION {
+        return Err(format!("dimension must be between 1 and {}", MAX_DIMENSION));
+    }
+    if req.vectors == 0 || req.vectors > MAX_OPERATIONS {
+        return Err(format!("vectors must be between 1 and {}", MAX_OPERATIONS));
+    }
+    if req.queries > MAX_OPERATIONS {
+        return Err(format!("queries must be at most {}", MAX_OPERATIONS));
+    }
+    if req.top_k == 0 {
+        return Err("top_k must be greater than 0".into());
+    }
+    if req.bulk_batch == Some(0) {
+        return Err("bulk_batch must be greater than 0".into());
+    }
+    Ok(())
+}
+
+/// Run the benchmark to completion or until `cancel` is set. Blocking and
+/// CPU-heavy: call it on the index pool.
+pub fn run(req: &BenchmarkRequest, cancel: &AtomicBool) -> BenchmarkResponse {
+    let mut rng = XorShift(SEED);
+    let mut index = InMemoryIndex::new(req.dimension);
+    let mut cancelled = false;
+
+    let mut latencies = Vec::with_capacity(req.vectors);
+    let started = Instant::now();
+    for i in 0..req.vectors {
+        if cancel.load(Ordering::Relaxed) {
+            cancelled = true;
+            break;
+        }
+        let values = rng.vector(req.dimension);
+        let op = Instant::now();
+        if let Err(e) = index.upsert(format!("bench-{}", i), values, None) {
+            tracing::warn!("benchmark upsert failed: {}", e);
+        }
+        latencies.push(op.elapsed());
+    }
+    let upserts = phase(&mut latencies, started.elapsed());
+
+    let bulk_upserts = match req.bulk_batch {
+        Some(batch_size) if !cancelled => {
+            let (bulk, stopped) = bulk_phase(req, batch_size, cancel);
+            cancelled = stopped;
+            Some(bulk)
+        }
+        _ => None,
+    };
+
+    latencies.clear();
+    let started = Instant::now();
+    for _ in 0..req.queries {
+        if cancelled || cancel.load(Ordering::Relaxed) {
+            cancelled = true;
+            break;
+        }
+        let query = rng.vector(req.dimension);
+        let op = Instant::now();
+        if let Err(e) = index.query(&query, req.top_k, None, None, EfBounds::default(), false) {
+            tracing::warn!("benchmark query failed: {}", e);
+        }
+        latencies.push(op.elapsed());
+    }
+    let queries = phase(&mut latencies, started.elapsed());
+
+    BenchmarkResponse {
+        dimension: req.dimension,
+        upserts,
+        bulk_upserts,
+        queries,
+        cancelled,
+    }
+}
+
+/// Stage and merge the vectors `run` upserts one by one, `batch_size` at a
+/// time, into a fresh index. Returns the phase and whether it was cancelled.
+fn bulk_phase(
+    req: &BenchmarkRequest,
+    batch_size: usize,
+    cancel: &AtomicBool,
+) -> (BenchmarkPhase, bool) {
+    // Same seed as `run`: the same vectors in the same order.
+    let mut rng = XorShift(SEED);
+    let mut index = InMemoryIndex::new(req.dimension);
+    let mut latencies = Vec::with_capacity(req.vectors.div_ceil(batch_size));
+    let mut loaded = 0;
+
+    let started = Instant::now();
+    while loaded < req.vectors {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let n = batch_size.min(req.vectors - loaded);
+        let op = Instant::now();
+        let mut batch = StagedBatch::new(req.dimension, CollectionConfig::default());
+        for i in loaded..loaded + n {
+            if let Err(e) = batch.push(format!("bench-{}", i), rng.vector(req.dimension), None) {
+                tracing::warn!("benchmark bulk upsert failed: {}", e);
+            }
+        }
+        if let Err(e) = index.merge(batch, false) {
+            tracing::warn!("benchmark bulk merge failed: {}", e);
+        }
+        latencies.push(op.elapsed());
+        loaded += n;
+    }
+    let elapsed = started.elapsed();
+
+    let mut bulk = phase(&mut latencies, elapsed);
+    bulk.operations = loaded;
+    bulk.ops_per_sec = loaded as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    (bulk, loaded < req.vectors)
+}
+
+fn phase(latencies: &mut [Duration], elapsed: Duration) -> BenchmarkPhase {
+    if latencies.is_empty() {
+        return BenchmarkPhase::default();
+    }
+    latencies.sort_unstable();
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let percentile = |p: f64| {
+        let rank = ((latencies.len() as f64 * p).ceil() as usize).clamp(1, latencies.len());
+        ms(latencies[rank - 1])
+    };
+    BenchmarkPhase {
+        operations: latencies.len(),
+        ops_per_sec: latencies.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        p50_ms: percentile(0.50),
+        p95_ms: percentile(0.95),
+        p99_ms: percentile(0.99),
+        max_ms: ms(latencies[latencies.len() - 1]),
+    }
+}
+
+/// Small deterministic generator, so runs are comparable across builds.
+struct XorShift(u64);
+
+const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+impl XorShift {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        // Top 24 bits mapped to [-1, 1).
+        (self.0 >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+
+    fn vector(&mut self, dim: usize) -> Vec<f32> {
+        (0..dim).map(|_| self.next_f32()).collect()
+    }
+}
@@ -0,0 +1,402 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::index::{EfBounds, Metric};
+
+/// Server-wide settings, resolved once at startup and shared via `AppState`.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Metric of collections created without one (`OPENVDB_DEFAULT_METRIC`:
+    /// `cosine`, `normalized_cosine`, `l2` or `dot`).
+    pub default_metric: Metric,
+    /// Directory holding the WAL and snapshot files, created at startup if
+    /// missing (`OPENVDB_DATA_DIR`, relative to the working directory unless
+    /// absolute).
+    pub data_dir: PathBuf,
+    /// Use `Hnsw::parallel_insert` for bulk upserts (`OPENVDB_PARALLEL_INSERT`).
+    pub parallel_insert: bool,
+    /// Smallest bulk batch worth the rayon fan-out
+    /// (`OPENVDB_PARALLEL_INSERT_MIN_BATCH`).
+    pub parallel_insert_min_batch: usize,
+    /// Round reported query scores to this many decimal places
+    /// (`OPENVDB_SCORE_DECIMALS`). Presentation only; unset = full f32.
+    pub score_decimals: Option<u32>,
+    /// Log WAL replay progress every this many entries
+    /// (`OPENVDB_WAL_REPLAY_LOG_EVERY`, 0 = summary only).
+    pub wal_replay_log_every: usize,
+    /// Without a snapshot on disk, take one once this many WAL entries have
+    /// been written (`OPENVDB_FIRST_SNAPSHOT_AFTER`, 0 = never).
+    pub first_snapshot_after: u64,
+    /// Upper bound on an `exact` query's scan, in milliseconds
+    /// (`OPENVDB_QUERY_TIMEOUT_MS`).
+    pub query_timeout_ms: u64,
+    /// Bounds on the HNSW search breadth queries get when they don't set
+    /// `ef_search` (`OPENVDB_EF_SEARCH_MIN` / `OPENVDB_EF_SEARCH_MAX`), see
+    /// `EfBounds::adaptive`.
+    pub ef_search_min: usize,
+    pub ef_search_max: usize,
+    /// Largest `top_k` a query may ask for (`OPENVDB_MAX_TOP_K`).
+    pub max_top_k: usize,
+    /// Log a warning for queries whose search takes longer than this many
+    /// milliseconds (`OPENVDB_SLOW_QUERY_MS`, unset = never).
+    pub slow_query_ms: Option<u64>,
+    /// Evict a tenant's collections from memory after this many seconds
+    /// without requests (`OPENVDB_TENANT_IDLE_EVICT_SECS`, unset = never).
+    pub tenant_idle_evict_secs: Option<u64>,
+    /// Load tenants on their first request instead of at startup
+    /// (`OPENVDB_LAZY_TENANT_LOAD`).
+    pub lazy_tenant_load: bool,
+    /// How long a request waits for its tenant to load before getting a 503
+    /// (`OPENVDB_TENANT_LOAD_TIMEOUT_MS`).
+    pub tenant_load_timeout_ms: u64,
+    /// When WAL appends are fsynced (`OPENVDB_WAL_SYNC`: `always`,
+    /// `interval` or `os`).
+    pub wal_sync: WalSync,
+    /// fsync period under `WalSync::Interval`
+    /// (`OPENVDB_WAL_SYNC_INTERVAL_MS`).
+    pub wal_sync_interval_ms: u64,
+    /// Refuse to start when a WAL line other than a torn final one fails
+    /// its checksum, instead of skipping it (`OPENVDB_WAL_STRICT`). Lazily
+    /// loaded tenants are checked when they load, and only logged.
+    pub wal_strict: bool,
+    /// Once the active WAL grows past this many bytes, seal it as the next
+    /// numbered segment (`wal.0000.jsonl`, `wal.0001.jsonl`, ...) and start
+    /// a fresh one (`OPENVDB_WAL_SEGMENT_BYTES`, unset = one unbounded WAL).
+    pub wal_segment_bytes: Option<u64>,
+    /// Reject upserts whose metadata serializes to more than this many bytes
+    /// (`OPENVDB_MAX_METADATA_BYTES`, unset = unlimited).
+    pub max_metadata_bytes: Option<usize>,
+    /// Most collections one tenant may create
+    /// (`OPENVDB_MAX_COLLECTIONS_PER_TENANT`, unset = unlimited).
+    pub max_collections_per_tenant: Option<usize>,
+    /// Most vectors one collection may hold, pending ones included
+    /// (`OPENVDB_MAX_VECTORS_PER_COLLECTION`, unset = unlimited). Only
+    /// enforced on writes: WAL replay always loads everything.
+    pub max_vectors_per_collection: Option<usize>,
+    /// Largest `capacity` a collection may be created or reindexed with
+    /// (`OPENVDB_MAX_CAPACITY`). The HNSW graph preallocates for its
+    /// capacity, so an unbounded one could exhaust memory.
+    pub max_capacity: usize,
+    /// `Cache-Control: max-age` for query responses
+    /// (`OPENVDB_QUERY_CACHE_MAX_AGE_SECS`, unset = `no-cache`, i.e.
+    /// revalidate via `ETag` on every use).
+    pub query_cache_max_age_secs: Option<u64>,
+    /// Keep vector values in memory-mapped files under `data_dir/vectors`
+    /// instead of on the heap (`OPENVDB_MMAP_VECTORS`).
+    pub mmap_vectors: bool,
+    /// Most queries allowed to run at once against one collection; more get
+    /// a 503 (`OPENVDB_COLLECTION_QUERY_CONCURRENCY`, unset = unlimited).
+    pub collection_query_concurrency: Option<usize>,
+    /// Most upsert requests one tenant may have in flight at once; more get
+    /// a 429, so a bulk load can't starve other tenants' writes
+    /// (`OPENVDB_TENANT_UPSERT_CONCURRENCY`, unset = unlimited).
+    pub tenant_upsert_concurrency: Option<usize>,
+    /// Oldest cached result an `eventual` query may be answered with
+    /// (`OPENVDB_EVENTUAL_MAX_STALENESS_MS`, 0 = always search).
+    pub eventual_max_staleness_ms: u64,
+    /// Keep the WAL (and the snapshot it applies to) for this long after a
+    /// snapshot instead of truncating it, so a corrupt snapshot can be
+    /// recovered from (`OPENVDB_WAL_RETENTION_SECS`, unset = truncate).
+    pub wal_retention_secs: Option<u64>,
+    /// Take a snapshot this often in the background, bounding how much WAL
+    /// a restart replays (`OPENVDB_SNAPSHOT_INTERVAL_SECS`, unset = only
+    /// on request).
+    pub snapshot_interval_secs: Option<u64>,
+    /// Past snapshots to keep for `?snapshot=` time-travel queries
+    /// (`OPENVDB_SNAPSHOT_HISTORY`, default 0 = none).
+    pub snapshot_history: usize,
+    /// gzip snapshots at this level, 0 (fastest) to 9 (smallest), as
+    /// `snapshot.json.gz` (`OPENVDB_SNAPSHOT_GZIP_LEVEL`, unset = plain
+    /// `snapshot.json`).
+    pub snapshot_gzip_level: Option<u32>,
+    /// On shutdown, how long in-flight requests may run before they are
+    /// cut off (`OPENVDB_DRAIN_TIMEOUT_SECS`).
+    pub drain_timeout_secs: u64,
+    /// Write a snapshot once requests have drained on shutdown, so the next
+    /// start has no WAL to replay (`OPENVDB_SNAPSHOT_ON_SHUTDOWN`).
+    pub snapshot_on_shutdown: bool,
+    /// Threads in the pool for CPU-heavy index work: reindex builds, tenant
+    /// loads and parallel bulk inserts (`OPENVDB_INDEX_THREADS`, default one
+    /// per core). The pool is separate from the tokio runtime, whose workers
+    /// keep serving requests (and running queries) while it is busy, so the
+    /// two together can use up to `tokio workers + index_threads` cores.
+    pub index_threads: usize,
+    /// Rebuild a collection in the background once deletes and overwrites
+    /// leave more dead HNSW nodes than half its live ones
+    /// (`OPENVDB_AUTO_COMPACT`).
+    pub auto_compact: bool,
+    /// Defer automatic compaction to this daily UTC window, e.g.
+    /// `02:00-04:00` (`OPENVDB_COMPACTION_WINDOW`, unset = compact as soon
+    /// as a write crosses the threshold).
+    pub compaction_window: Option<CompactionWindow>,
+    /// Key for operator-only endpoints such as `POST /admin/benchmark`
+    /// (`OPENVDB_ADMIN_API_KEY`, unset = those endpoints are disabled).
+    pub admin_api_key: Option<String>,
+    /// Access log of every data read and write, for audits
+    /// (`OPENVDB_AUDIT_LOG`, unset = no audit log).
+    pub audit_log: Option<PathBuf>,
+    /// Rotate the audit log to `<path>.1` once it grows past this
+    /// (`OPENVDB_AUDIT_LOG_MAX_BYTES`).
+    pub audit_log_max_bytes: u64,
+    /// Sustained requests per second allowed per API key; more get a 429
+    /// (`OPENVDB_RATE_LIMIT_RPS`, unset = unlimited).
+    pub rate_limit_rps: Option<f64>,
+    /// Requests a key may make at once after being idle
+    /// (`OPENVDB_RATE_LIMIT_BURST`, unset = one second's worth).
+    pub rate_limit_burst: Option<f64>,
+}
+
+/// Durability of WAL appends. Writes always reach the OS page cache and
+/// survive a process crash; what survives a power loss depends on the policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WalSync {
+    /// fsync after every append (a bulk upsert's lines are synced once).
+    Always,
+    /// fsync every `wal_sync_interval_ms` in the background, if anything
+    /// was appended meanwhile.
+    Interval,
+    /// Leave flushing to the OS, or to `POST /admin/sync`.
+    #[default]
+    Os,
+}
+
+impl FromStr for WalSync {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "always" => Ok(WalSync::Always),
+            "interval" => Ok(WalSync::Interval),
+            // `never` is the old name of `os`.
+            "os" | "never" => Ok(WalSync::Os),
+            other => Err(format!("unknown WAL sync policy {:?}", other)),
+        }
+    }
+}
+
+/// Daily time window in UTC, `HH:MM-HH:MM`. It may wrap past midnight
+/// (`22:00-02:00`); equal ends cover the whole day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompactionWindow {
+    /// Minutes after midnight, start inclusive, end exclusive.
+    pub start: u16,
+    pub end: u16,
+}
+
+impl CompactionWindow {
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start || minute_of_day < self.end
+        }
+    }
+
+    pub fn contains_now(&self) -> bool {
+        self.contains(utc_minute_of_day())
+    }
+}
+
+/// Minutes since midnight UTC.
+pub fn utc_minute_of_day() -> u16 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    ((secs / 60) % (24 * 60)) as u16
+}
+
+impl FromStr for CompactionWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let minute = |hhmm: &str| -> Option<u16> {
+            let (h, m) = hhmm.trim().split_once(':')?;
+            let (h, m): (u16, u16) = (h.parse().ok()?, m.parse().ok()?);
+            (h < 24 && m < 60).then_some(h * 60 + m)
+        };
+        let parsed = s
+            .split_once('-')
+            .and_then(|(start, end)| Some((minute(start)?, minute(end)?)));
+        match parsed {
+            Some((start, end)) => Ok(CompactionWindow { start, end }),
+            None => Err(format!("expected HH:MM-HH:MM, got {:?}", s)),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_metric: Metric::default(),
+            data_dir: PathBuf::from("data"),
+            parallel_insert: true,
+            parallel_insert_min_batch: 1024,
+            score_decimals: None,
+            wal_replay_log_every: 100_000,
+            first_snapshot_after: 1000,
+            query_timeout_ms: 5000,
+            ef_search_min: EfBounds::default().min,
+            ef_search_max: EfBounds::default().max,
+            max_top_k: 10_000,
+            slow_query_ms: None,
+            tenant_idle_evict_secs: None,
+            lazy_tenant_load: false,
+            tenant_load_timeout_ms: 30_000,
+            wal_sync: WalSync::default(),
+            wal_sync_interval_ms: 1000,
+            wal_strict: false,
+            wal_segment_bytes: None,
+            max_metadata_bytes: None,
+            max_collections_per_tenant: None,
+            max_vectors_per_collection: None,
+            max_capacity: 10_000_000,
+            query_cache_max_age_secs: None,
+            mmap_vectors: false,
+            collection_query_concurrency: None,
+            tenant_upsert_concurrency: None,
+            eventual_max_staleness_ms: 1000,
+            wal_retention_secs: None,
+            snapshot_interval_secs: None,
+            snapshot_history: 0,
+            snapshot_gzip_level: None,
+            drain_timeout_secs: 30,
+            snapshot_on_shutdown: true,
+            index_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            auto_compact: true,
+            compaction_window: None,
+            admin_api_key: None,
+            audit_log: None,
+            audit_log_max_bytes: 64 * 1024 * 1024,
+            rate_limit_rps: None,
+            rate_limit_burst: None,
+        }
+    }
+}
+
+impl Config {
+    /// Settings from `OPENVDB_*` variables. Most invalid values are logged
+    /// and replaced by the default; an invalid `OPENVDB_DEFAULT_METRIC` is
+    /// an error, as it would silently change every new collection.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let defaults = Self::default();
+        let default_metric = match std::env::var("OPENVDB_DEFAULT_METRIC") {
+            Ok(raw) => raw
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid OPENVDB_DEFAULT_METRIC: {}", e))?,
+            Err(_) => defaults.default_metric,
+        };
+        Ok(Self {
+            default_metric,
+            data_dir: env_or("OPENVDB_DATA_DIR", defaults.data_dir),
+            parallel_insert: env_or("OPENVDB_PARALLEL_INSERT", defaults.parallel_insert),
+            parallel_insert_min_batch: env_or(
+                "OPENVDB_PARALLEL_INSERT_MIN_BATCH",
+                defaults.parallel_insert_min_batch,
+            ),
+            score_decimals: env_opt("OPENVDB_SCORE_DECIMALS"),
+            wal_replay_log_every: env_or(
+                "OPENVDB_WAL_REPLAY_LOG_EVERY",
+                defaults.wal_replay_log_every,
+            ),
+            first_snapshot_after: env_or(
+                "OPENVDB_FIRST_SNAPSHOT_AFTER",
+                defaults.first_snapshot_after,
+            ),
+            query_timeout_ms: env_or("OPENVDB_QUERY_TIMEOUT_MS", defaults.query_timeout_ms),
+            ef_search_min: env_or("OPENVDB_EF_SEARCH_MIN", defaults.ef_search_min),
+            ef_search_max: env_or("OPENVDB_EF_SEARCH_MAX", defaults.ef_search_max),
+            max_top_k: env_or("OPENVDB_MAX_TOP_K", defaults.max_top_k),
+            slow_query_ms: env_opt("OPENVDB_SLOW_QUERY_MS"),
+            tenant_idle_evict_secs: env_opt("OPENVDB_TENANT_IDLE_EVICT_SECS"),
+            lazy_tenant_load: env_or("OPENVDB_LAZY_TENANT_LOAD", defaults.lazy_tenant_load),
+            tenant_load_timeout_ms: env_or(
+                "OPENVDB_TENANT_LOAD_TIMEOUT_MS",
+                defaults.tenant_load_timeout_ms,
+            ),
+            wal_sync: env_or("OPENVDB_WAL_SYNC", defaults.wal_sync),
+            wal_sync_interval_ms: env_or(
+                "OPENVDB_WAL_SYNC_INTERVAL_MS",
+                defaults.wal_sync_interval_ms,
+            ),
+            wal_strict: env_or("OPENVDB_WAL_STRICT", defaults.wal_strict),
+            wal_segment_bytes: env_opt("OPENVDB_WAL_SEGMENT_BYTES").filter(|&bytes| bytes > 0),
+            max_metadata_bytes: env_opt("OPENVDB_MAX_METADATA_BYTES"),
+            max_collections_per_tenant: env_opt("OPENVDB_MAX_COLLECTIONS_PER_TENANT"),
+            max_vectors_per_collection: env_opt("OPENVDB_MAX_VECTORS_PER_COLLECTION"),
+            max_capacity: env_or("OPENVDB_MAX_CAPACITY", defaults.max_capacity),
+            query_cache_max_age_secs: env_opt("OPENVDB_QUERY_CACHE_MAX_AGE_SECS"),
+            mmap_vectors: env_or("OPENVDB_MMAP_VECTORS", defaults.mmap_vectors),
+            collection_query_concurrency: env_opt("OPENVDB_COLLECTION_QUERY_CONCURRENCY"),
+            tenant_upsert_concurrency: env_opt("OPENVDB_TENANT_UPSERT_CONCURRENCY"),
+            eventual_max_staleness_ms: env_or(
+                "OPENVDB_EVENTUAL_MAX_STALENESS_MS",
+                defaults.eventual_max_staleness_ms,
+            ),
+            wal_retention_secs: env_opt("OPENVDB_WAL_RETENTION_SECS"),
+            snapshot_interval_secs: env_opt("OPENVDB_SNAPSHOT_INTERVAL_SECS")
+                .filter(|&secs| secs > 0),
+            snapshot_history: env_or("OPENVDB_SNAPSHOT_HISTORY", defaults.snapshot_history),
+            snapshot_gzip_level: env_opt("OPENVDB_SNAPSHOT_GZIP_LEVEL"),
+            drain_timeout_secs: env_or("OPENVDB_DRAIN_TIMEOUT_SECS", defaults.drain_timeout_secs),
+            snapshot_on_shutdown: env_or(
+                "OPENVDB_SNAPSHOT_ON_SHUTDOWN",
+                defaults.snapshot_on_shutdown,
+            ),
+            index_threads: env_or("OPENVDB_INDEX_THREADS", defaults.index_threads).max(1),
+            auto_compact: env_or("OPENVDB_AUTO_COMPACT", defaults.auto_compact),
+            compaction_window: env_opt("OPENVDB_COMPACTION_WINDOW"),
+            admin_api_key: env_opt("OPENVDB_ADMIN_API_KEY"),
+            audit_log: env_opt("OPENVDB_AUDIT_LOG"),
+            audit_log_max_bytes: env_or(
+                "OPENVDB_AUDIT_LOG_MAX_BYTES",
+                defaults.audit_log_max_bytes,
+            ),
+            rate_limit_rps: env_opt("OPENVDB_RATE_LIMIT_RPS"),
+            rate_limit_burst: env_opt("OPENVDB_RATE_LIMIT_BURST"),
+        })
+    }
+
+    pub fn ef_bounds(&self) -> EfBounds {
+        EfBounds {
+            min: self.ef_search_min,
+            max: self.ef_search_max,
+        }
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+
+    pub fn wal_retention(&self) -> Option<Duration> {
+        self.wal_retention_secs.map(Duration::from_secs)
+    }
+}
+
+/// Like `env_or` for settings that are off unless set.
+fn env_opt<T: FromStr>(name: &str) -> Option<T> {
+    let raw = std::env::var(name).ok()?;
+    match raw.trim().parse() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            tracing::warn!("ignoring invalid {}={:?}", name, raw);
+            None
+        }
+    }
+}
+
+/// Parse `name` from the environment, falling back to `default` when unset
+/// or unparsable (the latter is logged).
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(raw) => match raw.trim().parse() {
+            Ok(v) => v,
+            Err(_) => {
+                tracing::warn!("ignoring invalid {}={:?}", name, raw);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,76 @@
+//! `ApiError`, the error half of every handler's result. It renders as an
+//! `ErrorResponse` JSON body, so clients can branch on `error.code` instead
+//! of parsing messages.
+
+use std::fmt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::models::{ErrorBody, ErrorResponse};
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    /// An error whose `code` follows from `status`, see `code_for`.
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self::with_code(status, code_for(status), message)
+    }
+
+    pub fn with_code(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Status and message, for per-item results in batch responses.
+    pub fn into_parts(self) -> (StatusCode, String) {
+        (self.status, self.message)
+    }
+}
+
+/// The `code` of errors with no more specific one.
+fn code_for(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::TOO_MANY_REQUESTS => "too_many_requests",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        StatusCode::GATEWAY_TIMEOUT => "timeout",
+        StatusCode::INSUFFICIENT_STORAGE => "insufficient_storage",
+        s if s.is_server_error() => "internal",
+        _ => "error",
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            error: ErrorBody {
+                code: self.code.to_string(),
+                message: self.message,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
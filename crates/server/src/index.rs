@@ -1,119 +1,1019 @@
 use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use hnsw_rs::prelude::{DistCosine, Hnsw};
+use hnsw_rs::prelude::{DistCosine, DistL2, Distance, Hnsw, Neighbour};
+
+use crate::vector_store::MmapSlab;
+
+pub use fastdb_types::collection::{
+    CollectionConfig, Metric, OutOfRange, ValueRange, WriteMode, ZeroVectors,
+};
+
+/// `Distance` impl dispatching on the collection's metric, so every
+/// collection shares one `Hnsw` type.
+#[derive(Clone, Copy)]
+pub struct MetricDistance(Metric);
+
+thread_local! {
+    // Distance evaluations on this thread, read around a search to report
+    // how many nodes it visited (hnsw_rs doesn't expose that itself).
+    static DISTANCE_EVALS: Cell<usize> = const { Cell::new(0) };
+}
+
+impl Distance<f32> for MetricDistance {
+    fn eval(&self, va: &[f32], vb: &[f32]) -> f32 {
+        DISTANCE_EVALS.with(|n| n.set(n.get() + 1));
+        match self.0 {
+            Metric::Cosine => DistCosine.eval(va, vb),
+            // Both sides are unit length; clamp float error so hnsw_rs never
+            // sees a negative distance.
+            Metric::NormalizedCosine => {
+                let dot: f32 = va.iter().zip(vb).map(|(a, b)| a * b).sum();
+                (1.0 - dot).max(0.0)
+            }
+            Metric::L2 => DistL2.eval(va, vb),
+            // hnsw_rs requires distances >= 0, so map the inner product onto
+            // (0, inf), decreasing; `score` inverts this.
+            Metric::Dot => {
+                let dot: f32 = va.iter().zip(vb).map(|(a, b)| a * b).sum();
+                if dot >= 0.0 { 1.0 / (1.0 + dot) } else { 1.0 - dot }
+            }
+        }
+    }
+}
+
+impl MetricDistance {
+    /// Query score (higher is better) for a distance returned by `eval`.
+    fn score(self, dist: f32) -> f32 {
+        match self.0 {
+            Metric::Cosine | Metric::NormalizedCosine => 1.0 - dist,
+            Metric::L2 => -dist,
+            Metric::Dot if dist <= 1.0 => 1.0 / dist - 1.0,
+            Metric::Dot => 1.0 - dist,
+        }
+    }
+}
 
 pub struct InMemoryIndex {
     dim: usize,
+    config: CollectionConfig,
     // Ground-truth store for vectors + metadata
     vectors: HashMap<String, IndexedVector>,
     // HNSW index over the same vectors
-    hnsw: Hnsw<'static, f32, DistCosine>,
+    hnsw: Hnsw<'static, f32, MetricDistance>,
     // External string id -> internal numeric id used by HNSW
     id_to_data_id: HashMap<String, usize>,
     // Internal numeric id -> external string id
     data_id_to_id: HashMap<usize, String>,
     // Next internal id to allocate
     next_data_id: usize,
+    // HNSW nodes no longer mapped to a vector (deleted or overwritten),
+    // see `needs_compaction`
+    dead_nodes: usize,
+    // Serialized size of all stored metadata, see `metadata_size`
+    metadata_bytes: usize,
+    // Stored vectors without values, see `StoredValues::Pending`
+    pending: usize,
+    // Norms of the vectors upserted since load, see `normalized_fraction`
+    norms: NormStats,
+    // Changes on every write, see `generation`
+    generation: u64,
+    // Backing file for vector values instead of the heap, see `enable_mmap`
+    mapped: Option<MmapSlab>,
+    // Queries currently running against this collection
+    queries_in_flight: AtomicUsize,
+    // Token of the running reindex and the writes made since it started,
+    // see `begin_reindex`
+    reindex_delta: Option<(u64, Vec<DeltaOp>)>,
+}
+
+/// A write made while a reindex builds its new index, replayed onto that
+/// index before it is swapped in.
+enum DeltaOp {
+    Upsert {
+        id: String,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        times: Timestamps,
+        version: u64,
+    },
+    UpdateMetadata {
+        id: String,
+        metadata: Option<Value>,
+        at: u64,
+    },
+    Delete(String),
+}
+
+/// How many upserted vectors arrived unit-length, see
+/// `InMemoryIndex::normalized_fraction`.
+#[derive(Clone, Copy, Default)]
+struct NormStats {
+    upserted: u64,
+    unit: u64,
+}
+
+/// Largest distance from 1.0 at which an upserted vector's norm still
+/// counts as normalized.
+const UNIT_NORM_TOLERANCE: f32 = 1e-3;
+
+impl NormStats {
+    fn record(&mut self, norm: f32) {
+        self.upserted += 1;
+        if (norm - 1.0).abs() <= UNIT_NORM_TOLERANCE {
+            self.unit += 1;
+        }
+    }
+}
+
+/// HNSW links per node (`M`).
+const MAX_NB_CONNECTION: usize = 16;
+
+/// HNSW nodes a collection may hold when its config doesn't say.
+const DEFAULT_CAPACITY: usize = 1_000_000;
+
+/// Dead HNSW nodes, as a fraction of live ones, past which a collection
+/// should be compacted.
+const COMPACT_DEAD_RATIO: f64 = 0.5;
+
+/// Vectors `estimate_query` tests against a filter.
+const SELECTIVITY_SAMPLE: usize = 1000;
+
+/// Bounds on the default HNSW search breadth, see `EfBounds::adaptive`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EfBounds {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Default for EfBounds {
+    fn default() -> Self {
+        Self { min: 64, max: 512 }
+    }
+}
+
+impl EfBounds {
+    /// Search breadth for a collection of `live` vectors when the query
+    /// sets none: `8 * log2(live)`, clamped to `[min, max]`. Recall at a
+    /// fixed breadth drops as the graph grows deeper, so larger collections
+    /// search wider: 64 up to 256 vectors, 160 at a million.
+    pub fn adaptive(self, live: usize) -> usize {
+        let scaled = (8.0 * (live.max(1) as f64).log2()).ceil() as usize;
+        scaled.clamp(self.min, self.max.max(self.min))
+    }
+}
+
+/// HNSW candidates to fetch and search breadth for a top-`top_k` query
+/// against `live` vectors. Filtered queries oversample heavily because some
+/// candidates will be filtered out. `ef_search` overrides the adaptive
+/// breadth from `bounds` (never below `top_k`); HNSW searches at least
+/// `knbn` wide, so the candidate count is capped to it.
+fn search_params(
+    top_k: usize,
+    filtered: bool,
+    ef_search: Option<usize>,
+    live: usize,
+    bounds: EfBounds,
+) -> (usize, usize) {
+    let knbn = if filtered { top_k.saturating_mul(8) } else { top_k.saturating_mul(4) };
+    match ef_search {
+        Some(ef) => {
+            let ef = ef.max(top_k);
+            (knbn.min(ef), ef)
+        }
+        None if filtered => (knbn, knbn.max(bounds.adaptive(live))),
+        None => (knbn, top_k.max(bounds.adaptive(live))),
+    }
+}
+
+/// Output of `InMemoryIndex::estimate_query`.
+pub struct QueryEstimate {
+    /// Vectors the search would compare against the query (an upper bound
+    /// for HNSW).
+    pub candidates: usize,
+    /// Fraction of sampled vectors matching the filter; `None` without one.
+    pub selectivity: Option<f64>,
+    /// Matches the query is expected to return, at most `top_k`.
+    pub expected_matches: usize,
+}
+
+/// Second operand of `InMemoryIndex::distance`.
+pub enum DistanceTo<'a> {
+    Id(&'a str),
+    Vector(&'a [f32]),
+}
+
+/// The vectors of a collection as of `InMemoryIndex::begin_reindex`, to
+/// build a fresh index from without holding any lock.
+pub struct ReindexJob {
+    token: u64,
+    dim: usize,
+    config: CollectionConfig,
+    vectors: Vec<ExportedVector>,
+}
+
+impl ReindexJob {
+    pub fn token(&self) -> u64 {
+        self.token
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// Build the new index. This is the slow part of a reindex.
+    pub fn build(self) -> RebuiltIndex {
+        let mut index = InMemoryIndex::with_config(self.dim, self.config);
+        for v in self.vectors {
+            // Already validated and in stored form.
+            let _ = index.restore(v.id, v.values, v.metadata, v.times, v.version);
+        }
+        RebuiltIndex {
+            token: self.token,
+            index,
+        }
+    }
+}
+
+/// Output of `ReindexJob::build`, for `InMemoryIndex::finish_reindex`.
+pub struct RebuiltIndex {
+    token: u64,
+    pub index: InMemoryIndex,
+}
+
+/// Source of index generations. Process-wide, so a collection deleted and
+/// recreated never repeats a generation its predecessor reported, and
+/// seeded from the clock (in nanoseconds) so a restarted server doesn't
+/// either.
+static NEXT_GENERATION: OnceLock<AtomicU64> = OnceLock::new();
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION
+        .get_or_init(|| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64);
+            AtomicU64::new(now)
+        })
+        .fetch_add(1, Ordering::Relaxed)
 }
 
 struct IndexedVector {
-    values: Vec<f32>,
+    values: StoredValues,
     metadata: Option<Value>,
+    times: Timestamps,
+    // 1 on creation, +1 on every overwrite; see `check_version`
+    version: u64,
+    // L2 norm of the stored values, computed once per write (0 if pending)
+    norm: f32,
+}
+
+impl IndexedVector {
+    fn is_pending(&self) -> bool {
+        matches!(self.values, StoredValues::Pending)
+    }
+}
+
+/// When a vector was first and last upserted, in ms since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timestamps {
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+enum StoredValues {
+    Heap(Vec<f32>),
+    /// Slot in the collection's `MmapSlab` (its HNSW data id).
+    Mapped(usize),
+    /// Upserted without values: stored for id and filter lookups but not
+    /// searchable until a later upsert supplies them.
+    Pending,
+}
+
+/// A query slot held on a collection; see `InMemoryIndex::try_acquire_query`.
+pub struct QueryPermit<'a>(&'a AtomicUsize);
+
+impl Drop for QueryPermit<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Outcome of a query against one collection.
+#[derive(Default)]
+pub struct SearchResult {
+    /// Best-first matches, at most `top_k`.
+    pub points: Vec<ScoredPoint>,
+    /// True when an exact scan (rather than HNSW) produced `points`.
+    pub exact: bool,
+    /// True when HNSW could not be trusted and an exact scan served the query.
+    pub exact_fallback: bool,
+    /// Vectors compared against the query: HNSW nodes visited, or vectors
+    /// scored by an exact scan.
+    pub visited: usize,
+    /// Search breadth HNSW ran with; `None` for exact scans.
+    pub ef: Option<usize>,
 }
 
 pub struct ScoredPoint {
     pub id: String,
-    /// similarity score ~ 1 - cosine_distance (higher is better)
+    /// Query score under the collection metric (higher is better)
     pub score: f32,
+    /// Raw distance the score was derived from (lower is closer)
+    pub distance: f32,
     pub metadata: Option<Value>,
+    /// Stored values, when the search was asked to include them.
+    pub values: Option<Vec<f32>>,
 }
 
 impl InMemoryIndex {
     pub fn new(dim: usize) -> Self {
+        Self::with_config(dim, CollectionConfig::default())
+    }
+
+    pub fn with_config(dim: usize, config: CollectionConfig) -> Self {
         // Reasonable defaults; we can tune later
-        let max_nb_connection = 16;   // M
-        let max_elements = 1_000_000; // capacity hint
+        let max_nb_connection = MAX_NB_CONNECTION;
+        let max_elements = config.capacity.unwrap_or(DEFAULT_CAPACITY);
         let max_layer = 16;
         let ef_construction = 200;
 
-        let hnsw = Hnsw::<f32, DistCosine>::new(
+        let hnsw = Hnsw::<f32, MetricDistance>::new(
             max_nb_connection,
             max_elements,
             max_layer,
             ef_construction,
-            DistCosine {},
+            MetricDistance(config.metric),
         );
 
         Self {
             dim,
+            config,
             vectors: HashMap::new(),
             hnsw,
             id_to_data_id: HashMap::new(),
             data_id_to_id: HashMap::new(),
             next_data_id: 0,
+            dead_nodes: 0,
+            metadata_bytes: 0,
+            pending: 0,
+            norms: NormStats::default(),
+            generation: next_generation(),
+            mapped: None,
+            queries_in_flight: AtomicUsize::new(0),
+            reindex_delta: None,
         }
     }
 
+    /// Claim one of `limit` concurrent query slots on this collection, or
+    /// `None` if all are taken. The slot is released when the permit drops.
+    pub fn try_acquire_query(&self, limit: usize) -> Option<QueryPermit<'_>> {
+        self.queries_in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < limit).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| QueryPermit(&self.queries_in_flight))
+    }
+
+    /// Vector dimension; 0 while a collection created with `dimension: 0`
+    /// hasn't seen its first upsert yet.
     pub fn dimension(&self) -> usize {
         self.dim
     }
 
+    /// Fix the dimension of a collection created without one. Setting the
+    /// dimension it already has is a no-op.
+    pub fn set_dimension(&mut self, dim: usize) -> Result<(), String> {
+        if dim == 0 || (self.dim != 0 && self.dim != dim) {
+            return Err(format!(
+                "cannot set dimension {} on a collection with dimension {}",
+                dim, self.dim
+            ));
+        }
+        self.dim = dim;
+        self.generation = next_generation();
+        Ok(())
+    }
+
+    /// Opaque version of the collection's contents: equal generations mean
+    /// no write happened in between.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn config(&self) -> &CollectionConfig {
+        &self.config
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.config.metric
+    }
+
+    /// Index/metric label reported by stats endpoints.
+    pub fn index_type(&self) -> &'static str {
+        match self.config.metric {
+            Metric::Cosine => "hnsw_cosine",
+            Metric::NormalizedCosine => "hnsw_normalized_cosine",
+            Metric::L2 => "hnsw_l2",
+            Metric::Dot => "hnsw_dot",
+        }
+    }
+
+    /// Query score for a distance from `distance`.
+    pub fn score_for_distance(&self, dist: f32) -> f32 {
+        MetricDistance(self.config.metric).score(dist)
+    }
+
     pub fn upsert(
         &mut self,
         id: String,
         values: Vec<f32>,
         metadata: Option<Value>,
     ) -> Result<(), String> {
-        if values.len() != self.dim {
+        self.upsert_at(id, values, metadata, now_millis())
+    }
+
+    /// `upsert` recorded as written at `at` (ms since the Unix epoch), for
+    /// callers that log the write time, and for replaying those logs.
+    ///
+    /// Empty `values` store a pending vector: metadata only, not in HNSW,
+    /// until the id is upserted again with values.
+    pub fn upsert_at(
+        &mut self,
+        id: String,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        at: u64,
+    ) -> Result<(), String> {
+        if let Some(norm) = self.store(id, values, metadata, at)? {
+            self.norms.record(norm);
+        }
+        Ok(())
+    }
+
+    /// `upsert_at` without counting towards `normalized_fraction`. Returns
+    /// the norm of `values` as given, `None` for a pending vector.
+    fn store(
+        &mut self,
+        id: String,
+        mut values: Vec<f32>,
+        metadata: Option<Value>,
+        at: u64,
+    ) -> Result<Option<f32>, String> {
+        if values.is_empty() {
+            self.store_vector(id, None, values, metadata, at);
+            return Ok(None);
+        }
+        // An unset dimension is inferred from the first vector.
+        let dim = if self.dim == 0 { values.len() } else { self.dim };
+        let norm = prepare_values(dim, &self.config, &mut values)?;
+        self.dim = dim;
+        self.insert_validated(id, values, metadata, at);
+        Ok(Some(norm))
+    }
+
+    /// Load a vector from a snapshot, keeping its recorded timestamps and
+    /// version.
+    pub fn restore(
+        &mut self,
+        id: String,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        times: Timestamps,
+        version: u64,
+    ) -> Result<(), String> {
+        self.store(id.clone(), values, metadata, times.updated_at)?;
+        if let Some(v) = self.vectors.get_mut(&id) {
+            v.times.created_at = times.created_at;
+            v.version = version;
+        }
+        Ok(())
+    }
+
+    pub fn timestamps(&self, id: &str) -> Option<Timestamps> {
+        self.vectors.get(id).map(|v| v.times)
+    }
+
+    /// Current version of `id`, `None` if it isn't stored.
+    pub fn version(&self, id: &str) -> Option<u64> {
+        self.vectors.get(id).map(|v| v.version)
+    }
+
+    /// Overwrite the version of a stored vector, for WAL replay.
+    pub fn set_version(&mut self, id: &str, version: u64) {
+        if let Some(v) = self.vectors.get_mut(id) {
+            v.version = version;
+        }
+    }
+
+    /// Refuse `values` for `id` if they lie within the collection's
+    /// `dedup_threshold` of a vector stored under another id, by the raw
+    /// metric distance queries report. The check is a single approximate
+    /// search, so a near-duplicate that HNSW misses gets in.
+    pub fn check_duplicate(&self, id: &str, values: &[f32]) -> Result<(), String> {
+        let Some(threshold) = self.config.dedup_threshold else {
+            return Ok(());
+        };
+        if values.is_empty() || values.len() != self.dim {
+            return Ok(());
+        }
+        // Two results, as `id` itself may be the nearest.
+        let Ok(nearest) = self.query(values, 2, None, None, EfBounds::default(), false) else {
+            return Ok(());
+        };
+        match nearest.points.into_iter().find(|p| p.id != id) {
+            Some(p) if p.distance <= threshold => Err(format!(
+                "vector '{}' is a near-duplicate of '{}' (distance {} <= dedup_threshold {})",
+                id, p.id, p.distance, threshold
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse a write to an existing id (pending ones included) when the
+    /// collection is `insert_only`.
+    pub fn check_insert(&self, id: &str) -> Result<(), String> {
+        if self.config.mode == WriteMode::InsertOnly && self.contains(id) {
             return Err(format!(
-                "expected vector of dimension {}, got {}",
-                self.dim,
-                values.len()
+                "vector '{}' already exists and the collection is insert-only",
+                id
+            ));
+        }
+        Ok(())
+    }
+
+    /// Optimistic-locking check for a write to `id`: `expected` must be its
+    /// current version, or 0 for an id that isn't stored yet.
+    pub fn check_version(&self, id: &str, expected: u64) -> Result<(), String> {
+        let current = self.version(id).unwrap_or(0);
+        if current == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "version conflict on '{}': expected {}, found {}",
+                id, expected, current
+            ))
+        }
+    }
+
+    /// Merge a batch staged outside the lock into the live index.
+    ///
+    /// Every vector in the batch was already validated by `StagedBatch::push`,
+    /// so this only does the bookkeeping + HNSW insertion. With `parallel`
+    /// the HNSW inserts go through `Hnsw::parallel_insert` (rayon); internal
+    /// ids are still assigned sequentially first, so the id maps end up the
+    /// same as with one-by-one insertion.
+    pub fn merge(&mut self, batch: StagedBatch, parallel: bool) -> Result<usize, String> {
+        if self.dim == 0 && batch.dim != 0 {
+            self.set_dimension(batch.dim)?;
+        }
+        // A batch staged without a dimension holds only pending vectors.
+        if batch.dim != self.dim && batch.dim != 0 {
+            return Err(format!(
+                "batch was staged for dimension {}, collection has dimension {}",
+                batch.dim, self.dim
             ));
         }
+        if batch.config.metric != self.config.metric
+            || batch.config.value_range != self.config.value_range
+        {
+            return Err("batch was staged for a different collection config".into());
+        }
 
-        // Basic sanity: avoid zero vector, which is degenerate for cosine
-        let norm_sq: f32 = values.iter().map(|x| x * x).sum();
-        if norm_sq == 0.0 {
-            return Err("vector norm must be > 0".into());
+        let count = batch.vectors.len();
+        self.norms.upserted += batch.norms.upserted;
+        self.norms.unit += batch.norms.unit;
+        if !parallel {
+            for (id, values, metadata) in batch.vectors {
+                self.insert_validated(id, values, metadata, batch.written_at);
+            }
+            return Ok(count);
         }
 
-        let iv = IndexedVector { values, metadata };
+        let data_ids: Vec<Option<usize>> = batch
+            .vectors
+            .iter()
+            .map(|(id, values, _)| (!values.is_empty()).then(|| self.assign_data_id(id)))
+            .collect();
+        {
+            let items: Vec<(&Vec<f32>, usize)> = batch
+                .vectors
+                .iter()
+                .zip(&data_ids)
+                .filter_map(|((_, values, _), data_id)| Some((values, (*data_id)?)))
+                .collect();
+            self.hnsw.parallel_insert(&items);
+        }
 
-        // Get or assign an internal id for HNSW
-        let data_id = if let Some(&existing) = self.id_to_data_id.get(&id) {
-            existing
-        } else {
-            let d = self.next_data_id;
-            self.next_data_id += 1;
-            self.id_to_data_id.insert(id.clone(), d);
-            self.data_id_to_id.insert(d, id.clone());
-            d
-        };
+        // Later duplicates of an id overwrite earlier ones, as in the serial path.
+        for ((id, values, metadata), data_id) in batch.vectors.into_iter().zip(data_ids) {
+            self.store_vector(id, data_id, values, metadata, batch.written_at);
+        }
+        Ok(count)
+    }
+
+    /// Assign a fresh internal HNSW id to an external id. On an overwrite
+    /// the old node is unmapped, so searches skip it until a reindex drops
+    /// it, instead of scoring the id by its previous values.
+    fn assign_data_id(&mut self, id: &str) -> usize {
+        let d = self.next_data_id;
+        self.next_data_id += 1;
+        if let Some(old) = self.id_to_data_id.insert(id.to_string(), d) {
+            self.data_id_to_id.remove(&old);
+            self.dead_nodes += 1;
+        }
+        self.data_id_to_id.insert(d, id.to_string());
+        d
+    }
+
+    fn insert_validated(
+        &mut self,
+        id: String,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        at: u64,
+    ) {
+        if values.is_empty() {
+            self.store_vector(id, None, values, metadata, at);
+            return;
+        }
+        let data_id = self.assign_data_id(&id);
 
         // Insert into HNSW: NOTE the tuple argument (&[f32], usize)
-        let vec_ref: &[f32] = &iv.values;
+        let vec_ref: &[f32] = &values;
         self.hnsw.insert((vec_ref, data_id));
 
         // Store/overwrite in ground-truth map
-        self.vectors.insert(id, iv);
+        self.store_vector(id, Some(data_id), values, metadata, at);
+    }
 
+    /// Store a vector whose HNSW node is `data_id`, or a pending one (no
+    /// node, empty `values`) for `None`.
+    fn store_vector(
+        &mut self,
+        id: String,
+        data_id: Option<usize>,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        at: u64,
+    ) {
+        match data_id {
+            // Make sure queries map the node back to `id`, even if a pending
+            // version unlinked it in between.
+            Some(d) => {
+                if let Some(old) = self.id_to_data_id.insert(id.clone(), d)
+                    && old != d
+                {
+                    // A later duplicate within a parallel batch; the node
+                    // was already counted dead when it was assigned.
+                    self.data_id_to_id.remove(&old);
+                }
+                self.data_id_to_id.insert(d, id.clone());
+            }
+            // The node of a previous version must not turn up in searches.
+            None => {
+                if let Some(d) = self.id_to_data_id.remove(&id) {
+                    self.data_id_to_id.remove(&d);
+                    self.dead_nodes += 1;
+                }
+            }
+        }
+        self.generation = next_generation();
+        self.metadata_bytes += metadata_size(&metadata);
+        // Overwrites keep the original creation time.
+        let previous = self.vectors.get(&id);
+        let times = Timestamps {
+            created_at: previous.map_or(at, |v| v.times.created_at),
+            updated_at: at,
+        };
+        let version = previous.map_or(1, |v| v.version + 1);
+        if let Some((_, delta)) = &mut self.reindex_delta {
+            delta.push(DeltaOp::Upsert {
+                id: id.clone(),
+                values: values.clone(),
+                metadata: metadata.clone(),
+                times,
+                version,
+            });
+        }
+        let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let values = match data_id {
+            Some(d) => self.place_values(d, values),
+            None => {
+                self.pending += 1;
+                StoredValues::Pending
+            }
+        };
+        let stored = IndexedVector {
+            values,
+            metadata,
+            times,
+            version,
+            norm,
+        };
+        if let Some(old) = self.vectors.insert(id, stored) {
+            self.metadata_bytes -= metadata_size(&old.metadata);
+            if matches!(old.values, StoredValues::Pending) {
+                self.pending -= 1;
+            }
+        }
+    }
+
+    /// Move `values` into the mmap store if the collection has one. A failed
+    /// write keeps them on the heap rather than losing the vector.
+    fn place_values(&mut self, data_id: usize, values: Vec<f32>) -> StoredValues {
+        let Some(slab) = self.mapped.as_mut() else {
+            return StoredValues::Heap(values);
+        };
+        match slab.write(data_id, &values) {
+            Ok(()) => StoredValues::Mapped(data_id),
+            Err(e) => {
+                tracing::error!(
+                    "failed to write vector to {}, keeping it on the heap: {:?}",
+                    slab.path().display(),
+                    e
+                );
+                StoredValues::Heap(values)
+            }
+        }
+    }
+
+    /// Keep vector values in a memory-mapped file created under `dir`
+    /// instead of on the heap, moving over the ones already stored. See
+    /// `vector_store` for the tradeoffs.
+    pub fn enable_mmap(&mut self, dir: &Path) -> io::Result<()> {
+        if self.mapped.is_some() {
+            return Ok(());
+        }
+        self.mapped = Some(MmapSlab::create_in(dir)?);
+
+        let ids: Vec<String> = self.vectors.keys().cloned().collect();
+        for id in ids {
+            // Pending vectors have no node and nothing to move.
+            let Some(&data_id) = self.id_to_data_id.get(&id) else {
+                continue;
+            };
+            let Some(iv) = self.vectors.get_mut(&id) else {
+                continue;
+            };
+            let StoredValues::Heap(values) =
+                std::mem::replace(&mut iv.values, StoredValues::Mapped(data_id))
+            else {
+                continue;
+            };
+            let placed = self.place_values(data_id, values);
+            if let Some(iv) = self.vectors.get_mut(&id) {
+                iv.values = placed;
+            }
+        }
         Ok(())
     }
 
+    /// Values of a stored vector, wherever they live.
+    fn values_of<'a>(&'a self, iv: &'a IndexedVector) -> &'a [f32] {
+        match &iv.values {
+            StoredValues::Heap(values) => values,
+            StoredValues::Pending => &[],
+            StoredValues::Mapped(slot) => self
+                .mapped
+                .as_ref()
+                .expect("mapped values imply a slab")
+                .read(*slot),
+        }
+    }
+
+    /// Start rebuilding the HNSW graph from scratch: copy out every vector
+    /// and start logging writes. The caller builds the returned job without
+    /// holding the collection, then hands the result to `finish_reindex`.
+    /// `capacity` replaces the collection's capacity in the new index.
+    pub fn begin_reindex(&mut self, capacity: Option<usize>) -> Result<ReindexJob, String> {
+        if self.reindex_delta.is_some() {
+            return Err("a reindex of this collection is already running".into());
+        }
+        let mut config = self.config.clone();
+        if let Some(capacity) = capacity {
+            config.capacity = Some(capacity);
+        }
+        let token = next_generation();
+        self.reindex_delta = Some((token, Vec::new()));
+        Ok(ReindexJob {
+            token,
+            dim: self.dim,
+            config,
+            vectors: self.export_vectors().vectors,
+        })
+    }
+
+    /// Replay the writes logged since `begin_reindex` onto `rebuilt` and
+    /// replace this index with it. Returns how many writes were replayed.
+    pub fn finish_reindex(&mut self, rebuilt: RebuiltIndex) -> Result<usize, String> {
+        let delta = match self.reindex_delta.take() {
+            Some((token, delta)) if token == rebuilt.token => delta,
+            other => {
+                self.reindex_delta = other;
+                return Err("collection was replaced while it was being reindexed".into());
+            }
+        };
+
+        let mut index = rebuilt.index;
+        let replayed = delta.len();
+        for op in delta {
+            match op {
+                DeltaOp::Upsert {
+                    id,
+                    values,
+                    metadata,
+                    times,
+                    version,
+                } => {
+                    let _ = index.restore(id, values, metadata, times, version);
+                }
+                DeltaOp::UpdateMetadata { id, metadata, at } => {
+                    index.update_metadata_at(&id, metadata, at);
+                }
+                DeltaOp::Delete(id) => {
+                    index.delete(&id);
+                }
+            }
+        }
+        index.norms = self.norms;
+        *self = index;
+        Ok(replayed)
+    }
+
+    /// Nodes in the HNSW graph, live or dead.
+    pub fn index_nodes(&self) -> usize {
+        self.hnsw.get_nb_point()
+    }
+
+    /// Most HNSW nodes the collection may hold, see `check_capacity`.
+    pub fn capacity(&self) -> usize {
+        self.config.capacity.unwrap_or(DEFAULT_CAPACITY)
+    }
+
+    /// Record a capacity a reindex applied, when replaying the WAL.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.config.capacity = Some(capacity);
+    }
+
+    /// Refuse `nodes` more HNSW nodes once they would exceed the capacity
+    /// the graph was sized for. Every upsert with values adds a node, even
+    /// an overwrite, so a full collection may be freed by compacting it.
+    pub fn check_capacity(&self, nodes: usize) -> Result<(), String> {
+        let used = self.hnsw.get_nb_point();
+        if used + nodes <= self.capacity() {
+            return Ok(());
+        }
+        Err(format!(
+            "collection at capacity ({} of {} index nodes, {} dead); \
+             compact it or reindex with a larger capacity",
+            used,
+            self.capacity(),
+            self.dead_nodes
+        ))
+    }
+
+    /// Fraction of the vectors upserted since the collection was loaded
+    /// whose norm, as sent, was within `UNIT_NORM_TOLERANCE` of 1. `None`
+    /// for non-cosine metrics, where unit length doesn't matter, and before
+    /// the first upsert.
+    pub fn normalized_fraction(&self) -> Option<f64> {
+        (self.config.metric.is_cosine() && self.norms.upserted > 0)
+            .then(|| self.norms.unit as f64 / self.norms.upserted as f64)
+    }
+
+    /// HNSW nodes left behind by deletes and overwrites, see `begin_reindex`.
+    pub fn dead_nodes(&self) -> usize {
+        self.dead_nodes
+    }
+
+    /// True once dead nodes exceed `COMPACT_DEAD_RATIO` of the live ones.
+    pub fn needs_compaction(&self) -> bool {
+        self.dead_nodes > 0
+            && self.dead_nodes as f64 > self.id_to_data_id.len() as f64 * COMPACT_DEAD_RATIO
+    }
+
+    pub fn reindex_running(&self) -> bool {
+        self.reindex_delta.is_some()
+    }
+
+    /// Stop logging writes for a reindex that won't finish.
+    pub fn abort_reindex(&mut self, token: u64) {
+        if self.reindex_delta.as_ref().is_some_and(|(t, _)| *t == token) {
+            self.reindex_delta = None;
+        }
+    }
+
+    /// Total serialized size of the metadata of every stored vector.
+    pub fn metadata_bytes(&self) -> usize {
+        self.metadata_bytes
+    }
+
+    /// Rough RAM held by the collection, for capacity planning:
+    ///
+    /// - values: `dim` f32s per stored vector (pending ones have none),
+    ///   counted even when mmapped, as the page cache holds them;
+    /// - metadata: its serialized size, see `metadata_bytes`;
+    /// - HNSW: per node, its own copy of the values plus `M` neighbour ids,
+    ///   dead nodes included, so the figure drops after compaction.
+    ///
+    /// Map and allocator overheads and the upper graph layers are left out.
+    pub fn approx_memory_bytes(&self) -> usize {
+        let f32s = size_of::<f32>() * self.dim;
+        let nodes = self.hnsw.get_nb_point();
+        (self.vectors.len() - self.pending) * f32s
+            + self.metadata_bytes
+            + nodes * (f32s + MAX_NB_CONNECTION * size_of::<usize>())
+    }
+
+    /// Stored vectors still waiting for their values.
+    pub fn pending_count(&self) -> usize {
+        self.pending
+    }
+
+    /// Replace the metadata of `id` without touching its values or HNSW
+    /// node. Counts as a write: the version and `updated_at` move on.
+    /// Returns false if there is no such vector.
+    pub fn update_metadata(&mut self, id: &str, metadata: Option<Value>) -> bool {
+        self.update_metadata_at(id, metadata, now_millis())
+    }
+
+    /// `update_metadata` recorded as written at `at`, see `upsert_at`.
+    pub fn update_metadata_at(&mut self, id: &str, metadata: Option<Value>, at: u64) -> bool {
+        let Some(stored) = self.vectors.get_mut(id) else {
+            return false;
+        };
+        self.metadata_bytes -= metadata_size(&stored.metadata);
+        self.metadata_bytes += metadata_size(&metadata);
+        if let Some((_, delta)) = &mut self.reindex_delta {
+            delta.push(DeltaOp::UpdateMetadata {
+                id: id.to_string(),
+                metadata: metadata.clone(),
+                at,
+            });
+        }
+        stored.metadata = metadata;
+        stored.times.updated_at = at;
+        stored.version += 1;
+        self.generation = next_generation();
+        true
+    }
+
     pub fn delete(&mut self, id: &str) -> bool {
-        let removed = self.vectors.remove(id).is_some();
-        if removed {
-            if let Some(data_id) = self.id_to_data_id.remove(id) {
-                self.data_id_to_id.remove(&data_id);
-                // HNSW has no hard delete; we just stop exposing this id.
+        let removed = match self.vectors.remove(id) {
+            Some(old) => {
+                self.metadata_bytes -= metadata_size(&old.metadata);
+                if matches!(old.values, StoredValues::Pending) {
+                    self.pending -= 1;
+                }
+                self.generation = next_generation();
+                true
             }
+            None => false,
+        };
+        if removed && let Some((_, delta)) = &mut self.reindex_delta {
+            delta.push(DeltaOp::Delete(id.to_string()));
+        }
+        if removed && let Some(data_id) = self.id_to_data_id.remove(id) {
+            self.data_id_to_id.remove(&data_id);
+            // HNSW has no hard delete; we just stop exposing this id until
+            // a reindex drops the node.
+            self.dead_nodes += 1;
         }
         removed
     }
 
-    pub fn query(&self, query: &[f32], top_k: usize) -> Result<Vec<ScoredPoint>, String> {
+    /// Approximate top-`top_k` search. `ef_search` overrides the HNSW search
+    /// breadth; `None` picks one from `top_k` and the live vector count,
+    /// within `bounds`. With `min_score`, only matches scoring at least that
+    /// are returned, still at most `top_k`. `include_values` copies each
+    /// match's stored values into it.
+    pub fn query(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        min_score: Option<f32>,
+        ef_search: Option<usize>,
+        bounds: EfBounds,
+        include_values: bool,
+    ) -> Result<SearchResult, String> {
+        if self.dim == 0 {
+            // Dimension not inferred yet, so there are no vectors.
+            return Ok(SearchResult::default());
+        }
         if query.len() != self.dim {
             return Err(format!(
                 "expected query vector of dimension {}, got {}",
@@ -122,61 +1022,116 @@ impl InMemoryIndex {
             ));
         }
 
+        // A collection-level default filter scopes every query.
+        if self.config.default_filter.is_some() {
+            return self.query_with_filter(
+                query,
+                top_k,
+                &Map::new(),
+                min_score,
+                ef_search,
+                bounds,
+                include_values,
+            );
+        }
+
         if top_k == 0 || self.vectors.is_empty() {
-            return Ok(Vec::new());
+            return Ok(SearchResult::default());
         }
 
         let qnorm_sq: f32 = query.iter().map(|x| x * x).sum();
         if qnorm_sq == 0.0 {
             return Err("query vector norm must be > 0".into());
         }
+        let query = &*self.prepare_query(query);
 
-        // ef (search breadth) – can be tuned
-        let ef = top_k.max(64);
-        // Slight oversampling
-        let knbn = top_k * 4;
-        let neighbours = self.hnsw.search(query, knbn, ef);
+        let (mut knbn, mut ef) =
+            search_params(top_k, false, ef_search, self.vectors.len(), bounds);
+        let nodes = self.hnsw.get_nb_point();
+        let mut visited = 0;
+        loop {
+            let Some((neighbours, round_visited)) = self.hnsw_search(query, knbn, ef) else {
+                return Ok(self
+                    .exact_search(query, top_k, None, None, include_values)
+                    .unwrap_or_default());
+            };
+            visited += round_visited;
 
-        let mut scored = Vec::new();
+            let mut scored = Vec::new();
+            // Neighbours are best-first: past the first one under
+            // `min_score`, widening can't find more matches.
+            let mut below_min = false;
 
-        for n in neighbours {
-            let data_id = n.d_id;
-            let dist = n.distance;
+            for n in neighbours {
+                let data_id = n.d_id;
+                let dist = n.distance;
 
-            // Map back to external id; skip IDs we’ve “deleted”
-            let Some(external_id) = self.data_id_to_id.get(&data_id) else {
-                continue;
-            };
-            let Some(stored) = self.vectors.get(external_id) else {
-                continue;
-            };
+                // Map back to external id; skip IDs we’ve “deleted”
+                let Some(external_id) = self.data_id_to_id.get(&data_id) else {
+                    continue;
+                };
+                let Some(stored) = self.vectors.get(external_id) else {
+                    continue;
+                };
 
-            // DistCosine returns a distance; convert to similarity-ish score
-            let score = 1.0 - dist;
+                // The index returns a distance; convert to similarity-ish score
+                let score = self.score_for_distance(dist);
+                if min_score.is_some_and(|min| score < min) {
+                    below_min = true;
+                    break;
+                }
 
-            scored.push(ScoredPoint {
-                id: external_id.clone(),
-                score,
-                metadata: stored.metadata.clone(),
-            });
+                scored.push(ScoredPoint {
+                    id: external_id.clone(),
+                    score,
+                    distance: dist,
+                    metadata: stored.metadata.clone(),
+                    values: include_values.then(|| self.values_of(stored).to_vec()),
+                });
 
-            if scored.len() == top_k {
-                break;
+                if scored.len() == top_k {
+                    break;
+                }
             }
-        }
 
-        Ok(scored)
+            // Overwritten and deleted ids leave stale nodes in the graph, so
+            // over-fetch until `top_k` live matches are found, a candidate
+            // falls below `min_score`, or the graph is exhausted.
+            if scored.len() == top_k || below_min || knbn >= nodes {
+                return Ok(SearchResult {
+                    points: scored,
+                    exact: false,
+                    exact_fallback: false,
+                    visited,
+                    ef: Some(ef),
+                });
+            }
+            knbn = knbn.saturating_mul(4);
+            ef = ef.max(knbn);
+        }
     }
 
     /// Query with an additional metadata filter.
     ///
     /// `filter` must be a JSON object; each key/value must exactly match the vector's metadata.
+    /// The collection's `default_filter`, if any, is ANDed in. `min_score`,
+    /// `ef_search`, `bounds` and `include_values` are as in `query`;
+    /// `ef_search` and `bounds` set the breadth of the first search round.
+    #[allow(clippy::too_many_arguments)]
     pub fn query_with_filter(
         &self,
         query: &[f32],
         top_k: usize,
         filter: &Map<String, Value>,
-    ) -> Result<Vec<ScoredPoint>, String> {
+        min_score: Option<f32>,
+        ef_search: Option<usize>,
+        bounds: EfBounds,
+        include_values: bool,
+    ) -> Result<SearchResult, String> {
+        if self.dim == 0 {
+            // Dimension not inferred yet, so there are no vectors.
+            return Ok(SearchResult::default());
+        }
         if query.len() != self.dim {
             return Err(format!(
                 "expected query vector of dimension {}, got {}",
@@ -186,81 +1141,759 @@ impl InMemoryIndex {
         }
 
         if top_k == 0 || self.vectors.is_empty() {
-            return Ok(Vec::new());
+            return Ok(SearchResult::default());
         }
 
         let qnorm_sq: f32 = query.iter().map(|x| x * x).sum();
         if qnorm_sq == 0.0 {
             return Err("query vector norm must be > 0".into());
         }
+        let query = &*self.prepare_query(query);
 
-        // Oversample heavily because some candidates will be filtered out.
-        let knbn = (top_k * 8).max(top_k * 2);
-        let ef = knbn.max(64);
+        let Some(filter) = self.effective_filter(filter) else {
+            // Query filter contradicts the default filter: nothing can match.
+            return Ok(SearchResult::default());
+        };
+        let filter = &filter;
 
-        let neighbours = self.hnsw.search(query, knbn, ef);
+        let (mut knbn, mut ef) =
+            search_params(top_k, true, ef_search, self.vectors.len(), bounds);
+        let nodes = self.hnsw.get_nb_point();
+        let mut visited = 0;
+        loop {
+            let Some((neighbours, round_visited)) = self.hnsw_search(query, knbn, ef) else {
+                return Ok(self
+                    .exact_search(query, top_k, Some(filter), None, include_values)
+                    .unwrap_or_default());
+            };
+            visited += round_visited;
 
-        let mut scored = Vec::new();
+            let mut scored = Vec::new();
+            // Neighbours are best-first: past the first one under
+            // `min_score`, widening can't find more matches.
+            let mut below_min = false;
 
-        for n in neighbours {
-            let data_id = n.d_id;
-            let dist = n.distance;
+            for n in neighbours {
+                let data_id = n.d_id;
+                let dist = n.distance;
 
-            let Some(external_id) = self.data_id_to_id.get(&data_id) else {
-                continue;
-            };
-            let Some(stored) = self.vectors.get(external_id) else {
-                continue;
-            };
+                let Some(external_id) = self.data_id_to_id.get(&data_id) else {
+                    continue;
+                };
+                let Some(stored) = self.vectors.get(external_id) else {
+                    continue;
+                };
 
-            if !metadata_matches_filter(&stored.metadata, filter) {
-                continue;
+                let score = self.score_for_distance(dist);
+                if min_score.is_some_and(|min| score < min) {
+                    below_min = true;
+                    break;
+                }
+
+                if !metadata_matches_filter(&stored.metadata, filter) {
+                    continue;
+                }
+
+                scored.push(ScoredPoint {
+                    id: external_id.clone(),
+                    score,
+                    distance: dist,
+                    metadata: stored.metadata.clone(),
+                    values: include_values.then(|| self.values_of(stored).to_vec()),
+                });
+
+                if scored.len() == top_k {
+                    break;
+                }
+            }
+
+            // A selective filter can reject most candidates: widen the
+            // search until top_k pass or it already covered every node.
+            if scored.len() == top_k || below_min || knbn >= nodes {
+                return Ok(SearchResult {
+                    points: scored,
+                    exact: false,
+                    exact_fallback: false,
+                    visited,
+                    ef: Some(ef),
+                });
+            }
+            knbn = knbn.saturating_mul(4);
+            ef = ef.max(knbn);
+        }
+    }
+
+    /// Exact top-k by brute-force scan over every stored vector, for queries
+    /// that can't accept HNSW's approximation. `filter` is handled as in
+    /// `query_with_filter` and `include_values` as in `query`. Returns
+    /// `Ok(None)` if `deadline` passes first.
+    pub fn query_exact(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        filter: &Map<String, Value>,
+        deadline: Option<Instant>,
+        include_values: bool,
+    ) -> Result<Option<SearchResult>, String> {
+        if self.dim == 0 {
+            return Ok(Some(SearchResult {
+                exact: true,
+                ..SearchResult::default()
+            }));
+        }
+        if query.len() != self.dim {
+            return Err(format!(
+                "expected query vector of dimension {}, got {}",
+                self.dim,
+                query.len()
+            ));
+        }
+
+        if top_k == 0 || self.vectors.is_empty() {
+            return Ok(Some(SearchResult {
+                exact: true,
+                ..SearchResult::default()
+            }));
+        }
+
+        let qnorm_sq: f32 = query.iter().map(|x| x * x).sum();
+        if qnorm_sq == 0.0 {
+            return Err("query vector norm must be > 0".into());
+        }
+        let query = &*self.prepare_query(query);
+
+        let Some(filter) = self.effective_filter(filter) else {
+            return Ok(Some(SearchResult {
+                exact: true,
+                ..SearchResult::default()
+            }));
+        };
+        let filter = (!filter.is_empty()).then_some(&filter);
+
+        Ok(self
+            .exact_search(query, top_k, filter, deadline, include_values)
+            .map(|result| SearchResult {
+                exact_fallback: false,
+                ..result
+            }))
+    }
+
+    /// Run the HNSW search, returning `None` when its answer can't be
+    /// trusted: the search panicked, or it returned fewer candidates than the
+    /// graph holds (up to `knbn`). hnsw_rs can drop points from small
+    /// multi-layer graphs, and a damaged graph looks the same from outside.
+    /// Also returns the number of nodes the search visited.
+    fn hnsw_search(
+        &self,
+        query: &[f32],
+        knbn: usize,
+        ef: usize,
+    ) -> Option<(Vec<Neighbour>, usize)> {
+        let evals_before = DISTANCE_EVALS.with(Cell::get);
+        let searched = panic::catch_unwind(AssertUnwindSafe(|| self.hnsw.search(query, knbn, ef)));
+        let visited = DISTANCE_EVALS.with(Cell::get) - evals_before;
+        let neighbours = match searched {
+            Ok(n) => n,
+            Err(_) => {
+                tracing::error!("HNSW search panicked, falling back to exact scan");
+                return None;
+            }
+        };
+
+        let expected = knbn.min(self.hnsw.get_nb_point());
+        if neighbours.len() < expected {
+            tracing::warn!(
+                "HNSW search returned {} of {} expected candidates, falling back to exact scan",
+                neighbours.len(),
+                expected
+            );
+            return None;
+        }
+        Some((neighbours, visited))
+    }
+
+    /// Bring a (validated, non-zero) query into the same space as the stored
+    /// vectors.
+    fn prepare_query<'q>(&self, query: &'q [f32]) -> Cow<'q, [f32]> {
+        if self.config.metric.normalizes() {
+            let mut q = query.to_vec();
+            normalize(&mut q);
+            Cow::Owned(q)
+        } else {
+            Cow::Borrowed(query)
+        }
+    }
+
+    /// L2 norm of a stored vector, as cached at upsert.
+    pub fn norm(&self, id: &str) -> Option<f32> {
+        self.vectors.get(id).map(|v| v.norm)
+    }
+
+    /// Stored values and metadata of `id`, as kept in the ground-truth map
+    /// (normalized for `normalized_cosine`; empty while pending). Deleted ids
+    /// are gone from the map even though HNSW may still hold their nodes.
+    pub fn get(&self, id: &str) -> Option<(Vec<f32>, Option<Value>)> {
+        self.vectors
+            .get(id)
+            .map(|v| (self.values_of(v).to_vec(), v.metadata.clone()))
+    }
+
+    /// Up to `limit` stored ids after `after`, in ascending order, and
+    /// whether more follow. Ids are selected with a linear scan per page
+    /// rather than kept sorted, so writes pay nothing for scrolling; a
+    /// deleted `after` is fine, the page starts at the next id.
+    pub fn scroll(&self, after: Option<&str>, limit: usize) -> (Vec<&str>, bool) {
+        let mut ids: Vec<&str> = self
+            .vectors
+            .keys()
+            .map(String::as_str)
+            .filter(|id| after.is_none_or(|after| *id > after))
+            .collect();
+        let more = ids.len() > limit;
+        if more {
+            ids.select_nth_unstable(limit);
+            ids.truncate(limit);
+        }
+        ids.sort_unstable();
+        (ids, more)
+    }
+
+    pub fn metadata(&self, id: &str) -> Option<&Value> {
+        self.vectors.get(id).and_then(|v| v.metadata.as_ref())
+    }
+
+    pub fn is_pending(&self, id: &str) -> bool {
+        self.vectors.get(id).is_some_and(IndexedVector::is_pending)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.vectors.contains_key(id)
+    }
+
+    /// Distance under the collection's metric between stored vector `id` and
+    /// `other`. `score_for_distance` turns it into a query score.
+    pub fn distance(&self, id: &str, other: DistanceTo<'_>) -> Result<f32, String> {
+        let lookup = |id: &str| match self.vectors.get(id) {
+            None => Err(format!("vector '{}' not found", id)),
+            Some(v) if v.is_pending() => Err(format!("vector '{}' has no values yet", id)),
+            Some(v) => Ok(self.values_of(v)),
+        };
+        let a = lookup(id)?;
+        let b = match other {
+            DistanceTo::Id(other) => Cow::Borrowed(lookup(other)?),
+            DistanceTo::Vector(values) => {
+                validate_values(self.dim, values)?;
+                if values.iter().all(|x| *x == 0.0) {
+                    return Err("vector norm must be > 0".into());
+                }
+                self.prepare_query(values)
             }
+        };
+        Ok(MetricDistance(self.config.metric).eval(a, &b))
+    }
 
-            let score = 1.0 - dist;
+    /// Score of a stored vector against a prepared query, on the same scale
+    /// as HNSW query scores.
+    fn similarity(&self, query: &[f32], values: &[f32]) -> f32 {
+        let metric = MetricDistance(self.config.metric);
+        metric.score(metric.eval(query, values))
+    }
 
+    /// Brute-force top-k over the ground-truth map, best-first (ties by id).
+    /// Returns `None` once `deadline` passes (checked every few thousand
+    /// vectors).
+    fn exact_search(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        filter: Option<&Map<String, Value>>,
+        deadline: Option<Instant>,
+        include_values: bool,
+    ) -> Option<SearchResult> {
+        let metric = MetricDistance(self.config.metric);
+        let mut scored: Vec<ScoredPoint> = Vec::new();
+        for (i, (id, v)) in self.vectors.iter().enumerate() {
+            if i % 4096 == 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                return None;
+            }
+            if v.is_pending() || filter.is_some_and(|f| !metadata_matches_filter(&v.metadata, f)) {
+                continue;
+            }
+            let distance = metric.eval(query, self.values_of(v));
             scored.push(ScoredPoint {
-                id: external_id.clone(),
-                score,
-                metadata: stored.metadata.clone(),
+                id: id.clone(),
+                score: metric.score(distance),
+                distance,
+                metadata: v.metadata.clone(),
+                values: include_values.then(|| self.values_of(v).to_vec()),
             });
+        }
 
-            if scored.len() == top_k {
-                break;
+        let visited = scored.len();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        scored.truncate(top_k);
+
+        Some(SearchResult {
+            points: scored,
+            exact: true,
+            exact_fallback: true,
+            visited,
+            ef: None,
+        })
+    }
+
+    /// AND `filter` with the collection's default filter. Returns `None` when
+    /// the two constrain the same key to different values.
+    fn effective_filter(&self, filter: &Map<String, Value>) -> Option<Map<String, Value>> {
+        let Some(default) = &self.config.default_filter else {
+            return Some(filter.clone());
+        };
+
+        let mut combined = default.clone();
+        for (k, v) in filter {
+            match combined.get(k) {
+                Some(existing) if existing != v => return None,
+                _ => {
+                    combined.insert(k.clone(), v.clone());
+                }
             }
         }
+        Some(combined)
+    }
 
-        Ok(scored)
+    /// Predict the cost of a query without running it. The filter's
+    /// selectivity is measured on a sample of the stored vectors, so this
+    /// stays cheap on large collections.
+    pub fn estimate_query(
+        &self,
+        top_k: usize,
+        filter: &Map<String, Value>,
+        exact: bool,
+        ef_search: Option<usize>,
+        bounds: EfBounds,
+    ) -> QueryEstimate {
+        let n = self.vectors.len();
+        let selectivity = match self.effective_filter(filter) {
+            // Contradicts the default filter: nothing can match.
+            None => Some(0.0),
+            Some(f) if f.is_empty() => None,
+            Some(f) => {
+                let sampled = n.min(SELECTIVITY_SAMPLE);
+                let matching = self
+                    .vectors
+                    .values()
+                    .take(sampled)
+                    .filter(|v| metadata_matches_filter(&v.metadata, &f))
+                    .count();
+                Some(if sampled == 0 { 0.0 } else { matching as f64 / sampled as f64 })
+            }
+        };
+
+        let (knbn, ef) = search_params(top_k, selectivity.is_some(), ef_search, n, bounds);
+        let (candidates, pool) = if top_k == 0 || n == 0 {
+            (0, 0)
+        } else if exact {
+            (n, n)
+        } else {
+            // Each node HNSW expands compares against up to M neighbours.
+            ((ef * MAX_NB_CONNECTION).min(n), knbn.min(n))
+        };
+        let expected = (pool as f64 * selectivity.unwrap_or(1.0)).round() as usize;
+
+        QueryEstimate {
+            candidates,
+            selectivity,
+            expected_matches: expected.min(top_k),
+        }
+    }
+
+    /// Count vectors matching `filter` (ANDed with the default filter) and,
+    /// when `min_score` is set, scoring at least that against `query`.
+    ///
+    /// This is an exact scan over the ground-truth map: it never touches
+    /// HNSW and never clones metadata, so it is cheap for analytics-style
+    /// "how many match" questions.
+    pub fn count_matching(
+        &self,
+        query: Option<&[f32]>,
+        filter: &Map<String, Value>,
+        min_score: Option<f32>,
+    ) -> Result<usize, String> {
+        if self.dim == 0 && min_score.is_some() {
+            // No vector has values yet.
+            return Ok(0);
+        }
+        let threshold = match (query, min_score) {
+            (Some(q), Some(min)) => {
+                if q.len() != self.dim {
+                    return Err(format!(
+                        "expected query vector of dimension {}, got {}",
+                        self.dim,
+                        q.len()
+                    ));
+                }
+                Some((self.prepare_query(q), min))
+            }
+            (None, Some(_)) => return Err("min_score requires a query vector".into()),
+            _ => None,
+        };
+
+        let Some(filter) = self.effective_filter(filter) else {
+            return Ok(0);
+        };
+
+        let count = self
+            .vectors
+            .values()
+            .filter(|v| filter.is_empty() || metadata_matches_filter(&v.metadata, &filter))
+            .filter(|v| match &threshold {
+                Some((q, min)) => !v.is_pending() && self.similarity(q, self.values_of(v)) >= *min,
+                None => true,
+            })
+            .count();
+
+        Ok(count)
+    }
+
+    /// Vectors matching `filter` (a JSON object, as in `query_with_filter`)
+    /// and the collection's default filter. Without either this is just
+    /// `vector_count`; otherwise it is `count_matching`'s scan.
+    pub fn count(&self, filter: Option<&Value>) -> usize {
+        let filter = filter
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        if filter.is_empty() && self.config.default_filter.is_none() {
+            return self.vector_count();
+        }
+        // Without a threshold the scan can't fail.
+        self.count_matching(None, &filter, None).unwrap_or_default()
     }
 
     pub fn vector_count(&self) -> usize {
         self.vectors.len()
     }
 
-    /// Export all vectors for snapshots: (id, values, metadata).
-    pub fn export_vectors(&self) -> Vec<(String, Vec<f32>, Option<Value>)> {
-        self.vectors
-            .iter()
-            .map(|(id, v)| (id.clone(), v.values.clone(), v.metadata.clone()))
-            .collect()
+    /// Every vector as stored, like `export_vectors` but borrowed rather
+    /// than copied.
+    pub fn stored_vectors(&self) -> impl Iterator<Item = StoredVectorRef<'_>> {
+        self.vectors.iter().map(|(id, v)| StoredVectorRef {
+            id,
+            values: self.values_of(v),
+            metadata: v.metadata.as_ref(),
+            times: v.times,
+            version: v.version,
+        })
+    }
+
+    /// Export all vectors for snapshots, as stored (i.e. already normalized
+    /// for `normalized_cosine` collections).
+    pub fn export_vectors(&self) -> ExportedVectors {
+        ExportedVectors {
+            normalized: self.config.metric.normalizes(),
+            vectors: self
+                .vectors
+                .iter()
+                .map(|(id, v)| ExportedVector {
+                    id: id.clone(),
+                    values: self.values_of(v).to_vec(),
+                    metadata: v.metadata.clone(),
+                    times: v.times,
+                    version: v.version,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A stored vector, borrowed from its index, see
+/// `InMemoryIndex::stored_vectors`.
+pub struct StoredVectorRef<'a> {
+    pub id: &'a str,
+    pub values: &'a [f32],
+    pub metadata: Option<&'a Value>,
+    pub times: Timestamps,
+    pub version: u64,
+}
+
+/// Stored vectors of one collection.
+pub struct ExportedVectors {
+    /// True when `values` were L2-normalized on upsert rather than kept as sent.
+    pub normalized: bool,
+    pub vectors: Vec<ExportedVector>,
+}
+
+pub struct ExportedVector {
+    pub id: String,
+    pub values: Vec<f32>,
+    pub metadata: Option<Value>,
+    pub times: Timestamps,
+    pub version: u64,
+}
+
+/// Multiply the score of points whose metadata matches `filter`.
+pub struct ScoreBoost {
+    pub filter: Map<String, Value>,
+    pub factor: f32,
+}
+
+/// Apply `boosts` to already-scored points and re-sort best-first.
+///
+/// A point matching several boosts gets every factor. Factors scale the raw
+/// similarity, so a boost > 1 promotes positive scores (and demotes negative
+/// ones, which are already dissimilar).
+pub fn apply_boosts(points: &mut [ScoredPoint], boosts: &[ScoreBoost]) {
+    if boosts.is_empty() {
+        return;
+    }
+    for p in points.iter_mut() {
+        p.score *= boost_factor(&p.metadata, boosts);
+    }
+    points.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+/// Combined factor `apply_boosts` scales a point with `metadata` by.
+pub fn boost_factor(metadata: &Option<Value>, boosts: &[ScoreBoost]) -> f32 {
+    boosts
+        .iter()
+        .filter(|b| metadata_matches_filter(metadata, &b.filter))
+        .map(|b| b.factor)
+        .product()
+}
+
+/// Size of `metadata` serialized as compact JSON; 0 without metadata.
+pub fn metadata_size(metadata: &Option<Value>) -> usize {
+    metadata
+        .as_ref()
+        .map_or(0, |m| serde_json::to_vec(m).map_or(0, |b| b.len()))
+}
+
+/// Reject metadata whose serialized size exceeds `max` bytes.
+pub fn validate_metadata_size(metadata: &Option<Value>, max: Option<usize>) -> Result<(), String> {
+    let Some(max) = max else {
+        return Ok(());
+    };
+    let size = metadata_size(metadata);
+    if size > max {
+        return Err(format!(
+            "metadata is {} bytes, exceeding the {} byte limit",
+            size, max
+        ));
     }
+    Ok(())
+}
+
+/// Sort best-first with ties broken by id: the total order cursors page
+/// through.
+pub fn sort_for_paging(points: &mut [ScoredPoint]) {
+    points.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+}
+
+/// Sort best-first, breaking score ties by metadata `field` (descending if
+/// `descending`), then by id. Numbers sort before strings, and points where
+/// `field` is missing or neither sort last in both directions.
+pub fn sort_by_field(points: &mut [ScoredPoint], field: &str, descending: bool) {
+    fn key<'a>(p: &'a ScoredPoint, field: &str) -> Option<(u8, Option<f64>, Option<&'a str>)> {
+        match p.metadata.as_ref()?.get(field)? {
+            Value::Number(n) => Some((0, n.as_f64(), None)),
+            Value::String(s) => Some((1, None, Some(s.as_str()))),
+            _ => None,
+        }
+    }
+
+    points.sort_by(|a, b| {
+        let by_field = match (key(a, field), key(b, field)) {
+            (Some((ka, na, sa)), Some((kb, nb, sb))) => {
+                let values = match (na, nb) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    _ => sa.cmp(&sb),
+                };
+                let values = if descending { values.reverse() } else { values };
+                ka.cmp(&kb).then(values)
+            }
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        b.score
+            .total_cmp(&a.score)
+            .then(by_field)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Drop every point up to and including (`score`, `id`) in the
+/// `sort_for_paging` order.
+pub fn search_after(points: &mut Vec<ScoredPoint>, score: f32, id: &str) {
+    points.retain(|p| match p.score.total_cmp(&score) {
+        std::cmp::Ordering::Less => true,
+        std::cmp::Ordering::Equal => p.id.as_str() > id,
+        std::cmp::Ordering::Greater => false,
+    });
+}
+
+/// Keep at most `group_size` points per distinct value of metadata field
+/// `field`, preserving order. Points without the field aren't limited.
+pub fn limit_per_group(points: &mut Vec<ScoredPoint>, field: &str, group_size: usize) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    points.retain(|p| {
+        let Some(key) = p.metadata.as_ref().and_then(|m| m.get(field)) else {
+            return true;
+        };
+        let count = seen.entry(key.to_string()).or_default();
+        *count += 1;
+        *count <= group_size
+    });
+}
+
+/// Scale `values` to unit length. Callers have already rejected zero vectors.
+fn normalize(values: &mut [f32]) {
+    let norm = values.iter().map(|x| x * x).sum::<f32>().sqrt();
+    for x in values.iter_mut() {
+        *x /= norm;
+    }
+}
+
+/// Current time in ms since the Unix epoch, as stored in `Timestamps`.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Angular distance in radians for a cosine similarity score.
+///
+/// The similarity is clamped to `[-1, 1]` first so float error on
+/// near-identical vectors can't push `acos` to NaN.
+pub fn angular_distance(cos_sim: f32) -> f32 {
+    cos_sim.clamp(-1.0, 1.0).acos()
+}
+
+/// A batch of vectors validated against a collection's dimension without
+/// holding any lock, ready to be merged into the live index in one pass.
+pub struct StagedBatch {
+    dim: usize,
+    config: CollectionConfig,
+    vectors: Vec<(String, Vec<f32>, Option<Value>)>,
+    written_at: u64,
+    // Counted into the collection's on merge
+    norms: NormStats,
+}
+
+impl StagedBatch {
+    pub fn new(dim: usize, config: CollectionConfig) -> Self {
+        Self {
+            dim,
+            config,
+            vectors: Vec::new(),
+            written_at: now_millis(),
+            norms: NormStats::default(),
+        }
+    }
+
+    /// Time every vector in the batch is recorded as upserted at.
+    pub fn written_at(&self) -> u64 {
+        self.written_at
+    }
+
+    pub fn push(
+        &mut self,
+        id: String,
+        mut values: Vec<f32>,
+        metadata: Option<Value>,
+    ) -> Result<(), String> {
+        // Empty values stage a pending vector, see `InMemoryIndex::upsert_at`.
+        if !values.is_empty() {
+            let norm = prepare_values(self.dim, &self.config, &mut values)?;
+            self.norms.record(norm);
+        }
+        self.vectors.push((id, values, metadata));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Ids in the order they were pushed.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.vectors.iter().map(|(id, _, _)| id.as_str())
+    }
+
+    /// Ids and (prepared) values in the order they were pushed.
+    pub fn values(&self) -> impl Iterator<Item = (&str, &[f32])> {
+        self.vectors.iter().map(|(id, values, _)| (id.as_str(), values.as_slice()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    /// HNSW nodes merging the batch will add: one per vector with values.
+    pub fn nodes(&self) -> usize {
+        self.vectors.iter().filter(|(_, values, _)| !values.is_empty()).count()
+    }
+}
+
+/// Validate `values` for a collection and bring them into stored form
+/// (range-checked or clamped, then normalized if the metric wants it).
+/// Returns their norm before normalization.
+fn prepare_values(
+    dim: usize,
+    config: &CollectionConfig,
+    values: &mut [f32],
+) -> Result<f32, String> {
+    if values.len() == dim
+        && let Some(range) = &config.value_range
+    {
+        range.apply(values)?;
+    }
+    if config.zero_vectors == ZeroVectors::NormalizeToEpsilon && values.iter().all(|v| *v == 0.0) {
+        values.fill(f32::EPSILON);
+    }
+    let norm = validate_values(dim, values)?.sqrt();
+    if config.metric.normalizes() {
+        normalize(values);
+    }
+    Ok(norm)
+}
+
+/// Check `values` against the dimension and reject zero vectors. Returns
+/// their squared norm.
+fn validate_values(dim: usize, values: &[f32]) -> Result<f32, String> {
+    if values.len() != dim {
+        return Err(format!(
+            "expected vector of dimension {}, got {}",
+            dim,
+            values.len()
+        ));
+    }
+
+    // Basic sanity: avoid zero vector, which is degenerate for cosine
+    let norm_sq: f32 = values.iter().map(|x| x * x).sum();
+    if norm_sq == 0.0 {
+        return Err("vector norm must be > 0".into());
+    }
+
+    Ok(norm_sq)
 }
 
 fn metadata_matches_filter(
     metadata: &Option<Value>,
     filter: &Map<String, Value>,
 ) -> bool {
-    let meta_obj = match metadata {
-        Some(Value::Object(m)) => m,
-        _ => return false,
-    };
-
-    for (k, fv) in filter {
-        match meta_obj.get(k) {
-            Some(mv) if mv == fv => continue,
-            _ => return false,
-        }
+    match metadata {
+        Some(Value::Object(m)) => object_matches(m, filter),
+        _ => false,
     }
+}
 
-    true
+/// Every key of `filter` is in `obj` with an equal value. Object values
+/// match recursively, so nested filters only constrain the keys they name.
+fn object_matches(obj: &Map<String, Value>, filter: &Map<String, Value>) -> bool {
+    filter.iter().all(|(k, fv)| match (obj.get(k), fv) {
+        (Some(Value::Object(mv)), Value::Object(fv)) => object_matches(mv, fv),
+        (Some(mv), fv) => mv == fv,
+        (None, _) => false,
+    })
 }
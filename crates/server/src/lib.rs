@@ -0,0 +1,208 @@
+use std::future::{Future, IntoFuture};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+pub mod audit;
+pub mod auth;
+pub mod benchmark;
+pub mod config;
+pub mod error;
+pub mod index;
+pub mod metrics;
+pub mod models;
+pub mod routes;
+pub mod state;
+pub mod storage;
+pub mod vector_store;
+
+use crate::state::AppState;
+
+/// Build the HTTP router over `state`. Shared by `main` and the
+/// integration tests so both exercise the same route table.
+pub fn build_router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/collections",
+            post(routes::create_collection).get(routes::list_collections),
+        )
+        .route("/collections/stats", get(routes::all_collection_stats))
+        .route(
+            "/collections/batch",
+            post(routes::create_collections_batch),
+        )
+        .route(
+            "/collections/delete-by-prefix",
+            post(routes::delete_collections_by_prefix),
+        )
+        .route(
+            "/collections/:name",
+            get(routes::get_collection).delete(routes::delete_collection),
+        )
+        .route(
+            "/collections/:name/stats",
+            get(routes::collection_stats),
+        )
+        .route(
+            "/collections/:name/distances",
+            post(routes::vector_distance),
+        )
+        .route(
+            "/collections/:name/reindex",
+            post(routes::reindex_collection),
+        )
+        .route(
+            "/collections/:name/compact",
+            post(routes::compact_collection),
+        )
+        .route(
+            "/collections/:name/vectors",
+            get(routes::scroll_vectors),
+        )
+        .route(
+            "/collections/:name/export/documents",
+            get(routes::export_documents),
+        )
+        .route(
+            "/collections/:name/vectors/upsert",
+            post(routes::upsert_vectors),
+        )
+        .route(
+            "/collections/:name/vectors/delete",
+            post(routes::delete_vectors_batch),
+        )
+        .route(
+            "/collections/:name/vectors/:id",
+            get(routes::get_vector)
+                .patch(routes::update_vector_metadata)
+                .delete(routes::delete_vector),
+        )
+        .route(
+            "/admin/snapshot",
+            post(routes::create_snapshot),
+        )
+        .route("/admin/sync", post(routes::sync_wal_now))
+        .route(
+            "/admin/maintenance",
+            get(routes::maintenance_status).post(routes::set_maintenance),
+        )
+        .route(
+            "/admin/benchmark",
+            post(routes::run_benchmark).delete(routes::cancel_benchmark),
+        )
+        .route(
+            "/admin/collections",
+            get(routes::admin_list_collections),
+        )
+        .route(
+            "/admin/reconcile",
+            get(routes::reconcile_report).post(routes::reconcile_repair),
+        )
+        .route("/collections/:name/count", post(routes::count_vectors))
+        .route("/collections/:name/query", post(routes::query_vectors))
+        .route("/collections/:name/query/batch", post(routes::query_batch))
+        .route("/collections/:name/query/sse", get(routes::query_vectors_sse))
+        .route("/collections/:name/query/count", post(routes::count_query))
+        .route(
+            "/collections/:name/query/estimate",
+            post(routes::estimate_query),
+        )
+        // Only the keyed routes above report a rate limit budget.
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit_headers))
+        .route("/health", get(routes::health))
+        .route("/metrics", get(routes::metrics))
+        .layer(middleware::from_fn_with_state(state.clone(), track_in_flight))
+        .with_state(state)
+}
+
+async fn track_in_flight(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let _request = state.begin_request();
+    next.run(req).await
+}
+
+/// Tell clients where they stand under `Config::rate_limit_rps` with
+/// `X-RateLimit-Limit` (the burst) and `X-RateLimit-Remaining`, 429s
+/// included. Left off when rate limiting is disabled.
+async fn rate_limit_headers(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let key = auth::presented_key(req.headers()).ok().map(str::to_string);
+    let mut resp = next.run(req).await;
+    if let Some((limit, remaining)) = key.and_then(|key| state.rate_limit_status(&key)) {
+        let headers = resp.headers_mut();
+        headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+    }
+    resp
+}
+
+/// What happened to the requests in flight when shutdown began.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Finished within the drain timeout.
+    pub drained: usize,
+    /// Still running when it expired.
+    pub cut: usize,
+}
+
+/// Serve `state` on `listener` until `shutdown` resolves, then stop
+/// accepting connections and give in-flight requests up to the configured
+/// drain timeout to finish before persisting (see
+/// `AppState::persist_on_shutdown`).
+pub async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    shutdown: impl Future<Output = ()>,
+) -> anyhow::Result<DrainReport> {
+    let draining = Arc::new(Notify::new());
+    let server = axum::serve(listener, build_router(state.clone()))
+        .with_graceful_shutdown({
+            let draining = draining.clone();
+            async move { draining.notified().await }
+        })
+        .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        res = &mut server => {
+            res?;
+            return Ok(DrainReport::default());
+        }
+        _ = shutdown => {}
+    }
+
+    let in_flight = state.in_flight_requests();
+    let timeout = state.config.drain_timeout();
+    tracing::info!(
+        "shutting down: draining {} in-flight requests (timeout {:?})",
+        in_flight,
+        timeout
+    );
+    draining.notify_one();
+    let cut = match tokio::time::timeout(timeout, &mut server).await {
+        Ok(res) => {
+            res?;
+            0
+        }
+        Err(_) => state.in_flight_requests().min(in_flight),
+    };
+    let report = DrainReport {
+        drained: in_flight - cut,
+        cut,
+    };
+    if cut > 0 {
+        tracing::warn!("drained {} requests, cut off {}", report.drained, cut);
+    } else {
+        tracing::info!("drained {} requests", report.drained);
+    }
+
+    state.persist_on_shutdown().await;
+    Ok(report)
+}
@@ -1,91 +1,114 @@
-use axum::{
-    routing::{get, post, delete},
-    Router,
-};
+use std::net::SocketAddr;
+
+use anyhow::Context;
 use tokio::net::TcpListener;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod routes;
-mod state;
-mod index;
-mod models;
-mod storage;
-mod auth;
-
-use crate::state::AppState;
-
-use std::collections::HashMap;
+use openvdb_server::config::{Config, WalSync};
+use openvdb_server::state::{self, AppState};
 
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_tracing();
 
+    let config = Config::from_env()?;
+    tracing::info!("default collection metric: {:?}", config.default_metric);
+    let addr = bind_addr()?;
+
+    let idle_evict = config.tenant_idle_evict_secs.map(std::time::Duration::from_secs);
+    let wal_retention = config.wal_retention();
+    let snapshot_interval = config.snapshot_interval_secs.map(std::time::Duration::from_secs);
+    let compaction_window = config.compaction_window.filter(|_| config.auto_compact);
+    let wal_flush = (config.wal_sync == WalSync::Interval)
+        .then(|| std::time::Duration::from_millis(config.wal_sync_interval_ms));
+
     // Load previous state from WAL + snapshot
-    let mut collections = match storage::load_collections_from_snapshot() {
-		Ok(Some(map)) => {
-			tracing::info!(
-				"loaded collections from snapshot ({} tenants)",
-				map.len()
-			);
-			map
-		}
-		Ok(None) => {
-			tracing::info!("no snapshot found, starting from empty state");
-			HashMap::new()
-		}
-		Err(e) => {
-			tracing::error!("failed to load snapshot: {:?}", e);
-			HashMap::new()
-		}
-	};
+    let app_state = AppState::load(config, state::api_keys_from_env())?;
+    if let Some(idle) = idle_evict {
+        state::spawn_tenant_evictor(app_state.clone(), idle);
+    }
+    if let Some(interval) = wal_flush {
+        state::spawn_wal_flusher(app_state.clone(), interval);
+    }
+    if let Some(retention) = wal_retention {
+        state::spawn_wal_pruner(app_state.clone(), retention);
+    }
+    if let Some(interval) = snapshot_interval {
+        state::spawn_snapshotter(app_state.clone(), interval);
+    }
+    if let Some(window) = compaction_window {
+        state::spawn_compaction_scheduler(app_state.clone(), window);
+    }
 
-	
-	if let Err(e) = storage::replay_wal(&mut collections) {
-		tracing::error!("failed to replay WAL: {:?}", e);
-	} else {
-		tracing::info!("replayed WAL successfully");
-	}
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind {}", addr))?;
+    tracing::info!(
+        "🚀 openvdb-server listening on http://{}",
+        listener.local_addr().unwrap_or(addr)
+    );
 
-	let app_state = AppState::with_collections(collections);
+    openvdb_server::serve(listener, app_state, shutdown_signal()).await?;
 
+    Ok(())
+}
 
-    let app = Router::new()
-        .route("/health", get(routes::health))
-        .route(
-            "/collections",
-            post(routes::create_collection).get(routes::list_collections),
-        )
-        .route(
-            "/collections/:name",
-            get(routes::get_collection).delete(routes::delete_collection),
-        )
-		.route(
-			"/collections/:name/stats",
-			get(routes::collection_stats),
-		)
-        .route(
-            "/collections/:name/vectors/upsert",
-            post(routes::upsert_vectors),
-        )
-        .route(
-            "/collections/:name/vectors/:id",
-            delete(routes::delete_vector),
-        )
-		.route(
-			"/admin/snapshot",
-			post(routes::create_snapshot),
-		)
-        .route("/collections/:name/query", post(routes::query_vectors))
-        .with_state(app_state);
+const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8080";
 
-    let addr = "127.0.0.1:8080";
-    let listener = TcpListener::bind(addr).await?;
-    tracing::info!("🚀 openvdb-server listening on http://{}", addr);
+/// Listen address: `--bind <addr>` (or `--bind=<addr>`), else
+/// `OPENVDB_BIND_ADDR`, else `DEFAULT_BIND_ADDR`. Unlike other settings an
+/// invalid value is fatal rather than ignored, so a container never ends up
+/// listening somewhere unexpected.
+fn bind_addr() -> anyhow::Result<SocketAddr> {
+    let mut args = std::env::args().skip(1);
+    let mut from_args = None;
+    while let Some(arg) = args.next() {
+        if arg == "--bind" {
+            from_args = Some(args.next().context("--bind needs an address, e.g. 0.0.0.0:9000")?);
+        } else if let Some(value) = arg.strip_prefix("--bind=") {
+            from_args = Some(value.to_string());
+        }
+    }
+    let (source, raw) = match from_args {
+        Some(raw) => ("--bind", raw),
+        None => match std::env::var("OPENVDB_BIND_ADDR") {
+            Ok(raw) => ("OPENVDB_BIND_ADDR", raw),
+            Err(_) => ("default", DEFAULT_BIND_ADDR.to_string()),
+        },
+    };
+    raw.trim()
+        .parse()
+        .with_context(|| format!("invalid {} address {:?}, expected e.g. 0.0.0.0:9000", source, raw))
+}
 
-    axum::serve(listener, app).await?;
+/// Resolves on Ctrl-C, or SIGTERM on Unix (what orchestrators send).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("failed to listen for Ctrl-C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    Ok(())
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
 
 fn init_tracing() {
@@ -0,0 +1,130 @@
+use std::fmt::Write as _;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (seconds) of the latency histogram buckets.
+const BUCKETS: [f64; 12] = [
+    0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0,
+];
+
+/// Fixed-bucket latency histogram updated with relaxed atomics, so recording
+/// costs a handful of uncontended adds.
+#[derive(Default)]
+pub struct Histogram {
+    // Per-bucket (non-cumulative) counts; the last slot is +Inf.
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    sum_nanos: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, d: Duration) {
+        let secs = d.as_secs_f64();
+        let slot = BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[slot].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(d.as_nanos() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Append this histogram in Prometheus text format under `name{labels}`.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (i, le) in BUCKETS.iter().enumerate() {
+            cumulative += self.buckets[i].load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, le, cumulative);
+        }
+        cumulative += self.buckets[BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, cumulative);
+        let sum = self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count());
+    }
+}
+
+/// Wait/hold times of the global collections lock, split by mode.
+#[derive(Default)]
+pub struct LockMetrics {
+    pub read_wait: Histogram,
+    pub read_hold: Histogram,
+    pub write_wait: Histogram,
+    pub write_hold: Histogram,
+}
+
+/// Process-wide metrics served by `GET /metrics`.
+#[derive(Default)]
+pub struct Metrics {
+    pub collections_lock: LockMetrics,
+}
+
+impl Metrics {
+    /// Prometheus text exposition of every metric.
+    pub fn render(&self) -> String {
+        let lock = &self.collections_lock;
+        let mut out = String::new();
+
+        out.push_str("# HELP openvdb_collections_lock_wait_seconds Time spent waiting to acquire the collections lock.\n");
+        out.push_str("# TYPE openvdb_collections_lock_wait_seconds histogram\n");
+        lock.read_wait
+            .render(&mut out, "openvdb_collections_lock_wait_seconds", "mode=\"read\"");
+        lock.write_wait
+            .render(&mut out, "openvdb_collections_lock_wait_seconds", "mode=\"write\"");
+
+        out.push_str("# HELP openvdb_collections_lock_hold_seconds Time the collections lock was held.\n");
+        out.push_str("# TYPE openvdb_collections_lock_hold_seconds histogram\n");
+        lock.read_hold
+            .render(&mut out, "openvdb_collections_lock_hold_seconds", "mode=\"read\"");
+        lock.write_hold
+            .render(&mut out, "openvdb_collections_lock_hold_seconds", "mode=\"write\"");
+
+        out
+    }
+}
+
+/// Lock guard that records how long it was held into `hold` when dropped.
+pub struct TimedGuard<'a, G> {
+    guard: G,
+    acquired: Instant,
+    hold: &'a Histogram,
+}
+
+impl<'a, G> TimedGuard<'a, G> {
+    /// Wrap a guard obtained after waiting since `requested`.
+    pub fn new(guard: G, requested: Instant, wait: &Histogram, hold: &'a Histogram) -> Self {
+        let acquired = Instant::now();
+        wait.observe(acquired - requested);
+        Self {
+            guard,
+            acquired,
+            hold,
+        }
+    }
+}
+
+impl<G: Deref> Deref for TimedGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for TimedGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<G> Drop for TimedGuard<'_, G> {
+    fn drop(&mut self) {
+        self.hold.observe(self.acquired.elapsed());
+    }
+}
@@ -1,80 +1,231 @@
+use std::collections::{btree_set, BTreeSet, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{Duration, Instant};
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-
-use crate::auth::ApiKey;
-use crate::index::InMemoryIndex;
+use futures_util::Stream;
+
+use crate::auth::{AdminKey, ApiKey, WriteKey};
+use crate::benchmark;
+use crate::error::ApiError;
+use crate::index::{
+    angular_distance, apply_boosts, boost_factor, limit_per_group, DistanceTo, now_millis, search_after,
+    sort_by_field, sort_for_paging, validate_metadata_size, CollectionConfig, InMemoryIndex, QueryPermit, ScoreBoost,
+    Metric, StagedBatch, ValueRange, WriteMode,
+};
 use crate::models::{
-    CollectionSummary, CreateCollectionRequest, CreateCollectionResponse,
-    DeleteCollectionResponse, DeleteVectorResponse, GetCollectionResponse, HealthResponse,
-    ListCollectionsResponse, QueryMatch, QueryRequest, QueryResponse, UpsertRequest,
-    UpsertResponse,CollectionStatsResponse,SnapshotResponse,
+    AdminCollectionSummary, AdminListCollectionsResponse, CollectionRef, CountQueryRequest,
+    ReconcileResponse, CountResponse, CollectionSummary, CreateCollectionRequest, CreateCollectionResponse,
+    BatchResponse, CreateCollectionsRequest, DeleteByPrefixRequest, DeleteByPrefixResponse,
+    DeleteVectorsRequest, ItemStatus, DeleteCollectionResponse, DeleteVectorResponse, GetCollectionResponse, HealthResponse,
+    BenchmarkRequest, BenchmarkResponse, ListCollectionsResponse, QueryDebug, QueryMatch, QueryRequest, QueryResponse, ScoreComponents, DistanceRequest, DistanceResponse, GetVectorParams, GetVectorResponse, LatencyClass, QueryEstimateRequest,
+    QueryEstimateResponse, QueryParams, ReindexParams, ReindexResponse, CompactResponse, QueryBatchItem, QueryBatchRequest,
+    QueryBatchResponse, ScrollParams, ScrollResponse, ScrolledVector, DocumentExportParams, ExportedDocument, ScoreMode, SortOrder, UpsertRequest,
+    UpsertParams, UpsertResponse,CollectionStatsResponse,SnapshotResponse,
+    SnapshotEstimate, SnapshotParams, Maintenance, UpdateMetadataRequest, UpdateMetadataResponse,
+    AllCollectionStatsResponse, SseQueryParams, QueryStreamEnd, Consistency, CountRequest,
 };
 
-use crate::state::AppState;
-use crate::storage::{append_entry, WalEntry};
-
+use crate::state::{map_vectors, AppState};
+use crate::storage::{encode_entry, sync_wal, WalEntry};
+use crate::storage::{load_historical_collection, write_snapshot_from_state};
 
 // ---------- health ----------
 
 pub async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
+    Json(HealthResponse { status: "ok".to_string() })
+}
+
+// ---------- metrics ----------
+
+/// Prometheus scrape endpoint. Unauthenticated, like `/health`.
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
 }
 
 // ---------- collections -----------
 pub async fn create_collection(
     State(state): State<AppState>,
-    api_key: ApiKey,
+    api_key: WriteKey,
     Json(payload): Json<CreateCollectionRequest>,
-) -> Result<Json<CreateCollectionResponse>, (StatusCode, String)> {
-    if payload.dimension == 0 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "dimension must be greater than 0".into(),
-        ));
-    }
+) -> Result<(HeaderMap, Json<CreateCollectionResponse>), ApiError> {
+    let config = collection_config(&state, &payload)?;
+    let tenant = api_key.0;
 
+    let mut collections = state.write_collections().await;
+    insert_collection(&state, &mut collections, &tenant, &payload, config)?;
+    state.audit(&tenant, &payload.name, "create_collection", 0);
+
+    Ok((
+        collection_quota_headers(&state, &collections, &tenant),
+        Json(CreateCollectionResponse {
+            name: payload.name,
+            dimension: payload.dimension,
+        }),
+    ))
+}
+
+/// Create several collections in one request. Each is validated and created
+/// on its own; see `batch_status` for the response code.
+pub async fn create_collections_batch(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Json(payload): Json<CreateCollectionsRequest>,
+) -> (StatusCode, HeaderMap, Json<BatchResponse>) {
     let tenant = api_key.0;
+    let mut collections = state.write_collections().await;
+
+    let results = payload
+        .collections
+        .into_iter()
+        .map(|req| {
+            let outcome = collection_config(&state, &req).and_then(|config| {
+                insert_collection(&state, &mut collections, &tenant, &req, config)
+            });
+            ItemStatus::from_result(req.name, outcome.map_err(ApiError::into_parts))
+        })
+        .collect();
+
+    let (status, body) = batch_response(results);
+    (status, collection_quota_headers(&state, &collections, &tenant), body)
+}
+
+/// Validate a create request and build the collection's config. A request
+/// without a metric gets `Config::default_metric`.
+fn collection_config(
+    state: &AppState,
+    payload: &CreateCollectionRequest,
+) -> Result<CollectionConfig, ApiError> {
+    let default_filter = match &payload.default_filter {
+        None => None,
+        Some(serde_json::Value::Object(map)) => Some(map.clone()),
+        Some(_) => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "default_filter must be a JSON object",
+            ));
+        }
+    };
+    let value_range = payload
+        .value_range
+        .map(|[min, max]| ValueRange::new(min, max, payload.out_of_range))
+        .transpose()
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    validate_capacity(state, payload.capacity)?;
+    // `normalize` asks for cosine itself, whatever the server default.
+    let metric = match (payload.metric, payload.normalize) {
+        (None | Some(Metric::Cosine), true) => Metric::NormalizedCosine,
+        (Some(metric), true) if !metric.normalizes() => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "normalize requires the cosine metric",
+            ));
+        }
+        (metric, _) => metric.unwrap_or(state.config.default_metric),
+    };
+    // Compared against `QueryMatch.distance`, whose range depends on the
+    // metric.
+    if let Some(t) = payload.dedup_threshold {
+        let error = match metric {
+            Metric::Dot => Some("dedup_threshold is not supported with the dot metric"),
+            _ if !(t.is_finite() && t >= 0.0) => {
+                Some("dedup_threshold must be a non-negative number")
+            }
+            Metric::Cosine | Metric::NormalizedCosine if t > 2.0 => {
+                Some("dedup_threshold must be at most 2 with the cosine metric")
+            }
+            _ => None,
+        };
+        if let Some(error) = error {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, error));
+        }
+    }
+
+    Ok(CollectionConfig {
+        default_filter,
+        metric,
+        value_range,
+        capacity: payload.capacity,
+        mode: payload.mode,
+        zero_vectors: payload.zero_vectors,
+        dedup_threshold: payload.dedup_threshold,
+    })
+}
 
-    let mut collections = state.collections.write().await;
-    let tenant_map = collections.entry(tenant.clone()).or_default();
+/// Add a new collection under the (already held) write lock and log it.
+fn insert_collection(
+    state: &AppState,
+    collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+    tenant: &str,
+    payload: &CreateCollectionRequest,
+    config: CollectionConfig,
+) -> Result<(), ApiError> {
+    let tenant_map = collections.entry(tenant.to_string()).or_default();
 
     if tenant_map.contains_key(&payload.name) {
-        return Err((
+        return Err(ApiError::new(
             StatusCode::CONFLICT,
             format!("collection '{}' already exists", payload.name),
         ));
     }
+    if let Some(max) = state.config.max_collections_per_tenant
+        && tenant_map.len() >= max
+    {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("tenant is at its limit of {} collections", max),
+        ));
+    }
 
-    tenant_map.insert(
-        payload.name.clone(),
-        InMemoryIndex::new(payload.dimension),
-    );
+    let mut index = InMemoryIndex::with_config(payload.dimension, config.clone());
+    map_vectors(&state.config, &mut index);
+    tenant_map.insert(payload.name.clone(), index);
 
-    if let Err(e) = append_entry(&WalEntry::CreateCollection {
-        tenant: tenant.clone(),
+    if let Err(e) = wal_append(state, &WalEntry::CreateCollection {
+        tenant: tenant.to_string(),
         name: payload.name.clone(),
         dimension: payload.dimension,
+        config,
     }) {
         tracing::error!("failed to append WAL for create_collection: {:?}", e);
     }
-
-    Ok(Json(CreateCollectionResponse {
-        name: payload.name,
-        dimension: payload.dimension,
-    }))
+    Ok(())
 }
 
-
+/// `X-Collections-Used` and `X-Collections-Limit` for `tenant` under
+/// `Config::max_collections_per_tenant`; empty when there's no limit.
+fn collection_quota_headers(
+    state: &AppState,
+    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
+    tenant: &str,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(max) = state.config.max_collections_per_tenant {
+        let used = collections.get(tenant).map_or(0, HashMap::len);
+        headers.insert("x-collections-used", HeaderValue::from(used));
+        headers.insert("x-collections-limit", HeaderValue::from(max));
+    }
+    headers
+}
 
 pub async fn list_collections(
     State(state): State<AppState>,
     api_key: ApiKey,
-) -> Json<ListCollectionsResponse> {
+) -> (HeaderMap, Json<ListCollectionsResponse>) {
     let tenant = api_key.0;
-    let collections = state.collections.read().await;
+    let collections = state.read_collections().await;
 
     let mut items = Vec::new();
 
@@ -89,21 +240,22 @@ pub async fn list_collections(
         }
     }
 
-    Json(ListCollectionsResponse { collections: items })
+    (
+        collection_quota_headers(&state, &collections, &tenant),
+        Json(ListCollectionsResponse { collections: items }),
+    )
 }
 
-
-
 pub async fn get_collection(
     State(state): State<AppState>,
     api_key: ApiKey,
     Path(name): Path<String>,
-) -> Result<Json<GetCollectionResponse>, (StatusCode, String)> {
+) -> Result<Json<GetCollectionResponse>, ApiError> {
     let tenant = api_key.0;
-    let collections = state.collections.read().await;
+    let collections = state.read_collections().await;
 
     let tenant_map = collections.get(&tenant).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
@@ -115,7 +267,7 @@ pub async fn get_collection(
             dimension: index.dimension(),
             vectors: index.vector_count(),
         })),
-        None => Err((
+        None => Err(ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )),
@@ -126,43 +278,326 @@ pub async fn collection_stats(
     State(state): State<AppState>,
     api_key: ApiKey,
     Path(name): Path<String>,
-) -> Result<Json<CollectionStatsResponse>, (StatusCode, String)> {
+) -> Result<Json<CollectionStatsResponse>, ApiError> {
     let tenant = api_key.0;
-    let collections = state.collections.read().await;
+    let collections = state.read_collections().await;
 
     let tenant_map = collections.get(&tenant).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
     let index = tenant_map.get(&name).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
-    let resp = CollectionStatsResponse {
+    Ok(Json(stats_for(&state, name, index, tenant_map.len())))
+}
+
+/// Stats of every collection of the tenant, by name, from one pass under a
+/// single read lock. Saves dashboards a request per collection.
+pub async fn all_collection_stats(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+) -> Json<AllCollectionStatsResponse> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let mut stats: Vec<CollectionStatsResponse> = collections
+        .get(&tenant)
+        .map(|tenant_map| {
+            tenant_map
+                .iter()
+                .map(|(name, index)| stats_for(&state, name.clone(), index, tenant_map.len()))
+                .collect()
+        })
+        .unwrap_or_default();
+    stats.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Json(AllCollectionStatsResponse { collections: stats })
+}
+
+fn stats_for(
+    state: &AppState,
+    name: String,
+    index: &InMemoryIndex,
+    tenant_collections: usize,
+) -> CollectionStatsResponse {
+    CollectionStatsResponse {
         name,
         dimension: index.dimension(),
         vectors: index.vector_count(),
-        index_type: "hnsw_cosine".to_string(),
+        index_type: index.index_type().to_string(),
+        metadata_bytes: index.metadata_bytes(),
+        approx_memory_bytes: index.approx_memory_bytes(),
+        pending_vectors: index.pending_count(),
+        index_nodes: index.index_nodes(),
+        dead_nodes: index.dead_nodes(),
+        capacity: index.capacity(),
+        normalized_fraction: index.normalized_fraction(),
+        max_vectors: state.config.max_vectors_per_collection,
+        tenant_collections,
+        max_collections: state.config.max_collections_per_tenant,
+    }
+}
+
+/// Distance between two stored vectors, or a stored vector and a supplied
+/// one, under the collection's metric. For debugging scores.
+pub async fn vector_distance(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Json(payload): Json<DistanceRequest>,
+) -> Result<Json<DistanceResponse>, ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let (id, other) = match &payload {
+        DistanceRequest::Pair { id_a, id_b } => (id_a, DistanceTo::Id(id_b)),
+        DistanceRequest::Vector { id, vector } => (id, DistanceTo::Vector(vector)),
+    };
+    let other_id = match other {
+        DistanceTo::Id(other) => Some(other),
+        DistanceTo::Vector(_) => None,
     };
+    if let Some(missing) = std::iter::once(id.as_str())
+        .chain(other_id)
+        .find(|id| !index.contains(id))
+    {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("vector '{}' not found", missing),
+        ));
+    }
 
-    Ok(Json(resp))
+    let distance = index
+        .distance(id, other)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(DistanceResponse {
+        metric: index.metric(),
+        distance,
+        similarity: index.score_for_distance(distance),
+    }))
+}
+
+/// Rebuild a collection's HNSW graph without blocking it: the new index is
+/// built off-lock from a copy of the vectors, writes made meanwhile are
+/// logged, and the write lock is only taken to replay them and swap.
+/// `?capacity=` rebuilds the collection with a new capacity.
+pub async fn reindex_collection(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Path(name): Path<String>,
+    Query(params): Query<ReindexParams>,
+) -> Result<Json<ReindexResponse>, ApiError> {
+    validate_capacity(&state, params.capacity)?;
+    let _task = heavy_task(&state, "reindex")?;
+    reindex(&state, &api_key.0, &name, params.capacity)
+        .await
+        .map(Json)
+}
+
+/// A requested `capacity` must be positive and at most
+/// `Config::max_capacity`.
+fn validate_capacity(state: &AppState, capacity: Option<usize>) -> Result<(), ApiError> {
+    match capacity {
+        Some(0) => Err(ApiError::new(StatusCode::BAD_REQUEST, "capacity must be positive")),
+        Some(capacity) if capacity > state.config.max_capacity => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("capacity must be at most {}", state.config.max_capacity),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Online rebuild behind `reindex_collection` and automatic compaction.
+/// Callers hold a heavy task permit, see `AppState::begin_heavy_task`.
+pub(crate) async fn reindex(
+    state: &AppState,
+    tenant: &str,
+    name: &str,
+    capacity: Option<usize>,
+) -> Result<ReindexResponse, ApiError> {
+    let not_found = || {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("collection '{}' not found", name),
+        )
+    };
+
+    let job = {
+        let mut collections = state.write_collections().await;
+        let index = collections
+            .get_mut(tenant)
+            .and_then(|tenant_map| tenant_map.get_mut(name))
+            .ok_or_else(not_found)?;
+        let nodes = index.vector_count() - index.pending_count();
+        if let Some(capacity) = capacity
+            && capacity < nodes
+        {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("capacity {} is below the collection's {} vectors", capacity, nodes),
+            ));
+        }
+        index
+            .begin_reindex(capacity)
+            .map_err(|e| ApiError::new(StatusCode::CONFLICT, e))?
+    };
+    let token = job.token();
+    let vectors = job.len();
+
+    let started = Instant::now();
+    let config = state.config.clone();
+    let built = state
+        .run_on_index_pool(move || {
+            let mut rebuilt = job.build();
+            map_vectors(&config, &mut rebuilt.index);
+            rebuilt
+        })
+        .await;
+
+    let mut collections = state.write_collections().await;
+    let index = collections
+        .get_mut(tenant)
+        .and_then(|tenant_map| tenant_map.get_mut(name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::CONFLICT,
+                format!("collection '{}' was deleted during the reindex", name),
+            )
+        })?;
+    let rebuilt = match built {
+        Ok(rebuilt) => rebuilt,
+        Err(e) => {
+            index.abort_reindex(token);
+            tracing::error!("reindex task failed: {:?}", e);
+            return Err(ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "reindex failed",
+            ));
+        }
+    };
+    let replayed_writes = index
+        .finish_reindex(rebuilt)
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, e))?;
+    if let Some(capacity) = capacity
+        && let Err(e) = wal_append(state, &WalEntry::SetCapacity {
+            tenant: tenant.to_string(),
+            collection: name.to_string(),
+            capacity,
+        })
+    {
+        tracing::error!("failed to append WAL for set_capacity: {:?}", e);
+    }
+
+    tracing::info!(
+        "reindexed collection '{}' ({} vectors, {} writes replayed) in {:?}",
+        name,
+        vectors,
+        replayed_writes,
+        started.elapsed()
+    );
+    Ok(ReindexResponse {
+        vectors,
+        replayed_writes,
+    })
 }
 
+/// Rebuild a collection in place, dropping the HNSW nodes deletes and
+/// overwrites left behind. Runs online like `/reindex`, keeping the current
+/// capacity.
+pub async fn compact_collection(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Path(name): Path<String>,
+) -> Result<Json<CompactResponse>, ApiError> {
+    let tenant = api_key.0;
+    let _task = heavy_task(&state, "compaction")?;
+    let not_found = || {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("collection '{}' not found", name),
+        )
+    };
+    let nodes_before = state
+        .read_collections()
+        .await
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .map(|index| index.index_nodes())
+        .ok_or_else(not_found)?;
+
+    // Online, like `reindex_collection`: the lock is only held to start the
+    // build and to swap the result in.
+    let started = Instant::now();
+    reindex(&state, &tenant, &name, None).await?;
+
+    let collections = state.read_collections().await;
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(not_found)?;
+    let removed_nodes = nodes_before.saturating_sub(index.index_nodes());
+    tracing::info!(
+        "compacted collection '{}': dropped {} dead nodes in {:?}",
+        name,
+        removed_nodes,
+        started.elapsed()
+    );
+    Ok(Json(CompactResponse {
+        vectors: index.vector_count(),
+        removed_nodes,
+    }))
+}
 
+/// After a write, start an online rebuild of the collection in the
+/// background if it has piled up too many dead HNSW nodes.
+fn maybe_compact(state: &AppState, tenant: &str, name: &str, index: &InMemoryIndex) {
+    // With a maintenance window the scheduler compacts instead.
+    if !state.config.auto_compact
+        || state.config.compaction_window.is_some()
+        || !index.needs_compaction()
+        || index.reindex_running()
+    {
+        return;
+    }
+    let (state, tenant, name) = (state.clone(), tenant.to_string(), name.to_string());
+    let dead = index.dead_nodes();
+    tokio::spawn(async move {
+        // The next write retries.
+        let Some(_task) = state.try_begin_heavy_task("auto-compaction") else {
+            return;
+        };
+        match reindex(&state, &tenant, &name, None).await {
+            Ok(_) => tracing::info!("auto-compacted collection '{}' ({} dead nodes)", name, dead),
+            // Typically another rebuild got there first.
+            Err(e) => tracing::debug!("skipped auto-compaction of '{}': {}", name, e),
+        }
+    });
+}
 
 pub async fn delete_collection(
     State(state): State<AppState>,
-    api_key: ApiKey,
+    api_key: WriteKey,
     Path(name): Path<String>,
-) -> Result<Json<DeleteCollectionResponse>, (StatusCode, String)> {
+) -> Result<(HeaderMap, Json<DeleteCollectionResponse>), ApiError> {
     let tenant = api_key.0;
-    let mut collections = state.collections.write().await;
+    let mut collections = state.write_collections().await;
 
     let existed = if let Some(tenant_map) = collections.get_mut(&tenant) {
         let removed = tenant_map.remove(&name).is_some();
@@ -175,74 +610,431 @@ pub async fn delete_collection(
     };
 
     if !existed {
-        return Err((
+        return Err(ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         ));
     }
 
-    if let Err(e) = append_entry(&WalEntry::DeleteCollection {
+    if let Err(e) = wal_append(&state, &WalEntry::DeleteCollection {
         tenant: tenant.clone(),
         name: name.clone(),
     }) {
         tracing::error!("failed to append WAL for delete_collection: {:?}", e);
     }
+    state.forget_eventual_results(&tenant, &name);
+    state.audit(&tenant, &name, "delete_collection", 0);
 
-    Ok(Json(DeleteCollectionResponse { deleted: true }))
+    Ok((
+        collection_quota_headers(&state, &collections, &tenant),
+        Json(DeleteCollectionResponse { deleted: true }),
+    ))
 }
 
+/// Bulk cleanup, e.g. of `test_*` collections. One `DeleteCollection` WAL
+/// entry is written per deleted collection, so replay needs nothing new.
+pub async fn delete_collections_by_prefix(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Json(payload): Json<DeleteByPrefixRequest>,
+) -> Result<Json<DeleteByPrefixResponse>, ApiError> {
+    if payload.prefix.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "prefix must not be empty"));
+    }
+    if !payload.confirm {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "set \"confirm\": true to delete collections by prefix",
+        ));
+    }
+
+    let tenant = api_key.0;
+    let mut collections = state.write_collections().await;
+
+    let mut deleted = Vec::new();
+    if let Some(tenant_map) = collections.get_mut(&tenant) {
+        tenant_map.retain(|name, _| {
+            let matches = name.starts_with(&payload.prefix);
+            if matches {
+                deleted.push(name.clone());
+            }
+            !matches
+        });
+        if tenant_map.is_empty() {
+            collections.remove(&tenant);
+        }
+    }
+    deleted.sort();
+
+    for name in &deleted {
+        if let Err(e) = wal_append(&state, &WalEntry::DeleteCollection {
+            tenant: tenant.clone(),
+            name: name.clone(),
+        }) {
+            tracing::error!("failed to append WAL for delete_collection: {:?}", e);
+        }
+        state.forget_eventual_results(&tenant, name);
+        state.audit(&tenant, name, "delete_collection", 0);
+    }
 
+    Ok(Json(DeleteByPrefixResponse { deleted }))
+}
 
 // ---------- upsert ----------
 
 pub async fn upsert_vectors(
     State(state): State<AppState>,
-    api_key: ApiKey,
+    api_key: WriteKey,
     Path(name): Path<String>,
+    Query(params): Query<UpsertParams>,
     Json(payload): Json<UpsertRequest>,
-) -> Result<Json<UpsertResponse>, (StatusCode, String)> {
+) -> Result<(StatusCode, HeaderMap, Json<UpsertResponse>), ApiError> {
     let tenant = api_key.0;
-    let mut collections = state.collections.write().await;
+    let _slot = state.try_begin_upsert(&tenant).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "too many concurrent upserts for this tenant",
+        )
+    })?;
+
+    if params.bulk {
+        return bulk_upsert(&state, tenant, name, payload)
+            .await
+            .map(|(headers, resp)| (StatusCode::OK, headers, resp));
+    }
+
+    let mut collections = state.write_collections().await;
 
     let tenant_map = collections.get_mut(&tenant).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
     let index = tenant_map.get_mut(&name).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
-    let mut count = 0usize;
+    // Each vector stands alone: invalid ones are reported, the rest applied.
+    let mut results = Vec::with_capacity(payload.vectors.len());
+    let mut skipped = 0;
+    let mut wal_lines = String::new();
+    let mut wal_entries = 0;
     for v in payload.vectors {
         let id = v.id;
         let values = v.values;
         let metadata = v.metadata;
 
-        index
-            .upsert(id.clone(), values.clone(), metadata.clone())
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
-        count += 1;
+        if index.config().zero_vectors.skips(&values) {
+            skipped += 1;
+            continue;
+        }
+
+        if let Err(e) = validate_metadata_size(&metadata, state.config.max_metadata_bytes) {
+            results.push(ItemStatus::failed(id, StatusCode::BAD_REQUEST, e));
+            continue;
+        }
+        if let Some(expected) = v.if_version
+            && let Err(e) = index.check_version(&id, expected)
+        {
+            results.push(ItemStatus::failed(id, StatusCode::CONFLICT, e));
+            continue;
+        }
+        if let Err(e) = index.check_insert(&id) {
+            results.push(ItemStatus::failed(id, StatusCode::CONFLICT, e));
+            continue;
+        }
+        if let Err(e) = index.check_duplicate(&id, &values) {
+            results.push(ItemStatus::failed(id, StatusCode::CONFLICT, e));
+            continue;
+        }
+        let new_vectors = usize::from(!index.contains(&id));
+        if let Err(e) = check_vector_limit(&state, index, new_vectors) {
+            results.push(ItemStatus::failed(id, e.status, e.message));
+            continue;
+        }
+        if !values.is_empty()
+            && let Err(e) = index.check_capacity(1)
+        {
+            results.push(ItemStatus::failed(id, StatusCode::INSUFFICIENT_STORAGE, e));
+            continue;
+        }
+        let dim_before = index.dimension();
+        let written_at = now_millis();
+        if let Err(e) = index.upsert_at(id.clone(), values.clone(), metadata.clone(), written_at) {
+            results.push(ItemStatus::failed(id, StatusCode::BAD_REQUEST, e));
+            continue;
+        }
+
+        if dim_before == 0 && index.dimension() != 0 {
+            let entry = WalEntry::SetDimension {
+                tenant: tenant.clone(),
+                collection: name.clone(),
+                dimension: index.dimension(),
+            };
+            match encode_entry(&entry, &mut wal_lines) {
+                Ok(()) => wal_entries += 1,
+                Err(e) => tracing::error!("failed to encode WAL for set_dimension: {:?}", e),
+            }
+        }
 
-        if let Err(e) = append_entry(&WalEntry::UpsertVector {
+        let entry = WalEntry::UpsertVector {
             tenant: tenant.clone(),
             collection: name.clone(),
-            id,
+            id: id.clone(),
             values,
             metadata,
-        }) {
-            tracing::error!("failed to append WAL for upsert_vector: {:?}", e);
+            written_at: Some(written_at),
+            version: index.version(&id),
+        };
+        match encode_entry(&entry, &mut wal_lines) {
+            Ok(()) => wal_entries += 1,
+            Err(e) => tracing::error!("failed to encode WAL for upsert_vector: {:?}", e),
         }
+        results.push(ItemStatus::ok(id));
+    }
+    // One write (and, under `WalSync::Always`, one fsync) for the batch.
+    if wal_entries > 0
+        && let Err(e) = wal_append_encoded(&state, &wal_lines, wal_entries)
+    {
+        tracing::error!("failed to append WAL for upsert_vector: {:?}", e);
+    }
+    maybe_compact(&state, &tenant, &name, index);
+
+    let (status, Json(batch)) = batch_response(results);
+    state.audit(&tenant, &name, "upsert", batch.succeeded);
+    Ok((
+        status,
+        vector_quota_headers(&state, index),
+        Json(UpsertResponse {
+            upserted: batch.succeeded,
+            batch,
+            skipped,
+        }),
+    ))
+}
+
+/// Refuse a `top_k` over `Config::max_top_k` and an `ef_search` under
+/// `top_k` or over `Config::ef_search_max`: HNSW allocates its candidate
+/// heaps up front, so an unbounded breadth could exhaust memory.
+fn check_search_breadth(
+    state: &AppState,
+    top_k: usize,
+    ef_search: Option<usize>,
+) -> Result<(), ApiError> {
+    if top_k > state.config.max_top_k {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("top_k must be at most {}", state.config.max_top_k),
+        ));
+    }
+    match ef_search {
+        Some(ef) if ef < top_k => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "ef_search must be at least top_k",
+        )),
+        Some(ef) if ef > state.config.ef_bounds().max => Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("ef_search must be at most {}", state.config.ef_bounds().max),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Refuse a write that would add `new_vectors` past
+/// `Config::max_vectors_per_collection`.
+fn check_vector_limit(
+    state: &AppState,
+    index: &InMemoryIndex,
+    new_vectors: usize,
+) -> Result<(), ApiError> {
+    match state.config.max_vectors_per_collection {
+        Some(max) if index.vector_count() + new_vectors > max => Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!(
+                "collection would exceed its limit of {} vectors ({} stored, {} new)",
+                max,
+                index.vector_count(),
+                new_vectors
+            ),
+        )),
+        _ => Ok(()),
     }
+}
 
-    Ok(Json(UpsertResponse { upserted: count }))
+/// `X-Vectors-Used` and `X-Vectors-Limit` for `index` under
+/// `Config::max_vectors_per_collection`; empty when there's no limit.
+fn vector_quota_headers(state: &AppState, index: &InMemoryIndex) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(max) = state.config.max_vectors_per_collection {
+        headers.insert("x-vectors-used", HeaderValue::from(index.vector_count()));
+        headers.insert("x-vectors-limit", HeaderValue::from(max));
+    }
+    headers
 }
 
+/// Bulk path for `upsert_vectors`: validate + WAL-encode the whole batch
+/// without holding the collections lock, then take the write lock once to
+/// merge it and append the WAL in a single write.
+///
+/// Unlike the per-vector path this is all-or-nothing: one invalid vector
+/// rejects the batch before anything is applied.
+async fn bulk_upsert(
+    state: &AppState,
+    tenant: String,
+    name: String,
+    payload: UpsertRequest,
+) -> Result<(HeaderMap, Json<UpsertResponse>), ApiError> {
+    let (dim, config, quota) = {
+        let collections = state.read_collections().await;
+        collections
+            .get(&tenant)
+            .and_then(|tenant_map| tenant_map.get(&name))
+            .map(|index| {
+                (index.dimension(), index.config().clone(), vector_quota_headers(state, index))
+            })
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("collection '{}' not found", name),
+                )
+            })?
+    };
+
+    let started = Instant::now();
+    let mut wal_lines = String::new();
+
+    // A collection without a dimension takes it from the batch's first
+    // vector with values (pending ones have none).
+    let inferred = payload
+        .vectors
+        .iter()
+        .map(|v| v.values.len())
+        .find(|&n| n > 0)
+        .unwrap_or(0);
+    let infer_dim = dim == 0 && inferred != 0;
+    let dim = if infer_dim {
+        let entry = WalEntry::SetDimension {
+            tenant: tenant.clone(),
+            collection: name.clone(),
+            dimension: inferred,
+        };
+        if let Err(e) = encode_entry(&entry, &mut wal_lines) {
+            tracing::error!("failed to encode WAL for bulk upsert: {:?}", e);
+        }
+        inferred
+    } else {
+        dim
+    };
+    let zero_vectors = config.zero_vectors;
+    let mut batch = StagedBatch::new(dim, config);
+    // `if_version`s can only be checked under the write lock, before merging.
+    let mut expected_versions = Vec::new();
+    let mut skipped = 0;
+
+    for (i, v) in payload.vectors.into_iter().enumerate() {
+        if zero_vectors.skips(&v.values) {
+            skipped += 1;
+            continue;
+        }
+        validate_metadata_size(&v.metadata, state.config.max_metadata_bytes)
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("vector {}: {}", i, e)))?;
+        let entry = WalEntry::UpsertVector {
+            tenant: tenant.clone(),
+            collection: name.clone(),
+            id: v.id.clone(),
+            values: v.values.clone(),
+            metadata: v.metadata.clone(),
+            written_at: Some(batch.written_at()),
+            version: None,
+        };
+        if let Err(e) = encode_entry(&entry, &mut wal_lines) {
+            tracing::error!("failed to encode WAL for bulk upsert: {:?}", e);
+        }
+
+        if let Some(expected) = v.if_version {
+            expected_versions.push((i, v.id.clone(), expected));
+        }
+        batch
+            .push(v.id, v.values, v.metadata)
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("vector {}: {}", i, e)))?;
+    }
+
+    if batch.is_empty() {
+        return Ok((quota, Json(UpsertResponse::bulk(0, skipped))));
+    }
+    let staged_in = started.elapsed();
+
+    let mut collections = state.write_collections().await;
+    let index = collections
+        .get_mut(&tenant)
+        .and_then(|tenant_map| tenant_map.get_mut(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    if index.config().mode == WriteMode::InsertOnly {
+        let mut seen = HashSet::new();
+        for (i, id) in batch.ids().enumerate() {
+            if !seen.insert(id) {
+                return Err(ApiError::new(
+                    StatusCode::CONFLICT,
+                    format!("vector {}: '{}' appears twice in the batch", i, id),
+                ));
+            }
+            index
+                .check_insert(id)
+                .map_err(|e| ApiError::new(StatusCode::CONFLICT, format!("vector {}: {}", i, e)))?;
+        }
+    }
+    for (i, (id, values)) in batch.values().enumerate() {
+        index
+            .check_duplicate(id, values)
+            .map_err(|e| ApiError::new(StatusCode::CONFLICT, format!("vector {}: {}", i, e)))?;
+    }
+    let batch_ids: HashSet<&str> = batch.ids().collect();
+    let new_vectors = batch_ids.iter().filter(|id| !index.contains(id)).count();
+    check_vector_limit(state, index, new_vectors)?;
+    for (i, id, expected) in &expected_versions {
+        index
+            .check_version(id, *expected)
+            .map_err(|e| ApiError::new(StatusCode::CONFLICT, format!("vector {}: {}", i, e)))?;
+    }
+    index
+        .check_capacity(batch.nodes())
+        .map_err(|e| ApiError::new(StatusCode::INSUFFICIENT_STORAGE, e))?;
+
+    let parallel = state.config.parallel_insert
+        && batch.len() >= state.config.parallel_insert_min_batch;
+
+    let merge_started = Instant::now();
+    let count = state
+        .install_on_index_pool(|| index.merge(batch, parallel))
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, e))?;
+
+    if let Err(e) = wal_append_encoded(state, &wal_lines, count + usize::from(infer_dim)) {
+        tracing::error!("failed to append WAL for bulk upsert: {:?}", e);
+    }
+    maybe_compact(state, &tenant, &name, index);
+    state.audit(&tenant, &name, "upsert", count);
+
+    tracing::debug!(
+        "bulk upsert of {} vectors: staged in {:?}, merged under lock in {:?} (parallel: {})",
+        count,
+        staged_in,
+        merge_started.elapsed(),
+        parallel
+    );
+
+    Ok((vector_quota_headers(state, index), Json(UpsertResponse::bulk(count, skipped))))
+}
 
 // ---------- query ----------
 
@@ -250,72 +1042,1049 @@ pub async fn query_vectors(
     State(state): State<AppState>,
     api_key: ApiKey,
     Path(name): Path<String>,
+    Query(params): Query<QueryParams>,
+    headers: HeaderMap,
     Json(payload): Json<QueryRequest>,
-) -> Result<Json<QueryResponse>, (StatusCode, String)> {
+) -> Result<Response, ApiError> {
     let tenant = api_key.0;
-    let collections = state.collections.read().await;
+    if let Some(stamp) = params.snapshot {
+        return query_history(&state, tenant, name, stamp, &headers, payload).await;
+    }
+    // An identical `eventual` query answered recently: reuse its result
+    // without taking the lock.
+    let eventual_key = (payload.consistency == Consistency::Eventual).then(|| {
+        let body = serde_json::to_string(&payload).unwrap_or_default();
+        (tenant.clone(), name.clone(), body)
+    });
+    if let Some(key) = &eventual_key
+        && let Some((generation, resp)) = state.cached_eventual_result(key)
+    {
+        let etag = query_etag(&tenant, &name, generation, &payload, &headers);
+        let max_age = state.config.query_cache_max_age_secs;
+        if etag_matches(&headers, &etag) {
+            let mut resp = StatusCode::NOT_MODIFIED.into_response();
+            set_cache_headers(&mut resp, &etag, max_age);
+            return Ok(resp);
+        }
+        state.audit(&tenant, &name, "query", resp.matches.len());
+        let mut response = query_response(resp, &headers, payload.empty_as_204);
+        set_cache_headers(&mut response, &etag, max_age);
+        return Ok(response);
+    }
+    let collections = state.read_collections().await;
 
     let tenant_map = collections.get(&tenant).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
     let index = tenant_map.get(&name).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
-    let scored = if let Some(filter_val) = payload.filter {
-        let filter_obj = filter_val.as_object().ok_or((
+    let _permit = acquire_query_slot(&state, index, &name)?;
+
+    // Same collection generation + same request = same response.
+    let etag = query_etag(&tenant, &name, index.generation(), &payload, &headers);
+    if etag_matches(&headers, &etag) {
+        let mut resp = StatusCode::NOT_MODIFIED.into_response();
+        set_cache_headers(&mut resp, &etag, state.config.query_cache_max_age_secs);
+        return Ok(resp);
+    }
+
+    let empty_as_204 = payload.empty_as_204;
+    let resp = run_query(&state, &tenant, &name, index, payload)?;
+    if let Some(key) = eventual_key {
+        state.cache_eventual_result(key, index.generation(), resp.clone());
+    }
+    let mut response = query_response(resp, &headers, empty_as_204);
+    set_cache_headers(&mut response, &etag, state.config.query_cache_max_age_secs);
+    Ok(response)
+}
+
+/// `query_vectors` as Server-Sent Events, for progressive rendering: one
+/// `match` event per result, best first, then a `done` event, after which
+/// the stream ends. The search runs up front, so the read lock isn't held
+/// while a slow client drains the events.
+pub async fn query_vectors_sse(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Query(params): Query<SseQueryParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let vector = params
+        .vector
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            ApiError::new(StatusCode::BAD_REQUEST, "vector must be comma-separated numbers")
+        })?;
+    let filter = params
+        .filter
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("invalid filter: {}", e)))?;
+    let mut payload = QueryRequest::new(vector, params.top_k);
+    payload.filter = filter;
+    payload.min_score = params.min_score;
+    payload.ef_search = params.ef_search;
+
+    let tenant = api_key.0;
+    let matches = {
+        let collections = state.read_collections().await;
+        let index = collections
+            .get(&tenant)
+            .and_then(|tenant_map| tenant_map.get(&name))
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("collection '{}' not found", name),
+                )
+            })?;
+        let _permit = acquire_query_slot(&state, index, &name)?;
+        run_query(&state, &tenant, &name, index, payload)?.matches
+    };
+
+    let end = QueryStreamEnd {
+        matches: matches.len(),
+    };
+    let events = matches
+        .into_iter()
+        .map(|m| Event::default().event("match").json_data(m))
+        .chain(std::iter::once(Event::default().event("done").json_data(end)));
+    Ok(Sse::new(futures_util::stream::iter(events)))
+}
+
+/// `query_vectors` against the collection as of a history snapshot, for
+/// reproducible evaluation. Read-only: the collection is loaded into a
+/// throwaway index that is dropped after the query, see
+/// `load_historical_collection` for the memory this takes.
+async fn query_history(
+    state: &AppState,
+    tenant: String,
+    name: String,
+    stamp: u64,
+    headers: &HeaderMap,
+    payload: QueryRequest,
+) -> Result<Response, ApiError> {
+    let data_dir = state.config.data_dir.clone();
+    let (t, n) = (tenant.clone(), name.clone());
+    let loaded = state
+        .run_on_index_pool(move || load_historical_collection(&data_dir, stamp, &t, &n))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map_err(|e| {
+            tracing::error!("failed to load history snapshot {}: {:?}", stamp, e);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to load snapshot",
+            )
+        })?;
+    let index = loaded.ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("collection '{}' not found in snapshot {}", name, stamp),
+        )
+    })?;
+
+    let empty_as_204 = payload.empty_as_204;
+    let resp = run_query(state, &tenant, &name, &index, payload)?;
+    Ok(query_response(resp, headers, empty_as_204))
+}
+
+/// Render a query result in the format the client asked for.
+fn query_response(resp: QueryResponse, headers: &HeaderMap, empty_as_204: bool) -> Response {
+    if empty_as_204 && resp.matches.is_empty() {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+    match query_format(headers) {
+        QueryFormat::Csv => (
+            [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            query_response_csv(&resp),
+        )
+            .into_response(),
+        QueryFormat::Minimal => Json(resp.minimal()).into_response(),
+        QueryFormat::Json => Json(resp).into_response(),
+    }
+}
+
+/// Run several queries against one collection under a single read lock
+/// and collection lookup. Each query succeeds or fails on its own; results
+/// come back in input order.
+pub async fn query_batch(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Json(payload): Json<QueryBatchRequest>,
+) -> Result<(StatusCode, Json<QueryBatchResponse>), ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let _permit = acquire_query_slot(&state, index, &name)?;
+
+    let dim = index.dimension();
+    let results: Vec<QueryBatchItem> = payload
+        .queries
+        .into_iter()
+        .map(|query| {
+            if dim != 0 && query.vector.len() != dim {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "expected query vector of dimension {}, got {}",
+                        dim,
+                        query.vector.len()
+                    ),
+                ));
+            }
+            run_query(&state, &tenant, &name, index, query)
+        })
+        .map(|result| QueryBatchItem::from_result(result.map_err(ApiError::into_parts)))
+        .collect();
+
+    let status = batch_status(results.iter().map(|r| r.status));
+    let succeeded = results.iter().filter(|r| r.response.is_some()).count();
+    Ok((
+        status,
+        Json(QueryBatchResponse {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        }),
+    ))
+}
+
+/// Everything a query does once its collection is found: validate the
+/// options, search, then boost, order, group and page the matches.
+fn run_query(
+    state: &AppState,
+    tenant: &str,
+    name: &str,
+    index: &InMemoryIndex,
+    payload: QueryRequest,
+) -> Result<QueryResponse, ApiError> {
+    let mut boosts = Vec::with_capacity(payload.boosts.len());
+    for b in payload.boosts {
+        let Some(filter) = b.filter.as_object() else {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "boost filter must be a JSON object",
+            ));
+        };
+        if !(b.factor.is_finite() && b.factor > 0.0) {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "boost factor must be a positive number",
+            ));
+        }
+        boosts.push(ScoreBoost {
+            filter: filter.clone(),
+            factor: b.factor,
+        });
+    }
+    if payload.score_mode == ScoreMode::Angular && !index.metric().is_cosine() {
+        return Err(ApiError::new(
             StatusCode::BAD_REQUEST,
-            "filter must be a JSON object".into(),
+            "score_mode 'angular' requires a cosine metric",
+        ));
+    }
+    if !boosts.is_empty() && payload.score_mode == ScoreMode::Angular {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "boosts are not supported with score_mode 'angular'",
+        ));
+    }
+
+    check_search_breadth(state, payload.top_k, payload.ef_search)?;
+
+    if let Some(min) = payload.min_score
+        && !min.is_finite()
+    {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "min_score must be a number"));
+    }
+
+    if payload.group_by.is_some() && payload.group_size == 0 {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "group_size must be greater than 0",
+        ));
+    }
+
+    let after = match &payload.cursor {
+        Some(_) if payload.group_by.is_some() => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "cursor cannot be combined with group_by",
+            ));
+        }
+        Some(_) if payload.sort_by.is_some() => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "cursor cannot be combined with sort_by",
+            ));
+        }
+        Some(cursor) => Some(QueryCursor::decode(cursor).ok_or(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid cursor",
+        ))?),
+        None => None,
+    };
+    // A page after the cursor is a slice of the top `returned + top_k`.
+    let returned = after.as_ref().map_or(0, |c| c.returned);
+
+    // Boosts can promote candidates from below the cut and grouping drops
+    // candidates, so over-fetch for either.
+    let mut fetch_k = payload.top_k.saturating_add(returned);
+    if !boosts.is_empty() {
+        fetch_k = fetch_k.saturating_mul(4);
+    }
+    if payload.group_by.is_some() {
+        fetch_k = fetch_k.saturating_mul(10);
+    }
+
+    let has_filter = payload.filter.is_some();
+    let search_started = Instant::now();
+    let result = if payload.exact {
+        let filter = match &payload.filter {
+            None => serde_json::Map::new(),
+            Some(serde_json::Value::Object(map)) => map.clone(),
+            Some(_) => {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "filter must be a JSON object",
+                ));
+            }
+        };
+        let deadline =
+            Instant::now() + Duration::from_millis(state.config.query_timeout_ms);
+        index
+            .query_exact(
+                &payload.vector,
+                fetch_k,
+                &filter,
+                Some(deadline),
+                payload.include_values,
+            )
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?
+            .ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    format!(
+                        "exact query exceeded the {} ms query timeout",
+                        state.config.query_timeout_ms
+                    ),
+                )
+            })?
+    } else if let Some(filter_val) = payload.filter {
+        let filter_obj = filter_val.as_object().ok_or(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "filter must be a JSON object",
         ))?;
         index
-            .query_with_filter(&payload.vector, payload.top_k, filter_obj)
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+            .query_with_filter(
+                &payload.vector,
+                fetch_k,
+                filter_obj,
+                payload.min_score,
+                payload.ef_search,
+                state.config.ef_bounds(),
+                payload.include_values,
+            )
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?
     } else {
         index
-            .query(&payload.vector, payload.top_k)
-            .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+            .query(
+                &payload.vector,
+                fetch_k,
+                payload.min_score,
+                payload.ef_search,
+                state.config.ef_bounds(),
+                payload.include_values,
+            )
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?
+    };
+
+    // Parameters only: the query vector itself is never logged.
+    let searched_in = search_started.elapsed();
+    if let Some(slow_ms) = state.config.slow_query_ms
+        && searched_in >= Duration::from_millis(slow_ms)
+    {
+        tracing::warn!(
+            "slow query on collection '{}': top_k {}, ef_search {:?}, filter {}, exact {}, took {} ms",
+            name,
+            payload.top_k,
+            payload.ef_search,
+            has_filter,
+            payload.exact,
+            searched_in.as_millis()
+        );
+    }
+
+    if result.exact_fallback {
+        tracing::warn!(
+            "query on collection '{}' (tenant {}) served by exact fallback scan",
+            name,
+            tenant
+        );
+    }
+
+    let mut scored = result.points;
+    // HNSW searches stop at `min_score`; exact scans return the plain top.
+    if let Some(min) = payload.min_score {
+        scored.retain(|p| p.score >= min);
+    }
+    // Pre-boost similarities, for `explain`.
+    let vector_scores: HashMap<String, f32> = if payload.explain {
+        scored.iter().map(|p| (p.id.clone(), p.score)).collect()
+    } else {
+        HashMap::new()
+    };
+    apply_boosts(&mut scored, &boosts);
+    match &payload.sort_by {
+        Some(sort) => sort_by_field(&mut scored, &sort.field, sort.order == SortOrder::Desc),
+        None => sort_for_paging(&mut scored),
+    }
+    if let Some(field) = &payload.group_by {
+        limit_per_group(&mut scored, field, payload.group_size);
+    }
+    if let Some(after) = &after {
+        search_after(&mut scored, after.score, &after.id);
+    }
+    scored.truncate(payload.top_k);
+
+    // A full page may have more behind it.
+    let cursor = match scored.last() {
+        Some(last) if scored.len() == payload.top_k => Some(
+            QueryCursor {
+                returned: returned + scored.len(),
+                score: last.score,
+                id: last.id.clone(),
+            }
+            .encode(),
+        ),
+        _ => None,
     };
 
+    let report = |score: f32| {
+        round_score(
+            match payload.score_mode {
+                ScoreMode::Cosine => score,
+                ScoreMode::Angular => angular_distance(score),
+            },
+            state.config.score_decimals,
+        )
+    };
+    let times = |id: &str| {
+        payload
+            .include_timestamps
+            .then(|| index.timestamps(id))
+            .flatten()
+    };
     let matches: Vec<QueryMatch> = scored
         .into_iter()
         .map(|sp| QueryMatch {
+            score: report(sp.score),
+            distance: sp.distance,
+            score_components: payload.explain.then(|| ScoreComponents {
+                vector: report(vector_scores[&sp.id]),
+                boost: boost_factor(&sp.metadata, &boosts),
+            }),
+            created_at: times(&sp.id).map(|t| t.created_at),
+            updated_at: times(&sp.id).map(|t| t.updated_at),
+            norm: payload.include_norm.then(|| index.norm(&sp.id)).flatten(),
             id: sp.id,
-            score: sp.score,
             metadata: sp.metadata,
+            values: sp.values,
         })
         .collect();
+    state.audit(tenant, name, "query", matches.len());
+
+    Ok(QueryResponse {
+        matches,
+        exact: result.exact,
+        exact_fallback: result.exact_fallback,
+        debug: payload.debug.then_some(QueryDebug {
+            visited: result.visited,
+            ef: result.ef,
+        }),
+        cursor,
+        metric: Some(index.metric()),
+    })
+}
+
+/// Position after the last match of a page: the raw (boosted, unrounded)
+/// score and id of that match, plus how many matches came before it.
+struct QueryCursor {
+    returned: usize,
+    score: f32,
+    id: String,
+}
+
+impl QueryCursor {
+    /// Opaque to clients: hex of `returned:score_bits:id`.
+    fn encode(&self) -> String {
+        hex_encode(&format!("{}:{:08x}:{}", self.returned, self.score.to_bits(), self.id))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = hex_decode(cursor)?;
+        let mut parts = raw.splitn(3, ':');
+        let returned = parts.next()?.parse().ok()?;
+        let score = f32::from_bits(u32::from_str_radix(parts.next()?, 16).ok()?);
+        let id = parts.next()?.to_string();
+        Some(Self {
+            returned,
+            score,
+            id,
+        })
+    }
+}
+
+/// Cursors are hex so clients treat them as opaque.
+fn hex_encode(raw: &str) -> String {
+    raw.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(cursor: &str) -> Option<String> {
+    if !cursor.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Take one of the collection's `collection_query_concurrency` query slots,
+/// so one busy collection can't occupy every worker.
+fn acquire_query_slot<'a>(
+    state: &AppState,
+    index: &'a InMemoryIndex,
+    name: &str,
+) -> Result<Option<QueryPermit<'a>>, ApiError> {
+    let Some(limit) = state.config.collection_query_concurrency else {
+        return Ok(None);
+    };
+    index.try_acquire_query(limit).map(Some).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("too many concurrent queries on collection '{}'", name),
+        )
+    })
+}
+
+/// Strong ETag for a query: a hash of everything the response depends on.
+fn query_etag(
+    tenant: &str,
+    collection: &str,
+    generation: u64,
+    payload: &QueryRequest,
+    headers: &HeaderMap,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    tenant.hash(&mut hasher);
+    collection.hash(&mut hasher);
+    generation.hash(&mut hasher);
+    serde_json::to_string(payload)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    query_format(headers).hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// True when `If-None-Match` lists `etag` (or is `*`).
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == etag || tag == "*")
+}
+
+/// `ETag` plus `Cache-Control`: cacheable for `max_age_secs`, or
+/// revalidate every time (`no-cache`) when unset. Responses differ per
+/// tenant and representation, hence the `Vary`.
+fn set_cache_headers(resp: &mut Response, etag: &str, max_age_secs: Option<u64>) {
+    let cache_control = match max_age_secs {
+        Some(secs) => format!("max-age={}", secs),
+        None => "no-cache".to_string(),
+    };
+    let headers = resp.headers_mut();
+    if let Ok(v) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, v);
+    }
+    headers.insert(header::VARY, HeaderValue::from_static("x-api-key, accept"));
+}
+
+/// Round a reported score to the configured number of decimals. serde_json
+/// writes the shortest representation that round-trips the f32, so the
+/// rounded value serializes without trailing float noise.
+fn round_score(score: f32, decimals: Option<u32>) -> f32 {
+    match decimals {
+        Some(d) => {
+            let scale = 10f64.powi(d.min(9) as i32);
+            ((score as f64 * scale).round() / scale) as f32
+        }
+        None => score,
+    }
+}
+
+/// Representation of a query response, negotiated via `Accept`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum QueryFormat {
+    Json,
+    /// `application/json; profile=minimal`: ids and scores only.
+    Minimal,
+    Csv,
+}
+
+/// First of `text/csv` or `application/json; profile=minimal` the client's
+/// `Accept` header lists; plain JSON otherwise.
+fn query_format(headers: &HeaderMap) -> QueryFormat {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .find_map(|media| {
+            let mut parts = media.split(';').map(str::trim);
+            match parts.next().unwrap_or("") {
+                "text/csv" => Some(QueryFormat::Csv),
+                "application/json"
+                    if parts.any(|p| p.replace(' ', "") == "profile=minimal") =>
+                {
+                    Some(QueryFormat::Minimal)
+                }
+                _ => None,
+            }
+        })
+        .unwrap_or(QueryFormat::Json)
+}
+
+/// Render matches as `id,score,metadata_json` rows. Metadata is embedded as
+/// a JSON string column (empty when absent).
+fn query_response_csv(resp: &QueryResponse) -> String {
+    let mut out = String::from("id,score,metadata_json\n");
+    for m in &resp.matches {
+        let metadata = m
+            .metadata
+            .as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        out.push_str(&csv_field(&m.id));
+        out.push(',');
+        out.push_str(&m.score.to_string());
+        out.push(',');
+        out.push_str(&csv_field(&metadata));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline.
+fn csv_field(raw: &str) -> String {
+    if raw.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw.to_string()
+    }
+}
+
+pub async fn count_query(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Json(payload): Json<CountQueryRequest>,
+) -> Result<Json<CountResponse>, ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+    let _permit = acquire_query_slot(&state, index, &name)?;
+
+    let filter = match payload.filter {
+        None => serde_json::Map::new(),
+        Some(serde_json::Value::Object(map)) => map,
+        Some(_) => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "filter must be a JSON object",
+            ));
+        }
+    };
+
+    let count = index
+        .count_matching(payload.vector.as_deref(), &filter, payload.min_score)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    state.audit(&tenant, &name, "count", count);
+
+    Ok(Json(CountResponse { count }))
+}
+
+/// How many vectors match a metadata filter, without paging through them.
+pub async fn count_vectors(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Json(payload): Json<CountRequest>,
+) -> Result<Json<CountResponse>, ApiError> {
+    if payload.filter.as_ref().is_some_and(|f| !f.is_object()) {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "filter must be a JSON object",
+        ));
+    }
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+    let _permit = acquire_query_slot(&state, index, &name)?;
+
+    let count = index.count(payload.filter.as_ref());
+    state.audit(&tenant, &name, "count", count);
+
+    Ok(Json(CountResponse { count }))
+}
+
+/// Predict what a query would cost without running it. Never takes a
+/// query slot and never touches HNSW.
+pub async fn estimate_query(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Json(payload): Json<QueryEstimateRequest>,
+) -> Result<Json<QueryEstimateResponse>, ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let filter = match payload.filter {
+        None => serde_json::Map::new(),
+        Some(serde_json::Value::Object(map)) => map,
+        Some(_) => {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "filter must be a JSON object",
+            ));
+        }
+    };
+
+    check_search_breadth(&state, payload.top_k, payload.ef_search)?;
+
+    let estimate = index.estimate_query(
+        payload.top_k,
+        &filter,
+        payload.exact,
+        payload.ef_search,
+        state.config.ef_bounds(),
+    );
+    let latency_class = match estimate.candidates {
+        n if n < 10_000 => LatencyClass::Fast,
+        n if n < 250_000 => LatencyClass::Moderate,
+        _ => LatencyClass::Slow,
+    };
+    Ok(Json(QueryEstimateResponse {
+        vectors: index.vector_count(),
+        candidates: estimate.candidates,
+        filter_selectivity: estimate.selectivity,
+        expected_matches: estimate.expected_matches,
+        exact: payload.exact,
+        latency_class,
+    }))
+}
+
+// ---------- scroll ----------
+
+/// Page through a collection's vectors in id order. The cursor is the last
+/// id of the previous page, so deletes between pages skip nothing else.
+pub async fn scroll_vectors(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Query(params): Query<ScrollParams>,
+) -> Result<Json<ScrollResponse>, ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let limit = params.limit.unwrap_or(100).min(1000);
+    if limit == 0 {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "limit must be greater than 0",
+        ));
+    }
+    let after = match &params.cursor {
+        Some(cursor) => Some(hex_decode(cursor).ok_or(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid cursor",
+        ))?),
+        None => None,
+    };
+
+    let (ids, more) = index.scroll(after.as_deref(), limit);
+    let next_cursor = ids.last().filter(|_| more).map(|last| hex_encode(last));
+    let vectors = ids
+        .into_iter()
+        .map(|id| ScrolledVector {
+            id: id.to_string(),
+            metadata: index.metadata(id).cloned(),
+            values: params
+                .include_values
+                .then(|| index.get(id).map(|(values, _)| values))
+                .flatten(),
+        })
+        .collect::<Vec<_>>();
+    state.audit(&tenant, &name, "scroll", vectors.len());
+
+    Ok(Json(ScrollResponse {
+        vectors,
+        next_cursor,
+    }))
+}
+
+/// Ids per chunk of a document export; the collections lock is released
+/// between chunks so a long export never stalls writers.
+const EXPORT_CHUNK: usize = 1000;
+
+/// Stream every vector's id and source text as NDJSON, for re-embedding
+/// after a model change. Vectors are walked in id order like
+/// `scroll_vectors`, so writes during the export are seen at most once.
+pub async fn export_documents(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path(name): Path<String>,
+    Query(params): Query<DocumentExportParams>,
+) -> Result<Response, ApiError> {
+    let tenant = api_key.0;
+    if !state
+        .read_collections()
+        .await
+        .get(&tenant)
+        .is_some_and(|tenant_map| tenant_map.contains_key(&name))
+    {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("collection '{}' not found", name),
+        ));
+    }
+    let field = params.field.unwrap_or_else(|| "document".to_string());
+
+    let chunks = futures_util::stream::unfold(Some(None), move |after: Option<Option<String>>| {
+        let (state, tenant, name, field) = (state.clone(), tenant.clone(), name.clone(), field.clone());
+        async move {
+            let after = after?;
+            let collections = state.read_collections().await;
+            // Dropped mid-export: end the stream where it stands.
+            let index = collections.get(&tenant).and_then(|t| t.get(&name))?;
+            let (ids, more) = index.scroll(after.as_deref(), EXPORT_CHUNK);
+            if ids.is_empty() {
+                return None;
+            }
+            let mut chunk = String::new();
+            for id in &ids {
+                let metadata = index.metadata(id).cloned();
+                let line = ExportedDocument {
+                    id: id.to_string(),
+                    document: metadata
+                        .as_ref()
+                        .and_then(|m| m.get(&field))
+                        .and_then(|d| d.as_str())
+                        .map(str::to_string),
+                    metadata,
+                };
+                chunk.push_str(&serde_json::to_string(&line).unwrap_or_default());
+                chunk.push('\n');
+            }
+            state.audit(&tenant, &name, "export", ids.len());
+            let next = more.then(|| ids.last().map(|id| id.to_string()));
+            Some((Ok::<_, std::convert::Infallible>(chunk), next))
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+// ---------- get vector ----------
 
-    Ok(Json(QueryResponse { matches }))
+pub async fn get_vector(
+    State(state): State<AppState>,
+    api_key: ApiKey,
+    Path((name, id)): Path<(String, String)>,
+    Query(params): Query<GetVectorParams>,
+) -> Result<Json<GetVectorResponse>, ApiError> {
+    let tenant = api_key.0;
+    let collections = state.read_collections().await;
+
+    let index = collections
+        .get(&tenant)
+        .and_then(|tenant_map| tenant_map.get(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let (values, metadata) = index.get(&id).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("vector '{}' not found", id),
+        )
+    })?;
+    let times = params
+        .include_timestamps
+        .then(|| index.timestamps(&id))
+        .flatten();
+    state.audit(&tenant, &name, "get_vector", 1);
+
+    Ok(Json(GetVectorResponse {
+        pending: index.is_pending(&id),
+        version: index.version(&id).unwrap_or_default(),
+        norm: params.include_norm.then(|| index.norm(&id)).flatten(),
+        id,
+        values,
+        metadata,
+        created_at: times.map(|t| t.created_at),
+        updated_at: times.map(|t| t.updated_at),
+    }))
 }
 
+// ---------- update metadata ----------
+
+/// Replace a vector's metadata in place: no embedding needed, and the HNSW
+/// graph is left alone.
+pub async fn update_vector_metadata(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Path((name, id)): Path<(String, String)>,
+    Json(payload): Json<UpdateMetadataRequest>,
+) -> Result<Json<UpdateMetadataResponse>, ApiError> {
+    let tenant = api_key.0;
+    validate_metadata_size(&payload.metadata, state.config.max_metadata_bytes)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    let mut collections = state.write_collections().await;
+
+    let index = collections
+        .get_mut(&tenant)
+        .and_then(|tenant_map| tenant_map.get_mut(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+    if index.config().mode == WriteMode::InsertOnly {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "the collection is insert-only; stored vectors cannot be changed",
+        ));
+    }
+
+    let written_at = now_millis();
+    if !index.update_metadata_at(&id, payload.metadata.clone(), written_at) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            format!("vector '{}' not found", id),
+        ));
+    }
+    if let Err(e) = wal_append(&state, &WalEntry::UpdateMetadata {
+        tenant: tenant.clone(),
+        collection: name.clone(),
+        id: id.clone(),
+        metadata: payload.metadata,
+        written_at: Some(written_at),
+    }) {
+        tracing::error!("failed to append WAL for update_metadata: {:?}", e);
+    }
+    state.audit(&tenant, &name, "update_metadata", 1);
 
+    Ok(Json(UpdateMetadataResponse {
+        version: index.version(&id).unwrap_or_default(),
+        id,
+    }))
+}
 
 // ---------- delete vector ----------
 
 pub async fn delete_vector(
     State(state): State<AppState>,
-    api_key: ApiKey,
+    api_key: WriteKey,
     Path((name, id)): Path<(String, String)>,
-) -> Result<Json<DeleteVectorResponse>, (StatusCode, String)> {
+) -> Result<Json<DeleteVectorResponse>, ApiError> {
     let tenant = api_key.0;
-    let mut collections = state.collections.write().await;
+    let mut collections = state.write_collections().await;
 
     let tenant_map = collections.get_mut(&tenant).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
     })?;
 
     let index = tenant_map.get_mut(&name).ok_or_else(|| {
-        (
+        ApiError::new(
             StatusCode::NOT_FOUND,
             format!("collection '{}' not found", name),
         )
@@ -323,39 +2092,404 @@ pub async fn delete_vector(
 
     let deleted = index.delete(&id);
 
-    if deleted {
-        if let Err(e) = append_entry(&WalEntry::DeleteVector {
+    if deleted
+        && let Err(e) = wal_append(&state, &WalEntry::DeleteVector {
+            tenant: tenant.clone(),
+            collection: name.clone(),
+            id: id.clone(),
+        })
+    {
+        tracing::error!("failed to append WAL for delete_vector: {:?}", e);
+    }
+    maybe_compact(&state, &tenant, &name, index);
+    state.audit(&tenant, &name, "delete", usize::from(deleted));
+
+    Ok(Json(DeleteVectorResponse { deleted }))
+}
+
+/// Delete several vectors by id; unknown ids are reported as 404 items.
+pub async fn delete_vectors_batch(
+    State(state): State<AppState>,
+    api_key: WriteKey,
+    Path(name): Path<String>,
+    Json(payload): Json<DeleteVectorsRequest>,
+) -> Result<(StatusCode, Json<BatchResponse>), ApiError> {
+    let tenant = api_key.0;
+    let mut collections = state.write_collections().await;
+
+    let index = collections
+        .get_mut(&tenant)
+        .and_then(|tenant_map| tenant_map.get_mut(&name))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("collection '{}' not found", name),
+            )
+        })?;
+
+    let mut results = Vec::with_capacity(payload.ids.len());
+    for id in payload.ids {
+        if !index.delete(&id) {
+            let msg = format!("vector '{}' not found", id);
+            results.push(ItemStatus::failed(id, StatusCode::NOT_FOUND, msg));
+            continue;
+        }
+        if let Err(e) = wal_append(&state, &WalEntry::DeleteVector {
             tenant: tenant.clone(),
             collection: name.clone(),
             id: id.clone(),
         }) {
             tracing::error!("failed to append WAL for delete_vector: {:?}", e);
         }
+        results.push(ItemStatus::ok(id));
     }
+    maybe_compact(&state, &tenant, &name, index);
+    let (status, Json(batch)) = batch_response(results);
+    state.audit(&tenant, &name, "delete", batch.succeeded);
 
-    Ok(Json(DeleteVectorResponse { deleted }))
+    Ok((status, Json(batch)))
+}
+
+// ---------- batch results ----------
+
+/// Status for a whole batch from its items' statuses, see `batch_response`.
+fn batch_status(statuses: impl Iterator<Item = u16>) -> StatusCode {
+    let statuses: Vec<u16> = statuses.collect();
+    let failed: Vec<u16> = statuses
+        .iter()
+        .copied()
+        .filter(|s| !(200..300).contains(s))
+        .collect();
+    if failed.is_empty() {
+        StatusCode::OK
+    } else if failed.len() == statuses.len() && failed.iter().all(|&s| s == failed[0]) {
+        StatusCode::from_u16(failed[0]).unwrap_or(StatusCode::BAD_REQUEST)
+    } else {
+        StatusCode::MULTI_STATUS
+    }
 }
 
+/// Wrap per-item outcomes with the status for the whole batch: 200 when
+/// every item succeeded (or there were none), the items' shared status when
+/// all failed alike, and 207 Multi-Status otherwise.
+fn batch_response(results: Vec<ItemStatus>) -> (StatusCode, Json<BatchResponse>) {
+    let succeeded = results.iter().filter(|r| r.is_success()).count();
+    let failed = results.len() - succeeded;
+    let status = batch_status(results.iter().map(|r| r.status));
+
+    (
+        status,
+        Json(BatchResponse {
+            succeeded,
+            failed,
+            results,
+        }),
+    )
+}
 
 // -------------- Snapshot -------------
 
+/// 409 for a heavy task requested while another runs.
+fn maintenance_in_progress() -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "maintenance in progress")
+}
+
+/// `AppState::try_begin_heavy_task`, or the 409 to answer with.
+fn heavy_task(
+    state: &AppState,
+    task: &str,
+) -> Result<tokio::sync::OwnedSemaphorePermit, ApiError> {
+    state
+        .try_begin_heavy_task(task)
+        .ok_or_else(maintenance_in_progress)
+}
+
+/// Append one WAL entry and count it towards the first-snapshot milestone.
+fn wal_append(state: &AppState, entry: &WalEntry) -> anyhow::Result<()> {
+    let mut line = String::new();
+    encode_entry(entry, &mut line)?;
+    state.append_wal(&line)?;
+    note_wal_writes(state, 1);
+    Ok(())
+}
+
+/// `wal_append` for a batch of `entries` lines encoded up front.
+fn wal_append_encoded(state: &AppState, lines: &str, entries: usize) -> anyhow::Result<()> {
+    state.append_wal(lines)?;
+    note_wal_writes(state, entries as u64);
+    Ok(())
+}
+
+/// Kick off the one-time baseline snapshot once a fresh deployment's WAL
+/// crosses `first_snapshot_after` entries. Runs in the background; the
+/// caller still holds the write lock, so the task waits for it.
+fn note_wal_writes(state: &AppState, entries: u64) {
+    if !state.record_wal_writes(entries) {
+        return;
+    }
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        match state.write_snapshot().await {
+            Ok(_) => tracing::info!(
+                "wrote first snapshot after {} WAL entries",
+                state.wal_writes()
+            ),
+            Err(e) => {
+                tracing::error!("failed to write first snapshot: {:?}", e);
+                // Try again on the next write.
+                state.rearm_first_snapshot();
+            }
+        }
+    });
+}
+
 pub async fn create_snapshot(
     State(state): State<AppState>,
-    _api_key: ApiKey,
-) -> Result<Json<SnapshotResponse>, (StatusCode, String)> {
-    let collections = state.collections.read().await;
+    _api_key: WriteKey,
+    Query(params): Query<SnapshotParams>,
+) -> Result<Json<SnapshotResponse>, ApiError> {
+    if params.dry_run {
+        let started = Instant::now();
+        let (summary, bytes) = state.estimate_snapshot().await.map_err(|e| {
+            tracing::error!("failed to estimate snapshot: {:?}", e);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to estimate snapshot",
+            )
+        })?;
+        return Ok(Json(SnapshotResponse {
+            success: true,
+            message: "dry run, nothing written".to_string(),
+            history: None,
+            estimate: Some(SnapshotEstimate {
+                tenants: summary.tenants,
+                collections: summary.collections,
+                vectors: summary.vectors,
+                bytes,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            }),
+        }));
+    }
+
+    let summary = state
+        .try_write_snapshot()
+        .await
+        .ok_or_else(maintenance_in_progress)?
+        .map_err(|e| {
+            tracing::error!("failed to write snapshot: {:?}", e);
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to write snapshot",
+            )
+        })?;
 
-    if let Err(e) = crate::storage::write_snapshot_from_state(&*collections) {
-        tracing::error!("failed to write snapshot: {:?}", e);
-        return Err((
+    Ok(Json(SnapshotResponse {
+        success: true,
+        message: "snapshot written".to_string(),
+        history: summary.history,
+        estimate: None,
+    }))
+}
+
+/// fsync the WAL so every write acknowledged so far survives a power loss,
+/// for deployments running with `OPENVDB_WAL_SYNC=os` or `interval`.
+pub async fn sync_wal_now(
+    State(state): State<AppState>,
+    _api_key: WriteKey,
+) -> Result<Json<SnapshotResponse>, ApiError> {
+    if let Err(e) = sync_wal(&state.config.data_dir) {
+        tracing::error!("failed to sync WAL: {:?}", e);
+        return Err(ApiError::new(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to write snapshot".to_string(),
+            "failed to sync WAL",
         ));
     }
 
     Ok(Json(SnapshotResponse {
         success: true,
-        message: "snapshot written".to_string(),
+        message: "WAL synced".to_string(),
+        history: None,
+        estimate: None,
     }))
 }
 
+/// Flat inventory of every collection across all tenants, for fleet-wide
+/// capacity reports. Unlike `list_collections` this is not tenant-scoped.
+pub async fn admin_list_collections(
+    State(state): State<AppState>,
+    _api_key: ApiKey,
+) -> Json<AdminListCollectionsResponse> {
+    let collections = state.all_tenants_view().await;
+
+    let mut items: Vec<AdminCollectionSummary> = collections
+        .iter()
+        .flat_map(|(tenant, tenant_map)| {
+            tenant_map.iter().map(move |(name, index)| AdminCollectionSummary {
+                tenant: tenant.clone(),
+                name: name.clone(),
+                dimension: index.dimension(),
+                vectors: index.vector_count(),
+                index_type: index.index_type().to_string(),
+            })
+        })
+        .collect();
+    items.sort_by(|a, b| (&a.tenant, &a.name).cmp(&(&b.tenant, &b.name)));
+
+    Json(AdminListCollectionsResponse { collections: items })
+}
+
+/// Upsert random vectors into a throwaway index and query it, reporting
+/// throughput and latency. One run at a time; admin key only.
+pub async fn run_benchmark(
+    State(state): State<AppState>,
+    _admin: AdminKey,
+    Json(payload): Json<BenchmarkRequest>,
+) -> Result<Json<BenchmarkResponse>, ApiError> {
+    benchmark::validate(&payload).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    let run = state.benchmark.start().ok_or(ApiError::new(
+        StatusCode::CONFLICT,
+        "a benchmark is already running",
+    ))?;
+
+    let cancel = run.cancel_flag();
+    let result = state
+        .run_on_index_pool(move || benchmark::run(&payload, &cancel))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    drop(run);
+    Ok(Json(result))
+}
+
+/// Whether the server is in maintenance mode.
+pub async fn maintenance_status(
+    State(state): State<AppState>,
+    _admin: AdminKey,
+) -> Json<Maintenance> {
+    Json(Maintenance {
+        enabled: state.in_maintenance(),
+    })
+}
+
+/// Put the server in (or take it out of) read-only maintenance mode. The
+/// mode persists across restarts until cleared here.
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    _admin: AdminKey,
+    Json(payload): Json<Maintenance>,
+) -> Result<Json<Maintenance>, ApiError> {
+    state.set_maintenance(payload.enabled).map_err(|e| {
+        tracing::error!("failed to update maintenance marker: {:?}", e);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to update maintenance mode",
+        )
+    })?;
+    tracing::warn!(
+        "maintenance mode {}",
+        if payload.enabled { "enabled" } else { "disabled" }
+    );
+    Ok(Json(payload))
+}
+
+/// Stop the running benchmark; it responds with what it measured so far.
+pub async fn cancel_benchmark(
+    State(state): State<AppState>,
+    _admin: AdminKey,
+) -> Result<StatusCode, ApiError> {
+    if state.benchmark.cancel() {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err(ApiError::new(StatusCode::NOT_FOUND, "no benchmark is running"))
+    }
+}
+
+/// Report collections whose on-disk and in-memory presence disagree.
+pub async fn reconcile_report(
+    State(state): State<AppState>,
+    _api_key: ApiKey,
+) -> Result<Json<ReconcileResponse>, ApiError> {
+    let collections = state.all_tenants_view().await;
+    let resp = reconcile(&state, &collections)?;
+    Ok(Json(resp))
+}
+
+/// Like `reconcile_report`, then repair any discrepancy by rewriting disk
+/// state from memory: a fresh snapshot (which also truncates the WAL) drops
+/// disk-only collections and persists memory-only ones.
+pub async fn reconcile_repair(
+    State(state): State<AppState>,
+    _api_key: WriteKey,
+) -> Result<Json<ReconcileResponse>, ApiError> {
+    let _task = heavy_task(&state, "reconcile repair")?;
+    // Write lock: nothing may hit the WAL between the scan and the rewrite.
+    let _snapshot = state.lock_snapshots().await;
+    let mut collections = state.write_collections().await;
+    state.hydrate_all_locked(&mut collections);
+    let mut resp = reconcile(&state, &collections)?;
+
+    let internal = |e: anyhow::Error| {
+        tracing::error!("failed to repair disk state: {:?}", e);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to repair disk state",
+        )
+    };
+
+    if !resp.disk_only.is_empty() || !resp.memory_only.is_empty() {
+        let written = write_snapshot_from_state(
+            &state.config.data_dir,
+            &collections,
+            state.config.wal_retention(),
+            state.config.snapshot_history,
+            state.config.snapshot_gzip_level,
+        );
+        state.reopen_wal();
+        written.map_err(internal)?;
+        resp.repaired = true;
+        tracing::warn!(
+            "reconcile repaired {} disk-only and {} memory-only collections",
+            resp.disk_only.len(),
+            resp.memory_only.len()
+        );
+    }
+    resp.removed_files = crate::storage::remove_stray_files(&state.config.data_dir)
+        .map_err(internal)?;
+
+    Ok(Json(resp))
+}
+
+fn reconcile(
+    state: &AppState,
+    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
+) -> Result<ReconcileResponse, ApiError> {
+    let on_disk = crate::storage::disk_inventory(&state.config.data_dir).map_err(|e| {
+        tracing::error!("failed to scan data dir: {:?}", e);
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to scan data dir",
+        )
+    })?;
+
+    let in_memory: BTreeSet<(String, String)> = collections
+        .iter()
+        .flat_map(|(tenant, tenant_map)| {
+            tenant_map.keys().map(move |name| (tenant.clone(), name.clone()))
+        })
+        .collect();
+
+    let to_refs = |set: btree_set::Difference<'_, (String, String)>| {
+        set.map(|(tenant, collection)| CollectionRef {
+            tenant: tenant.clone(),
+            collection: collection.clone(),
+        })
+        .collect::<Vec<_>>()
+    };
+
+    Ok(ReconcileResponse {
+        disk_only: to_refs(on_disk.difference(&in_memory)),
+        memory_only: to_refs(in_memory.difference(&on_disk)),
+        repaired: false,
+        removed_files: Vec::new(),
+    })
+}
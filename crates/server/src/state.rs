@@ -1,51 +1,869 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use tokio::sync::RwLock;
+use anyhow::Context;
+use tokio::runtime::RuntimeFlavor;
+use tokio::sync::{OwnedSemaphorePermit, RwLock, RwLockReadGuard, RwLockWriteGuard, Semaphore};
 
+use crate::audit::AuditLog;
+use crate::auth::Role;
+use crate::benchmark::BenchmarkSlot;
+use crate::config::{CompactionWindow, Config};
 use crate::index::InMemoryIndex;
+use crate::metrics::{Metrics, TimedGuard};
+use crate::models::QueryResponse;
+use crate::routes;
+use crate::storage::{self, SnapshotSummary, WalWriter, MAINTENANCE_FILE};
+use crate::vector_store::{self, VECTORS_DIR};
+
+/// Most `eventual` query results kept at once, see
+/// `AppState::cache_eventual_result`.
+const EVENTUAL_RESULTS_MAX: usize = 1024;
+
+/// What an `eventual` query's result is cached under: tenant, collection
+/// and the request body as JSON. Only an identical request reuses it.
+pub type EventualKey = (String, String, String);
+
+/// Cached `eventual` results: when each was computed, the collection
+/// generation it saw, and the result.
+type EventualResults = HashMap<EventualKey, (Instant, u64, QueryResponse)>;
 
 #[derive(Clone)]
 pub struct AppState {
     // tenant_id (api_key) -> { collection_name -> index }
     pub collections: Arc<RwLock<HashMap<String, HashMap<String, InMemoryIndex>>>>,
-    pub api_keys: Arc<HashSet<String>>,
+    // API key -> role; the key also names the tenant
+    pub api_keys: Arc<HashMap<String, Role>>,
+    pub config: Arc<Config>,
+    pub metrics: Arc<Metrics>,
+    // Shared by every WAL append, see `append_wal`
+    wal: Arc<Mutex<WalWriter>>,
+    // WAL entries appended since boot
+    wal_writes: Arc<AtomicU64>,
+    // HTTP requests currently being handled, see `begin_request`
+    in_flight: Arc<AtomicUsize>,
+    // Set while a fresh deployment still owes its first snapshot
+    first_snapshot_pending: Arc<AtomicBool>,
+    // Tenants whose data lives only on disk (snapshot + WAL) until their
+    // next request: evicted, or never loaded under `lazy_tenant_load`. Only
+    // changed while holding the collections write lock.
+    evicted: Arc<Mutex<HashSet<String>>>,
+    // Bumped by every eviction, so an off-lock load can tell its tenant may
+    // have been reloaded and evicted again under it, see `hydrate`
+    evictions: Arc<AtomicU64>,
+    // tenant -> last authenticated request
+    last_access: Arc<Mutex<HashMap<String, Instant>>>,
+    // Pool for CPU-heavy index work, see `run_on_index_pool`
+    index_pool: Arc<rayon::ThreadPool>,
+    pub benchmark: Arc<BenchmarkSlot>,
+    // Held while a snapshot is written, so two never race on its temp file
+    snapshotting: Arc<tokio::sync::Mutex<()>>,
+    // One permit: heavy background work (snapshot, compaction, reindex) runs
+    // one task at a time, see `begin_heavy_task`
+    heavy_tasks: Arc<Semaphore>,
+    // tenant -> its upsert slots, created on first use, see
+    // `try_begin_upsert`
+    upsert_slots: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    // API key -> its request budget, see `take_request_token`
+    rate_limits: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    // Recent `eventual` query results with the collection generation they
+    // were computed at, see `cached_eventual_result`
+    eventual_results: Arc<Mutex<EventualResults>>,
+    // Set in maintenance mode, where `WriteKey` turns every write away
+    maintenance: Arc<AtomicBool>,
+    // Present when `Config::audit_log` is set, see `audit`
+    audit: Option<Arc<AuditLog>>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
-        let api_keys = default_api_keys();
-        Self {
-            collections: Arc::new(RwLock::new(HashMap::new())),
-            api_keys: Arc::new(api_keys),
+    pub fn new(
+        config: Config,
+        api_keys: HashMap<String, Role>,
+        mut initial: HashMap<String, HashMap<String, InMemoryIndex>>,
+    ) -> Self {
+        if config.mmap_vectors {
+            // Backing files are rebuilt from snapshot + WAL on every start.
+            if let Err(e) = vector_store::clear_vectors_dir(&config.data_dir) {
+                tracing::error!("failed to clear old vector files: {:?}", e);
+            }
+            for index in initial.values_mut().flat_map(|t| t.values_mut()) {
+                map_vectors(&config, index);
+            }
         }
-    }
 
-    pub fn with_collections(
-        initial: HashMap<String, HashMap<String, InMemoryIndex>>,
-    ) -> Self {
-        let api_keys = default_api_keys();
+        let first_snapshot_pending = config.first_snapshot_after > 0
+            && !storage::current_snapshot(&config.data_dir).exists();
+        let maintenance = config.data_dir.join(MAINTENANCE_FILE).exists();
+        if maintenance {
+            tracing::warn!("starting in maintenance mode: writes are disabled");
+        }
+        let audit = config.audit_log.clone().and_then(|path| {
+            AuditLog::open(path, config.audit_log_max_bytes)
+                .inspect_err(|e| tracing::error!("failed to open audit log: {:?}", e))
+                .ok()
+                .map(Arc::new)
+        });
+        let index_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.index_threads)
+            .thread_name(|i| format!("openvdb-index-{}", i))
+            .build()
+            .expect("failed to build index thread pool");
+        // Loading counts as an access: idle eviction starts from boot, not
+        // from the first tick of the evictor.
+        let loaded_at = Instant::now();
+        let last_access = initial.keys().map(|tenant| (tenant.clone(), loaded_at)).collect();
+
         Self {
             collections: Arc::new(RwLock::new(initial)),
             api_keys: Arc::new(api_keys),
+            config: Arc::new(config),
+            metrics: Arc::new(Metrics::default()),
+            wal: Arc::new(Mutex::new(WalWriter::default())),
+            wal_writes: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            first_snapshot_pending: Arc::new(AtomicBool::new(first_snapshot_pending)),
+            evicted: Arc::new(Mutex::new(HashSet::new())),
+            evictions: Arc::new(AtomicU64::new(0)),
+            last_access: Arc::new(Mutex::new(last_access)),
+            index_pool: Arc::new(index_pool),
+            benchmark: Arc::new(BenchmarkSlot::default()),
+            snapshotting: Arc::new(tokio::sync::Mutex::new(())),
+            heavy_tasks: Arc::new(Semaphore::new(1)),
+            upsert_slots: Arc::new(Mutex::new(HashMap::new())),
+            rate_limits: Arc::new(Mutex::new(HashMap::new())),
+            eventual_results: Arc::new(Mutex::new(HashMap::new())),
+            maintenance: Arc::new(AtomicBool::new(maintenance)),
+            audit,
+        }
+    }
+
+    /// Record a data access in the audit log, if one is configured. `ids`
+    /// is how many vectors `op` read or wrote.
+    pub fn audit(&self, tenant: &str, collection: &str, op: &'static str, ids: usize) {
+        if let Some(audit) = &self.audit {
+            audit.record(tenant, collection, op, ids);
+        }
+    }
+
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Acquire)
+    }
+
+    /// Enter or leave maintenance mode. The marker file is written (or
+    /// removed) first, so the mode never outlives a failed update on disk.
+    pub fn set_maintenance(&self, enabled: bool) -> std::io::Result<()> {
+        let marker = self.config.data_dir.join(MAINTENANCE_FILE);
+        if enabled {
+            std::fs::create_dir_all(&self.config.data_dir)?;
+            std::fs::write(&marker, b"")?;
+        } else if let Err(e) = std::fs::remove_file(&marker)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e);
+        }
+        self.maintenance.store(enabled, Ordering::Release);
+        Ok(())
+    }
+
+    /// Run `f` on the index pool and wait for it without blocking the
+    /// runtime. Errors if `f` panicked.
+    pub async fn run_on_index_pool<R: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> R + Send + 'static,
+    ) -> Result<R, String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.index_pool.spawn(move || {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        });
+        match rx.await {
+            Ok(Ok(r)) => Ok(r),
+            _ => Err("index task panicked".into()),
+        }
+    }
+
+    /// Run `f` on the index pool from a caller that can't give up what it
+    /// holds (e.g. the collections lock), so any rayon work inside it stays
+    /// on the pool. On a multi-threaded runtime the worker is handed off via
+    /// `block_in_place` first, so other tasks aren't stuck behind it.
+    pub fn install_on_index_pool<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        let multi_thread = tokio::runtime::Handle::try_current()
+            .is_ok_and(|handle| handle.runtime_flavor() == RuntimeFlavor::MultiThread);
+        if multi_thread {
+            tokio::task::block_in_place(|| self.index_pool.install(f))
+        } else {
+            self.index_pool.install(f)
+        }
+    }
+
+    /// Build the state from `config.data_dir`. Normally every tenant is
+    /// loaded up front; with `lazy_tenant_load` only the list of tenants on
+    /// disk is read, and each tenant is loaded on its first request. Fails
+    /// only when `wal_strict` finds a corrupt WAL line, or when `data_dir`
+    /// doesn't exist and can't be created.
+    pub fn load(config: Config, api_keys: HashMap<String, Role>) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&config.data_dir).with_context(|| {
+            format!("failed to create data directory {}", config.data_dir.display())
+        })?;
+        if let Err(e) = storage::trim_torn_wal_tail(&config.data_dir) {
+            tracing::error!("failed to trim torn WAL tail: {:?}", e);
+        }
+        if config.lazy_tenant_load {
+            match storage::disk_tenants(&config.data_dir) {
+                Ok(tenants) => {
+                    tracing::info!("found {} tenants on disk, loading lazily", tenants.len());
+                    let state = Self::new(config, api_keys, HashMap::new());
+                    state.evicted.lock().unwrap().extend(tenants);
+                    return Ok(state);
+                }
+                Err(e) => {
+                    tracing::error!("failed to list tenants on disk, loading eagerly: {:?}", e);
+                }
+            }
+        }
+
+        let collections = storage::load_collections(
+            &config.data_dir,
+            config.wal_replay_log_every,
+            config.wal_strict,
+        )?;
+        Ok(Self::new(config, api_keys, collections))
+    }
+
+    /// Read-lock the collections, recording wait and hold times.
+    pub async fn read_collections(
+        &self,
+    ) -> TimedGuard<'_, RwLockReadGuard<'_, HashMap<String, HashMap<String, InMemoryIndex>>>> {
+        let lock = &self.metrics.collections_lock;
+        let requested = Instant::now();
+        let guard = self.collections.read().await;
+        TimedGuard::new(guard, requested, &lock.read_wait, &lock.read_hold)
+    }
+
+    /// Write-lock the collections, recording wait and hold times.
+    pub async fn write_collections(
+        &self,
+    ) -> TimedGuard<'_, RwLockWriteGuard<'_, HashMap<String, HashMap<String, InMemoryIndex>>>> {
+        let lock = &self.metrics.collections_lock;
+        let requested = Instant::now();
+        let guard = self.collections.write().await;
+        TimedGuard::new(guard, requested, &lock.write_wait, &lock.write_hold)
+    }
+
+    /// Count a request as in flight until the returned guard drops.
+    pub fn begin_request(&self) -> InFlightRequest {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        InFlightRequest(self.in_flight.clone())
+    }
+
+    pub fn in_flight_requests(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Make everything written so far durable before the process exits:
+    /// fsync the WAL, then snapshot if `snapshot_on_shutdown` is set.
+    pub async fn persist_on_shutdown(&self) {
+        if let Err(e) = storage::sync_wal(&self.config.data_dir) {
+            tracing::error!("failed to sync WAL on shutdown: {:?}", e);
+        }
+        if !self.config.snapshot_on_shutdown {
+            return;
+        }
+        match self.write_snapshot().await {
+            Ok(summary) => tracing::info!(
+                "shutdown snapshot: {} tenants, {} collections, {} vectors",
+                summary.tenants,
+                summary.collections,
+                summary.vectors
+            ),
+            Err(e) => tracing::error!("failed to write shutdown snapshot: {:?}", e),
+        }
+    }
+
+    /// Append lines from `storage::encode_entry` to the WAL under the
+    /// configured sync policy, sealing the segment once it grows past
+    /// `wal_segment_bytes`.
+    pub fn append_wal(&self, lines: &str) -> anyhow::Result<()> {
+        self.wal
+            .lock()
+            .unwrap()
+            .append(
+                &self.config.data_dir,
+                lines,
+                self.config.wal_sync,
+                self.config.wal_segment_bytes,
+            )
+    }
+
+    /// fsync the WAL if anything was appended since the last sync.
+    pub fn flush_wal(&self) -> anyhow::Result<()> {
+        self.wal.lock().unwrap().sync()
+    }
+
+    /// Let go of the open WAL after a snapshot renamed or truncated it.
+    pub fn reopen_wal(&self) {
+        if let Err(e) = self.wal.lock().unwrap().close() {
+            tracing::error!("failed to sync WAL before reopening it: {:?}", e);
+        }
+    }
+
+    pub fn wal_writes(&self) -> u64 {
+        self.wal_writes.load(Ordering::Relaxed)
+    }
+
+    /// Count `entries` successful WAL appends. Returns true exactly once: for
+    /// the write that takes a deployment without a snapshot past
+    /// `first_snapshot_after`.
+    pub fn record_wal_writes(&self, entries: u64) -> bool {
+        let total = self.wal_writes.fetch_add(entries, Ordering::Relaxed) + entries;
+        total >= self.config.first_snapshot_after
+            && self.first_snapshot_pending.swap(false, Ordering::AcqRel)
+    }
+
+    /// Undo the trigger after a failed first snapshot.
+    pub fn rearm_first_snapshot(&self) {
+        self.first_snapshot_pending.store(true, Ordering::Release);
+    }
+
+    // ---------- tenant residency ----------
+
+    /// Record a request from `tenant`, for idle eviction.
+    pub fn touch_tenant(&self, tenant: &str) {
+        let mut last_access = self.last_access.lock().unwrap();
+        match last_access.get_mut(tenant) {
+            Some(at) => *at = Instant::now(),
+            None => {
+                last_access.insert(tenant.to_string(), Instant::now());
+            }
+        }
+    }
+
+    pub fn is_resident(&self, tenant: &str) -> bool {
+        !self.evicted.lock().unwrap().contains(tenant)
+    }
+
+    /// Make sure `tenant`'s collections are in memory, loading them from
+    /// snapshot + WAL if they aren't. Returns false if that takes longer than
+    /// `tenant_load_timeout_ms`; the load carries on in the background and a
+    /// retry will find the tenant resident.
+    pub async fn ensure_tenant_loaded(&self, tenant: &str) -> bool {
+        if self.is_resident(tenant) {
+            return true;
+        }
+
+        let state = self.clone();
+        let owned = tenant.to_string();
+        let load = tokio::spawn(async move { state.hydrate(&owned).await });
+        let timeout = Duration::from_millis(self.config.tenant_load_timeout_ms);
+        tokio::time::timeout(timeout, load).await.is_ok()
+    }
+
+    /// Load `tenant` from disk without holding the collections lock, so a
+    /// cold tenant doesn't stall every other one, then take the write lock
+    /// only to install it. The load is retried if its files may have changed
+    /// under it: a WAL segment was sealed, or the tenant was reloaded (and
+    /// so possibly snapshotted) and evicted again meanwhile.
+    async fn hydrate(&self, tenant: &str) {
+        let data_dir = &self.config.data_dir;
+        let (progress_every, strict) = (self.config.wal_replay_log_every, self.config.wal_strict);
+        loop {
+            let evictions = self.evictions.load(Ordering::Acquire);
+            let wal_files = storage::wal_files(data_dir).ok();
+            if self.is_resident(tenant) {
+                return;
+            }
+
+            let started = Instant::now();
+            let (dir, owned) = (data_dir.clone(), tenant.to_string());
+            let loaded = self
+                .run_on_index_pool(move || storage::load_tenant(&dir, &owned, progress_every, strict))
+                .await;
+            let tenant_map = match loaded {
+                Ok(tenant_map) => tenant_map,
+                Err(e) => {
+                    tracing::error!("tenant load task failed: {:?}", e);
+                    return;
+                }
+            };
+
+            let mut collections = self.write_collections().await;
+            // Re-check under the lock: a concurrent request may have won.
+            if self.is_resident(tenant) {
+                return;
+            }
+            if self.evictions.load(Ordering::Acquire) == evictions
+                && storage::wal_files(data_dir).ok() == wal_files
+            {
+                self.install_tenant(&mut collections, tenant, tenant_map, started);
+                return;
+            }
+            tracing::debug!("files changed while loading a tenant, loading it again");
+        }
+    }
+
+    /// Reload every evicted tenant. Anything that rewrites disk state from
+    /// memory (snapshots, reconcile) must call this under the same write
+    /// lock first, or it would drop the evicted tenants' data.
+    pub fn hydrate_all_locked(
+        &self,
+        collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+    ) {
+        let tenants: Vec<String> = self.evicted.lock().unwrap().iter().cloned().collect();
+        for tenant in tenants {
+            self.hydrate_locked(collections, &tenant);
+        }
+    }
+
+    fn hydrate_locked(
+        &self,
+        collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+        tenant: &str,
+    ) {
+        if self.is_resident(tenant) {
+            return;
+        }
+        let started = Instant::now();
+        let tenant_map = storage::load_tenant(
+            &self.config.data_dir,
+            tenant,
+            self.config.wal_replay_log_every,
+            self.config.wal_strict,
+        );
+        self.install_tenant(collections, tenant, tenant_map, started);
+    }
+
+    /// Make a freshly loaded tenant resident. Callers hold the write lock.
+    fn install_tenant(
+        &self,
+        collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+        tenant: &str,
+        mut tenant_map: HashMap<String, InMemoryIndex>,
+        started: Instant,
+    ) {
+        tracing::info!(
+            "loaded tenant with {} collections in {:?}",
+            tenant_map.len(),
+            started.elapsed()
+        );
+        self.evicted.lock().unwrap().remove(tenant);
+        self.touch_tenant(tenant);
+        if self.config.mmap_vectors {
+            for index in tenant_map.values_mut() {
+                map_vectors(&self.config, index);
+            }
+        }
+        if !tenant_map.is_empty() {
+            collections.insert(tenant.to_string(), tenant_map);
+        }
+    }
+
+    /// Exclusive right to write a snapshot. Take it before the collections
+    /// lock when holding both.
+    pub async fn lock_snapshots(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.snapshotting.lock().await
+    }
+
+    /// The right to run a heavy task (snapshot, compaction, reindex), so two
+    /// never double memory and I/O. Waits for the running one, if any; take
+    /// it before any other lock.
+    pub async fn begin_heavy_task(&self, task: &str) -> OwnedSemaphorePermit {
+        if let Ok(permit) = self.heavy_tasks.clone().try_acquire_owned() {
+            return permit;
+        }
+        tracing::info!("{} queued behind a running maintenance task", task);
+        self.heavy_tasks
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("heavy task semaphore is never closed")
+    }
+
+    /// Like `begin_heavy_task`, but `None` instead of waiting.
+    pub fn try_begin_heavy_task(&self, task: &str) -> Option<OwnedSemaphorePermit> {
+        let permit = self.heavy_tasks.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            tracing::info!("rejected {}: another maintenance task is running", task);
+        }
+        permit
+    }
+
+    /// One of `tenant`'s `Config::tenant_upsert_concurrency` upsert slots,
+    /// held for the whole request; `None` when they're all taken. Always
+    /// granted when the limit is unset.
+    pub fn try_begin_upsert(&self, tenant: &str) -> Option<Option<OwnedSemaphorePermit>> {
+        let Some(limit) = self.config.tenant_upsert_concurrency else {
+            return Some(None);
+        };
+        let slots = self
+            .upsert_slots
+            .lock()
+            .unwrap()
+            .entry(tenant.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone();
+        slots.try_acquire_owned().ok().map(Some)
+    }
+
+    /// Spend one of `key`'s requests under `Config::rate_limit_rps`. When
+    /// its bucket is empty, returns how long until the next token.
+    pub fn take_request_token(&self, key: &str) -> Result<(), Duration> {
+        let Some(rps) = self.config.rate_limit_rps.filter(|r| *r > 0.0) else {
+            return Ok(());
+        };
+        let burst = self.config.rate_limit_burst.unwrap_or(rps).max(1.0);
+        let mut buckets = self.rate_limits.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(TokenBucket {
+            tokens: burst,
+            refilled: Instant::now(),
+        });
+        let now = Instant::now();
+        bucket.tokens =
+            (bucket.tokens + now.duration_since(bucket.refilled).as_secs_f64() * rps).min(burst);
+        bucket.refilled = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rps))
         }
     }
+
+    /// `key`'s burst size and whole tokens left, for the `X-RateLimit-*`
+    /// response headers. Doesn't spend a token. `None` when rate limiting
+    /// is off or `key` hasn't made a request yet.
+    pub fn rate_limit_status(&self, key: &str) -> Option<(u64, u64)> {
+        let rps = self.config.rate_limit_rps.filter(|r| *r > 0.0)?;
+        let burst = self.config.rate_limit_burst.unwrap_or(rps).max(1.0);
+        let buckets = self.rate_limits.lock().unwrap();
+        let bucket = buckets.get(key)?;
+        let tokens =
+            (bucket.tokens + bucket.refilled.elapsed().as_secs_f64() * rps).min(burst);
+        Some((burst as u64, tokens as u64))
+    }
+
+    /// The result and collection generation cached under `key` by
+    /// `cache_eventual_result`, if it is within
+    /// `Config::eventual_max_staleness_ms`.
+    pub fn cached_eventual_result(&self, key: &EventualKey) -> Option<(u64, QueryResponse)> {
+        let max_age = Duration::from_millis(self.config.eventual_max_staleness_ms);
+        self.eventual_results
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(at, _, _)| at.elapsed() < max_age)
+            .map(|(_, generation, resp)| (*generation, resp.clone()))
+    }
+
+    /// Cache a fresh `eventual` query result. Results past the staleness
+    /// bound are dropped first; at most `EVENTUAL_RESULTS_MAX` are kept.
+    pub fn cache_eventual_result(&self, key: EventualKey, generation: u64, resp: QueryResponse) {
+        let max_age = Duration::from_millis(self.config.eventual_max_staleness_ms);
+        let mut cached = self.eventual_results.lock().unwrap();
+        cached.retain(|_, (at, _, _)| at.elapsed() < max_age);
+        if cached.len() < EVENTUAL_RESULTS_MAX || cached.contains_key(&key) {
+            cached.insert(key, (Instant::now(), generation, resp));
+        }
+    }
+
+    /// Drop the cached `eventual` results of a deleted collection, so none
+    /// outlive it.
+    pub fn forget_eventual_results(&self, tenant: &str, collection: &str) {
+        self.eventual_results
+            .lock()
+            .unwrap()
+            .retain(|(t, c, _), _| t != tenant || c != collection);
+    }
+
+    /// Snapshot every tenant, waiting for any heavy task already running.
+    pub async fn write_snapshot(&self) -> anyhow::Result<SnapshotSummary> {
+        let _task = self.begin_heavy_task("snapshot").await;
+        let _snapshot = self.lock_snapshots().await;
+        self.snapshot_locked().await
+    }
+
+    /// Like `write_snapshot`, but `None` instead of waiting if a heavy task
+    /// is already running.
+    pub async fn try_write_snapshot(&self) -> Option<anyhow::Result<SnapshotSummary>> {
+        let _task = self.try_begin_heavy_task("snapshot")?;
+        let _snapshot = self.snapshotting.try_lock().ok()?;
+        Some(self.snapshot_locked().await)
+    }
+
+    /// Measure the snapshot `write_snapshot` would take, see
+    /// `storage::estimate_snapshot`. Needs no snapshot lock: nothing is
+    /// written, and evicted tenants are measured on disk, not reloaded.
+    pub async fn estimate_snapshot(&self) -> anyhow::Result<(SnapshotSummary, u64)> {
+        let collections = self.read_collections().await;
+        // `evicted` only changes under the write lock, so this is stable.
+        let evicted = self.evicted.lock().unwrap().clone();
+        storage::estimate_snapshot(&self.config.data_dir, &collections, &evicted)
+    }
+
+    async fn snapshot_locked(&self) -> anyhow::Result<SnapshotSummary> {
+        let collections = self.all_tenants_view().await;
+        let written = storage::write_snapshot_from_state(
+            &self.config.data_dir,
+            &collections,
+            self.config.wal_retention(),
+            self.config.snapshot_history,
+            self.config.snapshot_gzip_level,
+        );
+        // Even a failed snapshot may have moved the WAL.
+        self.reopen_wal();
+        written
+    }
+
+    /// Lock the collections for a view spanning every tenant (snapshots,
+    /// admin reports). A read lock, unless tenants are evicted: then a write
+    /// lock under which they are reloaded first, so the view is complete.
+    pub async fn all_tenants_view(&self) -> CollectionsView<'_> {
+        let collections = self.read_collections().await;
+        // `evicted` only changes under the write lock, so this is stable.
+        if self.evicted.lock().unwrap().is_empty() {
+            return CollectionsView::Read(collections);
+        }
+        drop(collections);
+
+        let mut collections = self.write_collections().await;
+        self.hydrate_all_locked(&mut collections);
+        CollectionsView::Write(collections)
+    }
+
+    /// Drop from memory every tenant with no request for `idle`. Their data
+    /// stays in snapshot + WAL and is reloaded on the next request. Returns
+    /// how many tenants were evicted.
+    pub async fn evict_idle_tenants(&self, idle: Duration) -> usize {
+        let mut collections = self.write_collections().await;
+        let now = Instant::now();
+
+        let stale: Vec<String> = {
+            let last_access = self.last_access.lock().unwrap();
+            collections
+                .keys()
+                .filter(|tenant| {
+                    last_access
+                        .get(*tenant)
+                        .is_none_or(|at| now.duration_since(*at) >= idle)
+                })
+                .cloned()
+                .collect()
+        };
+
+        let mut evicted = self.evicted.lock().unwrap();
+        for tenant in &stale {
+            collections.remove(tenant);
+            evicted.insert(tenant.clone());
+        }
+        if !stale.is_empty() {
+            self.evictions.fetch_add(1, Ordering::AcqRel);
+        }
+        stale.len()
+    }
 }
 
-fn default_api_keys() -> HashSet<String> {
-    if let Ok(val) = std::env::var("OPENVDB_API_KEYS") {
-        let keys = val
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect::<HashSet<_>>();
+type Collections = HashMap<String, HashMap<String, InMemoryIndex>>;
+
+/// Requests an API key may still make right away, refilled at
+/// `Config::rate_limit_rps` up to the burst size.
+struct TokenBucket {
+    tokens: f64,
+    refilled: Instant,
+}
 
+/// Either lock guard over the collections; see `AppState::all_tenants_view`.
+pub enum CollectionsView<'a> {
+    Read(TimedGuard<'a, RwLockReadGuard<'a, Collections>>),
+    Write(TimedGuard<'a, RwLockWriteGuard<'a, Collections>>),
+}
+
+impl Deref for CollectionsView<'_> {
+    type Target = Collections;
+
+    fn deref(&self) -> &Collections {
+        match self {
+            CollectionsView::Read(guard) => guard,
+            CollectionsView::Write(guard) => guard,
+        }
+    }
+}
+
+/// Move `index`'s vector values to an mmap file if `mmap_vectors` is set.
+/// On failure the values stay on the heap.
+pub fn map_vectors(config: &Config, index: &mut InMemoryIndex) {
+    if !config.mmap_vectors {
+        return;
+    }
+    if let Err(e) = index.enable_mmap(&config.data_dir.join(VECTORS_DIR)) {
+        tracing::error!("failed to set up mmap vector storage: {:?}", e);
+    }
+}
+
+/// A request counted by `AppState::begin_request`.
+pub struct InFlightRequest(Arc<AtomicUsize>);
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Periodically evict tenants idle for longer than `idle`.
+pub fn spawn_tenant_evictor(state: AppState, idle: Duration) {
+    let period = (idle / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            let evicted = state.evict_idle_tenants(idle).await;
+            if evicted > 0 {
+                tracing::info!("evicted {} idle tenants from memory", evicted);
+            }
+        }
+    });
+}
+
+/// Periodically delete WALs retained past `retention` after a snapshot.
+pub fn spawn_wal_pruner(state: AppState, retention: Duration) {
+    let period = (retention / 4).clamp(Duration::from_secs(1), Duration::from_secs(60));
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = storage::prune_retained_wals(&state.config.data_dir, retention) {
+                tracing::error!("failed to prune retained WALs: {:?}", e);
+            }
+        }
+    });
+}
+
+/// fsync the WAL every `interval` (`WalSync::Interval`).
+pub fn spawn_wal_flusher(state: AppState, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = state.flush_wal() {
+                tracing::error!("failed to sync WAL: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Snapshot every `interval`, skipping cycles with no WAL writes since the
+/// last one or with a snapshot already running.
+pub fn spawn_snapshotter(state: AppState, interval: Duration) {
+    let mut written_at = state.wal_writes();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick fires immediately; the state was just loaded.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let writes = state.wal_writes();
+            if writes == written_at {
+                continue;
+            }
+            match state.try_write_snapshot().await {
+                None => tracing::debug!("maintenance task running, skipping this cycle"),
+                Some(Ok(summary)) => {
+                    written_at = writes;
+                    tracing::info!(
+                        "periodic snapshot: {} tenants, {} collections, {} vectors",
+                        summary.tenants,
+                        summary.collections,
+                        summary.vectors
+                    );
+                }
+                Some(Err(e)) => tracing::error!("failed to write periodic snapshot: {:?}", e),
+            }
+        }
+    });
+}
+
+/// Compact collections past the dead-node threshold during `window` only,
+/// checking once a minute.
+pub fn spawn_compaction_scheduler(state: AppState, window: CompactionWindow) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            if window.contains_now() {
+                compact_due_collections(&state, window).await;
+            }
+        }
+    });
+}
+
+/// Rebuild, one at a time, every loaded collection that needs compaction,
+/// stopping once `window` closes. Returns how many were compacted.
+pub async fn compact_due_collections(state: &AppState, window: CompactionWindow) -> usize {
+    let due: Vec<(String, String)> = state
+        .read_collections()
+        .await
+        .iter()
+        .flat_map(|(tenant, tenant_map)| {
+            tenant_map
+                .iter()
+                .filter(|(_, index)| index.needs_compaction() && !index.reindex_running())
+                .map(move |(name, _)| (tenant.clone(), name.clone()))
+        })
+        .collect();
+
+    let mut compacted = 0;
+    for (tenant, name) in due {
+        if !window.contains_now() {
+            tracing::info!("compaction window closed, deferring remaining collections");
+            break;
+        }
+        let _task = state.begin_heavy_task("scheduled compaction").await;
+        match routes::reindex(state, &tenant, &name, None).await {
+            Ok(r) => {
+                tracing::info!(
+                    "compacted collection '{}' (tenant {}) in maintenance window: {} vectors",
+                    name,
+                    tenant,
+                    r.vectors
+                );
+                compacted += 1;
+            }
+            Err(e) => tracing::warn!("failed to compact collection '{}': {}", name, e),
+        }
+    }
+    compacted
+}
+
+/// Keys from `OPENVDB_API_KEYS`: comma-separated `key` or `key:role`
+/// entries (see `Role`). Entries with an unknown role are skipped.
+pub fn api_keys_from_env() -> HashMap<String, Role> {
+    if let Ok(val) = std::env::var("OPENVDB_API_KEYS") {
+        let keys = parse_api_keys(&val);
         tracing::info!("loaded {} API keys from OPENVDB_API_KEYS", keys.len());
         keys
     } else {
-        let mut set = HashSet::new();
-        set.insert("dev-key".to_string());
         tracing::warn!("OPENVDB_API_KEYS not set, using default dev-key");
-        set
+        HashMap::from([("dev-key".to_string(), Role::ReadWrite)])
+    }
+}
+
+/// Parse the `OPENVDB_API_KEYS` syntax, see `api_keys_from_env`.
+pub fn parse_api_keys(val: &str) -> HashMap<String, Role> {
+    let mut keys = HashMap::new();
+    for entry in val.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (key, role) = match entry.rsplit_once(':') {
+            None => (entry, Ok(Role::default())),
+            Some((key, role)) => (key.trim(), role.trim().parse()),
+        };
+        match role {
+            Ok(role) if !key.is_empty() => {
+                keys.insert(key.to_string(), role);
+            }
+            Ok(_) => tracing::error!("ignoring API key entry with an empty key"),
+            Err(e) => tracing::error!("ignoring API key entry: {}", e),
+        }
     }
+    keys
 }
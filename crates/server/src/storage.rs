@@ -1,124 +1,368 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    marker::PhantomData,
     fs::{self, File, OpenOptions},
-    io::{BufRead, BufReader, BufWriter, Write},
-    path::Path,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::index::InMemoryIndex;
-
-pub const WAL_FILE: &str = "data/wal.jsonl";
-pub const SNAPSHOT_FILE: &str = "data/snapshot.json";
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum WalEntry {
-    CreateCollection {
-        tenant: String,
-        name: String,
-        dimension: usize,
-    },
-    DeleteCollection {
-        tenant: String,
-        name: String,
-    },
-    UpsertVector {
-        tenant: String,
-        collection: String,
-        id: String,
-        values: Vec<f32>,
-        metadata: Option<Value>,
-    },
-    DeleteVector {
-        tenant: String,
-        collection: String,
-        id: String,
-    },
-}
-
-fn ensure_data_dir() -> anyhow::Result<()> {
-    let path = Path::new("data");
-    if !path.exists() {
-        fs::create_dir_all(path)?;
+use crate::config::WalSync;
+use crate::index::{now_millis, CollectionConfig, InMemoryIndex, Timestamps};
+
+pub use fastdb_types::wal::WalEntry;
+
+/// The active WAL. Sealed segments (`wal.<n>.jsonl`) hold what came
+/// before it, see `wal_segments`.
+pub const WAL_FILE: &str = "wal.jsonl";
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+/// `SNAPSHOT_FILE` when `Config::snapshot_gzip_level` is set.
+pub const SNAPSHOT_GZ_FILE: &str = "snapshot.json.gz";
+/// Prefix of WALs kept after a snapshot (`wal.pre-snapshot.<ms>.jsonl`), see
+/// `write_snapshot_from_state`.
+pub const RETAINED_WAL_PREFIX: &str = "wal.pre-snapshot.";
+/// Past snapshots kept for time-travel queries
+/// (`history/snapshot.<ms>.json`), see `load_historical_collection`.
+pub const HISTORY_DIR: &str = "history";
+/// Present while the server is in maintenance mode, so the mode survives a
+/// restart until it is cleared through `POST /admin/maintenance`.
+pub const MAINTENANCE_FILE: &str = "maintenance";
+
+fn ensure_data_dir(data_dir: &Path) -> anyhow::Result<()> {
+    if !data_dir.exists() {
+        fs::create_dir_all(data_dir)?;
     }
     Ok(())
 }
 
-pub fn append_entry(entry: &WalEntry) -> anyhow::Result<()> {
-    ensure_data_dir()?;
+/// Serialize `entry` as one newline-terminated WAL line onto `buf`:
+/// the CRC32 of the JSON in hex, a space, then the JSON.
+///
+/// Lets callers encode a whole batch, or do the serialization work before
+/// taking the collections lock, and then hand the buffer to
+/// `AppState::append_wal` in one write.
+pub fn encode_entry(entry: &WalEntry, buf: &mut String) -> anyhow::Result<()> {
+    let json = serde_json::to_string(entry)?;
+    buf.push_str(&format!("{:08x} ", crc32fast::hash(json.as_bytes())));
+    buf.push_str(&json);
+    buf.push('\n');
+    Ok(())
+}
+
+/// Parse a line written by `encode_entry`, verifying its checksum. Lines
+/// from before checksums were added start with the JSON and are parsed
+/// unchecked.
+fn decode_line(line: &str) -> Result<WalEntry, String> {
+    let json = match line.split_once(' ') {
+        Some((crc, json)) if !line.starts_with('{') => {
+            let expected =
+                u32::from_str_radix(crc, 16).map_err(|_| "malformed checksum".to_string())?;
+            if crc32fast::hash(json.as_bytes()) != expected {
+                return Err("checksum mismatch".into());
+            }
+            json
+        }
+        _ => line,
+    };
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/// Cut a line left unfinished by a crash mid-append off the end of the WAL,
+/// so new appends don't land on its tail. Only safe before anything
+/// appends, i.e. at startup. Returns true if a partial line was removed.
+pub fn trim_torn_wal_tail(data_dir: &Path) -> anyhow::Result<bool> {
+    let path = data_dir.join(WAL_FILE);
+    if !path.exists() {
+        return Ok(false);
+    }
+    let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+    let len = file.metadata()?.len();
+
+    // Scan back from the end for the last newline.
+    let mut end = len;
+    let mut chunk = vec![0u8; 64 * 1024];
+    while end > 0 {
+        let start = end.saturating_sub(chunk.len() as u64);
+        let buf = &mut chunk[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(buf)?;
+        if let Some(i) = buf.iter().rposition(|&b| b == b'\n') {
+            end = start + i as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+    if end == len {
+        return Ok(false);
+    }
+    file.set_len(end)?;
+    file.sync_all()?;
+    tracing::warn!(
+        "dropped {} bytes of a WAL line torn by a crash mid-write",
+        len - end
+    );
+    Ok(true)
+}
+
+/// Sealed WAL segment `n`.
+fn wal_segment_path(data_dir: &Path, n: u32) -> PathBuf {
+    data_dir.join(format!("wal.{:04}.jsonl", n))
+}
+
+/// Sealed WAL segments in `data_dir` and their numbers, oldest first.
+fn wal_segments(data_dir: &Path) -> anyhow::Result<Vec<(u32, PathBuf)>> {
+    let mut segments = Vec::new();
+    if !data_dir.exists() {
+        return Ok(segments);
+    }
+    for entry in fs::read_dir(data_dir)? {
+        let entry = entry?;
+        let n = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("wal."))
+            .and_then(|rest| rest.strip_suffix(".jsonl"))
+            .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|n| n.parse().ok());
+        if let Some(n) = n {
+            segments.push((n, entry.path()));
+        }
+    }
+    segments.sort();
+    Ok(segments)
+}
+
+/// Every WAL file in replay order: the sealed segments, then `WAL_FILE`.
+pub(crate) fn wal_files(data_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = wal_segments(data_dir)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    files.push(data_dir.join(WAL_FILE));
+    Ok(files)
+}
+
+/// The WAL, kept open across appends (see `AppState::append_wal`).
+///
+/// Opened on first use, and again after `close`, which whoever renames or
+/// truncates the WAL must call.
+#[derive(Default)]
+pub struct WalWriter {
+    file: Option<File>,
+    // Length of the WAL after the last complete append
+    len: u64,
+    // Appended to since the last fsync
+    dirty: bool,
+}
+
+impl WalWriter {
+    /// Append lines produced by `encode_entry` with a single write, fsyncing
+    /// it under `WalSync::Always`. Once `WAL_FILE` is past `segment_bytes`
+    /// it is sealed as the next segment, so a batch never straddles two.
+    ///
+    /// A failed write is cut back off the file, so later appends never land
+    /// on the tail of a partial line. A crash mid-write still leaves one;
+    /// its checksum gives it away and replay drops it.
+    pub fn append(
+        &mut self,
+        data_dir: &Path,
+        lines: &str,
+        sync: WalSync,
+        segment_bytes: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                ensure_data_dir(data_dir)?;
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(data_dir.join(WAL_FILE))?;
+                self.len = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+
+        if let Err(e) = file.write_all(lines.as_bytes()) {
+            let _ = file.set_len(self.len);
+            self.file = None;
+            return Err(e.into());
+        }
+        self.len += lines.len() as u64;
+        if sync == WalSync::Always {
+            file.sync_all()?;
+        } else {
+            self.dirty = true;
+        }
+        if segment_bytes.is_some_and(|limit| self.len > limit) {
+            self.seal(data_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Rename `WAL_FILE` to the segment after the newest one; the next
+    /// append starts a fresh `WAL_FILE`.
+    fn seal(&mut self, data_dir: &Path) -> anyhow::Result<()> {
+        self.close()?;
+        let next = wal_segments(data_dir)?.last().map_or(0, |(n, _)| n + 1);
+        fs::rename(data_dir.join(WAL_FILE), wal_segment_path(data_dir, next))?;
+        File::open(data_dir)?.sync_all()?;
+        Ok(())
+    }
+
+    /// fsync if anything was appended since the last sync.
+    pub fn sync(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = &self.file
+            && self.dirty
+        {
+            file.sync_all()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Drop the open file after syncing it, so the next append reopens
+    /// `WAL_FILE`.
+    pub fn close(&mut self) -> anyhow::Result<()> {
+        let synced = self.sync();
+        self.file = None;
+        synced
+    }
+}
+
+/// fsync the WAL and the data directory, making every append that has
+/// returned so far durable regardless of the sync policy it was written with.
+pub fn sync_wal(data_dir: &Path) -> anyhow::Result<()> {
+    ensure_data_dir(data_dir)?;
 
     let file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(WAL_FILE)?;
-    let mut writer = BufWriter::new(file);
-
-    let line = serde_json::to_string(entry)?;
-    writer.write_all(line.as_bytes())?;
-    writer.write_all(b"\n")?;
-    writer.flush()?;
+        .open(data_dir.join(WAL_FILE))?;
+    file.sync_all()?;
+    // Persist the directory entry too, in case the WAL was just created.
+    File::open(data_dir)?.sync_all()?;
 
     Ok(())
 }
 
-/// Apply all WAL entries onto an existing collections map.
+/// Apply every WAL entry in `data_dir`, sealed segments first, on top of
+/// `collections`. A progress line is logged every `progress_every` applied
+/// entries (0 disables them), followed by a summary per file.
 ///
 /// This is the core replay logic used both when there is no snapshot
 /// (start from empty map) and when there *is* a snapshot (start from
 /// snapshot state, then apply changes since snapshot).
+///
+/// A final line cut short by a crash mid-write is ignored. A corrupt line
+/// anywhere else (bad checksum or JSON) is logged and skipped, or, with
+/// `strict`, aborts the replay with an error. Upserts into a collection
+/// that doesn't exist, or whose length disagrees with the collection's
+/// dimension, are logged and skipped too; the summary counts skips by
+/// reason.
 pub fn replay_wal(
+    data_dir: &Path,
     collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+    progress_every: usize,
+    strict: bool,
 ) -> anyhow::Result<()> {
-    ensure_data_dir()?;
+    replay_wal_for(data_dir, collections, progress_every, strict, None)
+}
 
-    let path = Path::new(WAL_FILE);
+/// `replay_wal`, optionally applying only `tenant`'s entries.
+fn replay_wal_for(
+    data_dir: &Path,
+    collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+    progress_every: usize,
+    strict: bool,
+    tenant: Option<&str>,
+) -> anyhow::Result<()> {
+    ensure_data_dir(data_dir)?;
+    for path in wal_files(data_dir)? {
+        replay_wal_file(&path, collections, progress_every, strict, tenant)?;
+    }
+    Ok(())
+}
+
+/// Apply the WAL at `path`, if it exists.
+fn replay_wal_file(
+    path: &Path,
+    collections: &mut HashMap<String, HashMap<String, InMemoryIndex>>,
+    progress_every: usize,
+    strict: bool,
+    tenant: Option<&str>,
+) -> anyhow::Result<()> {
     if !path.exists() {
         return Ok(());
     }
 
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
+    let started = Instant::now();
+    let mut applied = 0usize;
+    let mut skipped: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut skip = |reason| *skipped.entry(reason).or_default() += 1;
+    let mut buf = Vec::new();
 
-    for (lineno, line) in reader.lines().enumerate() {
-        let line = match line {
-            Ok(l) => l,
-            Err(e) => {
-                eprintln!("failed to read WAL line {}: {:?}", lineno + 1, e);
-                continue;
-            }
-        };
+    for lineno in 1usize.. {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        // Every append ends its lines with a newline; only a torn final
+        // write leaves one without.
+        let torn = buf.last() != Some(&b'\n');
 
+        let line = String::from_utf8_lossy(&buf);
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
         }
 
-        let entry: WalEntry = match serde_json::from_str(trimmed) {
+        let entry = match decode_line(trimmed) {
             Ok(e) => e,
+            Err(e) if torn => {
+                tracing::warn!(
+                    "ignoring truncated final WAL line {} ({}), likely a crash mid-write",
+                    lineno, e
+                );
+                skip("truncated");
+                continue;
+            }
+            Err(e) if strict => {
+                anyhow::bail!("WAL line {} is corrupt: {}", lineno, e);
+            }
             Err(e) => {
-                eprintln!(
-                    "failed to parse WAL line {}: {:?} (line: {})",
-                    lineno + 1,
-                    e,
-                    trimmed
+                tracing::warn!(
+                    "skipping corrupt WAL line {}: {} (line: {})",
+                    lineno, e, trimmed
                 );
+                skip("corrupt");
                 continue;
             }
         };
 
+        if tenant.is_some_and(|t| t != entry.tenant()) {
+            continue;
+        }
+
         match entry {
             WalEntry::CreateCollection {
                 tenant,
                 name,
                 dimension,
+                config,
             } => {
-                let tenant_map = collections.entry(tenant).or_insert_with(HashMap::new);
+                let tenant_map = collections.entry(tenant).or_default();
                 tenant_map
                     .entry(name)
-                    .or_insert_with(|| InMemoryIndex::new(dimension));
+                    .or_insert_with(|| InMemoryIndex::with_config(dimension, config));
             }
             WalEntry::DeleteCollection { tenant, name } => {
                 if let Some(tenant_map) = collections.get_mut(&tenant) {
@@ -134,13 +378,47 @@ pub fn replay_wal(
                 id,
                 values,
                 metadata,
+                written_at,
+                version,
             } => {
-                let dim = values.len();
-                let tenant_map = collections.entry(tenant).or_insert_with(HashMap::new);
-                let index = tenant_map
-                    .entry(collection)
-                    .or_insert_with(|| InMemoryIndex::new(dim));
-                let _ = index.upsert(id, values, metadata);
+                // The dimension comes from `CreateCollection` (or a
+                // `SetDimension`), never from the vectors themselves.
+                let Some(index) = collections
+                    .get_mut(&tenant)
+                    .and_then(|tenant_map| tenant_map.get_mut(&collection))
+                else {
+                    tracing::warn!(
+                        "WAL line {}: skipping upsert of '{}' into unknown collection '{}'",
+                        lineno, id, collection
+                    );
+                    skip("unknown collection");
+                    continue;
+                };
+                // Empty values are a pending vector, waiting on its embedding.
+                if index.dimension() != 0
+                    && !values.is_empty()
+                    && values.len() != index.dimension()
+                {
+                    tracing::warn!(
+                        "WAL line {}: skipping upsert of '{}': {} values, but '{}' has dimension {}",
+                        lineno,
+                        id,
+                        values.len(),
+                        collection,
+                        index.dimension()
+                    );
+                    skip("dimension mismatch");
+                    continue;
+                }
+                let at = written_at.unwrap_or_else(now_millis);
+                if let Err(e) = index.upsert_at(id.clone(), values, metadata, at) {
+                    tracing::warn!("WAL line {}: skipping upsert of '{}': {}", lineno, id, e);
+                    skip("rejected upsert");
+                    continue;
+                }
+                if let Some(version) = version {
+                    index.set_version(&id, version);
+                }
             }
             WalEntry::DeleteVector {
                 tenant,
@@ -156,17 +434,117 @@ pub fn replay_wal(
                     }
                 }
             }
+            WalEntry::SetDimension {
+                tenant,
+                collection,
+                dimension,
+            } => {
+                if let Some(index) = collections
+                    .get_mut(&tenant)
+                    .and_then(|tenant_map| tenant_map.get_mut(&collection))
+                    && let Err(e) = index.set_dimension(dimension)
+                {
+                    tracing::warn!("WAL line {}: {}", lineno, e);
+                }
+            }
+            WalEntry::SetCapacity {
+                tenant,
+                collection,
+                capacity,
+            } => {
+                if let Some(index) = collections
+                    .get_mut(&tenant)
+                    .and_then(|tenant_map| tenant_map.get_mut(&collection))
+                {
+                    index.set_capacity(capacity);
+                }
+            }
+            WalEntry::UpdateMetadata {
+                tenant,
+                collection,
+                id,
+                metadata,
+                written_at,
+            } => {
+                if let Some(index) = collections
+                    .get_mut(&tenant)
+                    .and_then(|tenant_map| tenant_map.get_mut(&collection))
+                {
+                    index.update_metadata_at(&id, metadata, written_at.unwrap_or_else(now_millis));
+                }
+            }
+        }
+
+        applied += 1;
+        if progress_every > 0 && applied.is_multiple_of(progress_every) {
+            tracing::info!(
+                "WAL replay: {} entries applied ({:.1}s elapsed)",
+                applied,
+                started.elapsed().as_secs_f64()
+            );
         }
     }
 
+    let reasons: Vec<String> = skipped
+        .iter()
+        .map(|(reason, n)| format!("{} {}", n, reason))
+        .collect();
+    tracing::info!(
+        "WAL replay finished: {} entries applied, {} skipped{} in {:.1}s",
+        applied,
+        skipped.values().sum::<usize>(),
+        if reasons.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", reasons.join(", "))
+        },
+        started.elapsed().as_secs_f64()
+    );
     Ok(())
 }
 
 /// Helper: load collections *only* from WAL (no snapshot).
 pub fn load_collections_from_wal(
+    data_dir: &Path,
+    progress_every: usize,
+    strict: bool,
 ) -> anyhow::Result<HashMap<String, HashMap<String, InMemoryIndex>>> {
     let mut collections: HashMap<String, HashMap<String, InMemoryIndex>> = HashMap::new();
-    replay_wal(&mut collections)?;
+    replay_wal(data_dir, &mut collections, progress_every, strict)?;
+    Ok(collections)
+}
+
+/// Rebuild the full in-memory state from `data_dir`: snapshot first (if any),
+/// then every WAL entry written since. Failures are logged and whatever could
+/// be recovered is returned, so the server can still start; only a corrupt
+/// WAL line under `strict` is an error.
+pub fn load_collections(
+    data_dir: &Path,
+    progress_every: usize,
+    strict: bool,
+) -> anyhow::Result<HashMap<String, HashMap<String, InMemoryIndex>>> {
+    let mut collections = match load_collections_from_snapshot(data_dir) {
+        Ok(Some(map)) => {
+            tracing::info!("loaded collections from snapshot ({} tenants)", map.len());
+            map
+        }
+        Ok(None) => {
+            tracing::info!("no snapshot found, starting from empty state");
+            HashMap::new()
+        }
+        Err(e) => {
+            tracing::error!("failed to load snapshot: {:?}", e);
+            recover_from_retained(data_dir, progress_every, strict, None)
+        }
+    };
+
+    if let Err(e) = replay_wal(data_dir, &mut collections, progress_every, strict) {
+        if strict {
+            return Err(e);
+        }
+        tracing::error!("failed to replay WAL: {:?}", e);
+    }
+
     Ok(collections)
 }
 
@@ -179,11 +557,24 @@ struct SnapshotVector {
     id: String,
     values: Vec<f32>,
     metadata: Option<Value>,
+    // Absent in snapshots written before vectors carried timestamps.
+    #[serde(default)]
+    created_at: Option<u64>,
+    #[serde(default)]
+    updated_at: Option<u64>,
+    // Absent in snapshots written before vectors were versioned.
+    #[serde(default)]
+    version: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct SnapshotCollection {
     dimension: usize,
+    #[serde(default)]
+    config: CollectionConfig,
+    /// Vectors below are stored unit-length (`normalized_cosine`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    normalized: bool,
     vectors: Vec<SnapshotVector>,
 }
 
@@ -195,28 +586,88 @@ struct Snapshot {
 /// Load collections from snapshot.json if it exists.
 /// Returns Ok(Some(map)) if snapshot found, Ok(None) if not present.
 pub fn load_collections_from_snapshot(
+    data_dir: &Path,
 ) -> anyhow::Result<Option<HashMap<String, HashMap<String, InMemoryIndex>>>> {
-    ensure_data_dir()?;
+    load_snapshot_tenants(data_dir, None)
+}
+
+/// Load the snapshot, keeping only `tenant` when given. Other tenants are
+/// skipped while parsing, without materializing their vectors.
+fn load_snapshot_tenants(
+    data_dir: &Path,
+    tenant: Option<&str>,
+) -> anyhow::Result<Option<HashMap<String, HashMap<String, InMemoryIndex>>>> {
+    ensure_data_dir(data_dir)?;
+    load_snapshot_file(&current_snapshot(data_dir), tenant)
+}
+
+/// The snapshot a restart loads: the gzipped one if there is one, else
+/// the plain one (which may not exist either).
+pub fn current_snapshot(data_dir: &Path) -> PathBuf {
+    let gz = data_dir.join(SNAPSHOT_GZ_FILE);
+    if gz.exists() { gz } else { data_dir.join(SNAPSHOT_FILE) }
+}
 
-    let path = Path::new(SNAPSHOT_FILE);
+/// Open a snapshot for reading, gunzipping it if it starts with the gzip
+/// magic. Retained and history copies keep their `.json` names either way.
+fn open_snapshot(path: &Path) -> anyhow::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(GzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Serialize `snap` to `path`, gzipped at `gzip_level` if set, and fsync it.
+fn write_snapshot_file(path: &Path, snap: &Snapshot, gzip_level: Option<u32>) -> anyhow::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    let mut writer = BufWriter::new(file);
+    match gzip_level {
+        Some(level) => {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::new(level.min(9)));
+            serde_json::to_writer(&mut encoder, snap)?;
+            encoder.finish()?;
+        }
+        None => serde_json::to_writer(&mut writer, snap)?,
+    }
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    Ok(())
+}
+
+/// `load_snapshot_tenants` for the snapshot at `path`.
+fn load_snapshot_file(
+    path: &Path,
+    tenant: Option<&str>,
+) -> anyhow::Result<Option<HashMap<String, HashMap<String, InMemoryIndex>>>> {
     if !path.exists() {
         return Ok(None);
     }
 
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let snap: Snapshot = serde_json::from_reader(reader)?;
+    let mut de = serde_json::Deserializer::from_reader(open_snapshot(path)?);
+    let keep = |name: &str| tenant.is_none_or(|t| t == name);
+    let tenants = SnapshotSeed::<SnapshotCollection>::new(&keep).deserialize(&mut de)?;
 
     let mut result: HashMap<String, HashMap<String, InMemoryIndex>> = HashMap::new();
 
-    for (tenant, collections) in snap.tenants {
+    for (tenant, collections) in tenants {
         let mut tenant_map: HashMap<String, InMemoryIndex> = HashMap::new();
 
         for (name, sc) in collections {
-            let mut index = InMemoryIndex::new(sc.dimension);
+            let mut index = InMemoryIndex::with_config(sc.dimension, sc.config);
             for v in sc.vectors {
-                let _ = index.upsert(v.id, v.values, v.metadata);
+                let updated_at = v.updated_at.unwrap_or_else(now_millis);
+                let times = Timestamps {
+                    created_at: v.created_at.unwrap_or(updated_at),
+                    updated_at,
+                };
+                let version = v.version.unwrap_or(1);
+                let _ = index.restore(v.id, v.values, v.metadata, times, version);
             }
             tenant_map.insert(name, index);
         }
@@ -227,28 +678,159 @@ pub fn load_collections_from_snapshot(
     Ok(Some(result))
 }
 
-/// Write a full snapshot of all tenants/collections to snapshot.json
-/// and truncate the WAL afterwards.
-pub fn write_snapshot_from_state(
-    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
-) -> anyhow::Result<()> {
-    ensure_data_dir()?;
+/// Rebuild one tenant's collections from snapshot + WAL, for tenants that
+/// are not resident in memory. Like `load_collections`, failures are logged
+/// and whatever could be recovered is returned.
+pub fn load_tenant(
+    data_dir: &Path,
+    tenant: &str,
+    progress_every: usize,
+    strict: bool,
+) -> HashMap<String, InMemoryIndex> {
+    let mut collections = match load_snapshot_tenants(data_dir, Some(tenant)) {
+        Ok(map) => map.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("failed to load snapshot for tenant: {:?}", e);
+            recover_from_retained(data_dir, progress_every, strict, Some(tenant))
+        }
+    };
 
-    // Build snapshot struct
+    let replayed = replay_wal_for(data_dir, &mut collections, progress_every, strict, Some(tenant));
+    if let Err(e) = replayed {
+        tracing::error!("failed to replay WAL for tenant: {:?}", e);
+    }
+
+    collections.remove(tenant).unwrap_or_default()
+}
+
+type SnapshotTenants<C> = HashMap<String, HashMap<String, C>>;
+
+/// Deserializes a `Snapshot`'s tenants, dropping those `keep` rejects
+/// without materializing them, and each kept collection as a `C`.
+struct SnapshotSeed<'a, C> {
+    keep: &'a dyn Fn(&str) -> bool,
+    collection: PhantomData<C>,
+}
+
+impl<'a, C> SnapshotSeed<'a, C> {
+    fn new(keep: &'a dyn Fn(&str) -> bool) -> Self {
+        Self {
+            keep,
+            collection: PhantomData,
+        }
+    }
+}
+
+impl<'de, C: Deserialize<'de>> DeserializeSeed<'de> for SnapshotSeed<'_, C> {
+    type Value = SnapshotTenants<C>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, C: Deserialize<'de>> Visitor<'de> for SnapshotSeed<'_, C> {
+    type Value = SnapshotTenants<C>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a snapshot object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tenants = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "tenants" {
+                tenants = Some(map.next_value_seed(TenantsSeed::<C>::new(self.keep))?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        tenants.ok_or_else(|| de::Error::missing_field("tenants"))
+    }
+}
+
+struct TenantsSeed<'a, C> {
+    keep: &'a dyn Fn(&str) -> bool,
+    collection: PhantomData<C>,
+}
+
+impl<'a, C> TenantsSeed<'a, C> {
+    fn new(keep: &'a dyn Fn(&str) -> bool) -> Self {
+        Self {
+            keep,
+            collection: PhantomData,
+        }
+    }
+}
+
+impl<'de, C: Deserialize<'de>> DeserializeSeed<'de> for TenantsSeed<'_, C> {
+    type Value = SnapshotTenants<C>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+        de.deserialize_map(self)
+    }
+}
+
+impl<'de, C: Deserialize<'de>> Visitor<'de> for TenantsSeed<'_, C> {
+    type Value = SnapshotTenants<C>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of tenants")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tenants = HashMap::new();
+        while let Some(name) = map.next_key::<String>()? {
+            if (self.keep)(&name) {
+                tenants.insert(name, map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(tenants)
+    }
+}
+
+/// What a snapshot persisted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    pub tenants: usize,
+    pub collections: usize,
+    pub vectors: usize,
+    /// Timestamp the snapshot was kept under in `HISTORY_DIR`, if any.
+    pub history: Option<u64>,
+}
+
+fn build_snapshot(
+    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
+) -> (Snapshot, SnapshotSummary) {
+    let mut summary = SnapshotSummary::default();
     let mut tenants: HashMap<String, HashMap<String, SnapshotCollection>> = HashMap::new();
 
     for (tenant, col_map) in collections.iter() {
         let mut col_snap_map = HashMap::new();
 
         for (name, index) in col_map.iter() {
-            let vectors = index
-                .export_vectors()
+            let exported = index.export_vectors();
+            summary.collections += 1;
+            summary.vectors += exported.vectors.len();
+            let vectors = exported
+                .vectors
                 .into_iter()
-                .map(|(id, values, metadata)| SnapshotVector { id, values, metadata })
+                .map(|v| SnapshotVector {
+                    id: v.id,
+                    values: v.values,
+                    metadata: v.metadata,
+                    created_at: Some(v.times.created_at),
+                    updated_at: Some(v.times.updated_at),
+                    version: Some(v.version),
+                })
                 .collect();
 
             let sc = SnapshotCollection {
                 dimension: index.dimension(),
+                config: index.config().clone(),
+                normalized: exported.normalized,
                 vectors,
             };
 
@@ -258,34 +840,695 @@ pub fn write_snapshot_from_state(
         tenants.insert(tenant.clone(), col_snap_map);
     }
 
-    let snap = Snapshot { tenants };
+    summary.tenants = tenants.len();
+    (Snapshot { tenants }, summary)
+}
+
+/// Counts bytes instead of storing them.
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Bytes `value` takes serialized.
+fn json_len<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<u64> {
+    let mut counter = ByteCounter::default();
+    serde_json::to_writer(&mut counter, value)?;
+    Ok(counter.0)
+}
+
+/// Bytes of a JSON object whose members (key and value) take `members`.
+fn object_len(members: impl IntoIterator<Item = u64>) -> u64 {
+    let (count, bytes) = members
+        .into_iter()
+        .fold((0u64, 0u64), |(count, bytes), len| (count + 1, bytes + len));
+    // Braces, plus a comma between members.
+    2 + bytes + count.saturating_sub(1)
+}
+
+/// A `SnapshotVector` borrowed from the live index.
+#[derive(Serialize)]
+struct SnapshotVectorRef<'a> {
+    id: &'a str,
+    values: &'a [f32],
+    metadata: Option<&'a Value>,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    version: Option<u64>,
+}
+
+/// A `SnapshotCollection` borrowed from the live index: serializes the
+/// same bytes without copying any vector.
+#[derive(Serialize)]
+struct SnapshotCollectionRef<'a> {
+    dimension: usize,
+    config: &'a CollectionConfig,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    normalized: bool,
+    vectors: StoredVectors<'a>,
+}
+
+struct StoredVectors<'a>(&'a InMemoryIndex);
+
+impl Serialize for StoredVectors<'_> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(self.0.stored_vectors().map(|v| SnapshotVectorRef {
+            id: v.id,
+            values: v.values,
+            metadata: v.metadata,
+            created_at: Some(v.times.created_at),
+            updated_at: Some(v.times.updated_at),
+            version: Some(v.version),
+        }))
+    }
+}
+
+/// A collection of an evicted tenant, measured from disk by
+/// `estimate_snapshot`: its snapshot bytes without the vectors, and each
+/// vector's bytes split into the part outside its metadata and the
+/// metadata itself, so WAL entries can replace either.
+#[derive(Default)]
+struct MeasuredCollection {
+    dimension: usize,
+    config: CollectionConfig,
+    vectors: HashMap<String, (u64, u64)>,
+}
+
+impl MeasuredCollection {
+    fn new(dimension: usize, config: CollectionConfig) -> Self {
+        Self {
+            dimension,
+            config,
+            vectors: HashMap::new(),
+        }
+    }
+
+    /// Bytes of the collection as `write_snapshot_from_state` would write it.
+    fn len(&self) -> anyhow::Result<u64> {
+        let empty = SnapshotCollection {
+            dimension: self.dimension,
+            config: self.config.clone(),
+            normalized: self.config.metric.normalizes(),
+            vectors: Vec::new(),
+        };
+        let vectors: u64 = self.vectors.values().map(|(rest, meta)| rest + meta).sum();
+        let commas = (self.vectors.len() as u64).saturating_sub(1);
+        Ok(json_len(&empty)? + vectors + commas)
+    }
+
+    /// Record a vector's size under `id`.
+    fn measure(&mut self, id: &str, v: SnapshotVectorRef) -> anyhow::Result<()> {
+        let metadata = json_len(&v.metadata)?;
+        let rest = json_len(&SnapshotVectorRef { metadata: None, ..v })? - json_len(&None::<Value>)?;
+        self.vectors.insert(id.to_string(), (rest, metadata));
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for MeasuredCollection {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        de.deserialize_map(MeasuredCollectionVisitor)
+    }
+}
+
+struct MeasuredCollectionVisitor;
+
+impl<'de> Visitor<'de> for MeasuredCollectionVisitor {
+    type Value = MeasuredCollection;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a snapshot collection")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut measured = MeasuredCollection::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "dimension" => measured.dimension = map.next_value()?,
+                "config" => measured.config = map.next_value()?,
+                "vectors" => measured.vectors = map.next_value::<MeasuredVectors>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(measured)
+    }
+}
+
+/// A snapshot collection's vectors, each read, measured and dropped before
+/// the next, so even a huge collection is never held.
+struct MeasuredVectors(HashMap<String, (u64, u64)>);
+
+impl<'de> Deserialize<'de> for MeasuredVectors {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        de.deserialize_seq(MeasuredVectorsVisitor)
+    }
+}
+
+struct MeasuredVectorsVisitor;
+
+impl<'de> Visitor<'de> for MeasuredVectorsVisitor {
+    type Value = MeasuredVectors;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of vectors")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut measured = MeasuredCollection::default();
+        while let Some(v) = seq.next_element::<SnapshotVector>()? {
+            let v_ref = SnapshotVectorRef {
+                id: &v.id,
+                values: &v.values,
+                metadata: v.metadata.as_ref(),
+                created_at: v.created_at,
+                updated_at: v.updated_at,
+                version: v.version,
+            };
+            measured.measure(&v.id, v_ref).map_err(de::Error::custom)?;
+        }
+        Ok(MeasuredVectors(measured.vectors))
+    }
+}
+
+/// Measure `tenants` as the next snapshot would hold them, from their part
+/// of the current snapshot and their WAL entries since, without loading
+/// them (no index is built, and vectors are held one at a time).
+fn measure_tenants_on_disk(
+    data_dir: &Path,
+    tenants: &HashSet<String>,
+) -> anyhow::Result<SnapshotTenants<MeasuredCollection>> {
+    let snapshot_path = current_snapshot(data_dir);
+    let mut measured = if snapshot_path.exists() {
+        let mut de = serde_json::Deserializer::from_reader(open_snapshot(&snapshot_path)?);
+        let keep = |name: &str| tenants.contains(name);
+        SnapshotSeed::<MeasuredCollection>::new(&keep).deserialize(&mut de)?
+    } else {
+        HashMap::new()
+    };
+
+    for wal_path in wal_files(data_dir)? {
+        if !wal_path.exists() {
+            continue;
+        }
+        for line in BufReader::new(File::open(wal_path)?).lines() {
+            let Ok(entry) = decode_line(line?.trim()) else {
+                continue;
+            };
+            if !tenants.contains(entry.tenant()) {
+                continue;
+            }
+            match entry {
+                WalEntry::CreateCollection {
+                    tenant,
+                    name,
+                    dimension,
+                    config,
+                } => {
+                    let collection = MeasuredCollection::new(dimension, config);
+                    measured.entry(tenant).or_default().insert(name, collection);
+                }
+                WalEntry::DeleteCollection { tenant, name } => {
+                    if let Some(cols) = measured.get_mut(&tenant) {
+                        cols.remove(&name);
+                    }
+                }
+                WalEntry::UpsertVector {
+                    tenant,
+                    collection,
+                    id,
+                    values,
+                    metadata,
+                    written_at,
+                    version,
+                } => {
+                    let Some(col) = measured.get_mut(&tenant).and_then(|c| c.get_mut(&collection))
+                    else {
+                        continue;
+                    };
+                    let written_at = written_at.unwrap_or_else(now_millis);
+                    let v = SnapshotVectorRef {
+                        id: &id,
+                        values: &values,
+                        metadata: metadata.as_ref(),
+                        created_at: Some(written_at),
+                        updated_at: Some(written_at),
+                        version: Some(version.unwrap_or(1)),
+                    };
+                    col.measure(&id, v)?;
+                }
+                WalEntry::DeleteVector {
+                    tenant,
+                    collection,
+                    id,
+                } => {
+                    if let Some(col) = measured.get_mut(&tenant).and_then(|c| c.get_mut(&collection)) {
+                        col.vectors.remove(&id);
+                    }
+                }
+                WalEntry::SetDimension {
+                    tenant,
+                    collection,
+                    dimension,
+                } => {
+                    if let Some(col) = measured.get_mut(&tenant).and_then(|c| c.get_mut(&collection)) {
+                        col.dimension = dimension;
+                    }
+                }
+                WalEntry::SetCapacity {
+                    tenant,
+                    collection,
+                    capacity,
+                } => {
+                    if let Some(col) = measured.get_mut(&tenant).and_then(|c| c.get_mut(&collection)) {
+                        col.config.capacity = Some(capacity);
+                    }
+                }
+                WalEntry::UpdateMetadata {
+                    tenant,
+                    collection,
+                    id,
+                    metadata,
+                    ..
+                } => {
+                    if let Some((_, meta)) = measured
+                        .get_mut(&tenant)
+                        .and_then(|c| c.get_mut(&collection))
+                        .and_then(|col| col.vectors.get_mut(&id))
+                    {
+                        *meta = json_len(&metadata)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(measured)
+}
+
+/// Size in bytes of the snapshot `write_snapshot_from_state` would write
+/// (before any gzip), and what it would contain. Serializes the resident
+/// `collections` without keeping the output or copying any vector, so it
+/// costs the CPU of a real snapshot but neither its memory nor disk I/O.
+/// `evicted` tenants are measured from their files on disk instead of
+/// being loaded; version numbers and timestamps the WAL leaves out are
+/// guessed, so their part can be off by a few bytes per vector.
+pub fn estimate_snapshot(
+    data_dir: &Path,
+    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
+    evicted: &HashSet<String>,
+) -> anyhow::Result<(SnapshotSummary, u64)> {
+    let mut summary = SnapshotSummary::default();
+    let mut tenant_lens = Vec::new();
+
+    for (tenant, col_map) in collections {
+        let mut col_lens = Vec::new();
+        for (name, index) in col_map {
+            let col = SnapshotCollectionRef {
+                dimension: index.dimension(),
+                config: index.config(),
+                normalized: index.config().metric.normalizes(),
+                vectors: StoredVectors(index),
+            };
+            summary.collections += 1;
+            summary.vectors += index.stored_vectors().count();
+            col_lens.push(json_len(name)? + 1 + json_len(&col)?);
+        }
+        tenant_lens.push(json_len(tenant)? + 1 + object_len(col_lens));
+    }
+
+    if !evicted.is_empty() {
+        for (tenant, cols) in measure_tenants_on_disk(data_dir, evicted)? {
+            let mut col_lens = Vec::new();
+            for (name, col) in &cols {
+                summary.collections += 1;
+                summary.vectors += col.vectors.len();
+                col_lens.push(json_len(name)? + 1 + col.len()?);
+            }
+            tenant_lens.push(json_len(&tenant)? + 1 + object_len(col_lens));
+        }
+    }
+
+    summary.tenants = tenant_lens.len();
+    // `{"tenants":` + the tenants object + `}`
+    Ok((summary, json_len("tenants")? + 3 + object_len(tenant_lens)))
+}
+
+/// Write a full snapshot of all tenants/collections to snapshot.json
+/// and truncate the WAL afterwards. The WAL is only touched once the
+/// snapshot is fsynced and renamed into place, so a crash in between
+/// replays it on top of whichever snapshot survived.
+///
+/// With `wal_retention` set the WAL is moved aside instead, together with a
+/// link to the snapshot it applies to, and kept until the retention expires
+/// or the next snapshot succeeds. If the new snapshot turns out corrupt,
+/// `load_collections` rebuilds the state from that pair.
+///
+/// With `history` above zero the snapshot is also kept in `HISTORY_DIR`,
+/// alongside the `history - 1` before it.
+///
+/// With `gzip_level` set the snapshot is written gzipped as
+/// `SNAPSHOT_GZ_FILE`. A snapshot left in the other format is removed only
+/// after the new one is in place and before the WAL is touched, so a crash
+/// in between loads the old one and replays the whole WAL on top.
+pub fn write_snapshot_from_state(
+    data_dir: &Path,
+    collections: &HashMap<String, HashMap<String, InMemoryIndex>>,
+    wal_retention: Option<Duration>,
+    history: usize,
+    gzip_level: Option<u32>,
+) -> anyhow::Result<SnapshotSummary> {
+    ensure_data_dir(data_dir)?;
+    let (snap, mut summary) = build_snapshot(collections);
 
     // Write to temp file first, then atomically rename
-    let tmp_path = Path::new("data/snapshot.json.tmp");
-    {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(tmp_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, &snap)?;
+    let tmp_path = data_dir.join("snapshot.json.tmp");
+    write_snapshot_file(&tmp_path, &snap, gzip_level)?;
+    let (target, other) = match gzip_level {
+        Some(_) => (SNAPSHOT_GZ_FILE, SNAPSHOT_FILE),
+        None => (SNAPSHOT_FILE, SNAPSHOT_GZ_FILE),
+    };
+    let replace_snapshot = || -> anyhow::Result<()> {
+        fs::rename(&tmp_path, data_dir.join(target))?;
+        match fs::remove_file(data_dir.join(other)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        File::open(data_dir)?.sync_all()?;
+        Ok(())
+    };
+
+    let Some(retention) = wal_retention else {
+        replace_snapshot()?;
+        summary.history = keep_in_history(data_dir, history)?;
+
+        // Truncate WAL after successful snapshot (simple compaction)
+        truncate_wal(data_dir)?;
+        remove_retained(data_dir, |_| true)?;
+        return Ok(summary);
+    };
+
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let (base_path, wal_path) = retained_paths(data_dir, stamp);
+    let snapshot_path = current_snapshot(data_dir);
+    if snapshot_path.exists() {
+        // Keep the outgoing snapshot alive under a second name; the rename
+        // below then swaps in the new one without copying either.
+        let _ = fs::remove_file(&base_path);
+        if fs::hard_link(&snapshot_path, &base_path).is_err() {
+            fs::copy(&snapshot_path, &base_path)?;
+        }
+    }
+    replace_snapshot()?;
+    summary.history = keep_in_history(data_dir, history)?;
+
+    retain_wal(data_dir, &wal_path)?;
+    truncate_wal(data_dir)?;
+
+    // The new pair supersedes every older one.
+    remove_retained(data_dir, |s| s != stamp)?;
+    prune_retained_wals(data_dir, retention)?;
+
+    Ok(summary)
+}
+
+/// Link the current snapshot into `HISTORY_DIR` and drop all but the newest
+/// `keep` there. Returns the timestamp it was kept under.
+fn keep_in_history(data_dir: &Path, keep: usize) -> anyhow::Result<Option<u64>> {
+    if keep == 0 {
+        return Ok(None);
+    }
+    let dir = data_dir.join(HISTORY_DIR);
+    fs::create_dir_all(&dir)?;
+
+    // Two snapshots within a millisecond still get distinct names.
+    let mut stamp = now_millis();
+    while history_path(data_dir, stamp).exists() {
+        stamp += 1;
     }
+    let path = history_path(data_dir, stamp);
+    let current = current_snapshot(data_dir);
+    if fs::hard_link(&current, &path).is_err() {
+        fs::copy(&current, &path)?;
+    }
+
+    let stamps = history_stamps(data_dir)?;
+    for old in &stamps[..stamps.len().saturating_sub(keep)] {
+        fs::remove_file(history_path(data_dir, *old))?;
+    }
+    Ok(Some(stamp))
+}
+
+fn history_path(data_dir: &Path, stamp: u64) -> PathBuf {
+    data_dir
+        .join(HISTORY_DIR)
+        .join(format!("snapshot.{}.json", stamp))
+}
 
-    fs::rename(tmp_path, SNAPSHOT_FILE)?;
+/// Timestamps of the snapshots in `HISTORY_DIR`, oldest first.
+pub fn history_stamps(data_dir: &Path) -> anyhow::Result<Vec<u64>> {
+    let dir = data_dir.join(HISTORY_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut stamps = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let stamp = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("snapshot."))
+            .and_then(|n| n.strip_suffix(".json"))
+            .and_then(|n| n.parse::<u64>().ok());
+        stamps.extend(stamp);
+    }
+    stamps.sort_unstable();
+    Ok(stamps)
+}
 
-    // Truncate WAL after successful snapshot (simple compaction)
-    truncate_wal()?;
+/// Build a throwaway index for one collection as of the history snapshot
+/// taken at `stamp`. `Ok(None)` if there is no such snapshot, or the
+/// collection wasn't in it.
+///
+/// Nothing is cached: every call parses the tenant's part of the snapshot
+/// and rebuilds the collection's HNSW graph, so it briefly needs as much
+/// memory as the tenant did at the time, on top of the live data.
+pub fn load_historical_collection(
+    data_dir: &Path,
+    stamp: u64,
+    tenant: &str,
+    name: &str,
+) -> anyhow::Result<Option<InMemoryIndex>> {
+    let snapshot = load_snapshot_file(&history_path(data_dir, stamp), Some(tenant))?;
+    Ok(snapshot
+        .and_then(|mut tenants| tenants.remove(tenant))
+        .and_then(|mut collections| collections.remove(name)))
+}
 
+/// The retained snapshot and WAL written by the snapshot taken at `stamp`.
+fn retained_paths(data_dir: &Path, stamp: u64) -> (PathBuf, PathBuf) {
+    (
+        data_dir.join(format!("snapshot.pre-snapshot.{}.json", stamp)),
+        data_dir.join(format!("{}{}.jsonl", RETAINED_WAL_PREFIX, stamp)),
+    )
+}
+
+/// Stamps of the retained pairs in `data_dir`, oldest first.
+fn retained_stamps(data_dir: &Path) -> anyhow::Result<Vec<u64>> {
+    let mut stamps = Vec::new();
+    for entry in fs::read_dir(data_dir)? {
+        let name = entry?.file_name();
+        let stamp = name
+            .to_str()
+            .and_then(|n| n.strip_prefix(RETAINED_WAL_PREFIX))
+            .and_then(|n| n.strip_suffix(".jsonl"))
+            .and_then(|n| n.parse::<u64>().ok());
+        stamps.extend(stamp);
+    }
+    stamps.sort_unstable();
+    Ok(stamps)
+}
+
+fn remove_retained(data_dir: &Path, remove: impl Fn(u64) -> bool) -> anyhow::Result<()> {
+    for stamp in retained_stamps(data_dir)? {
+        if !remove(stamp) {
+            continue;
+        }
+        let (base_path, wal_path) = retained_paths(data_dir, stamp);
+        for path in [base_path, wal_path] {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+    }
     Ok(())
 }
 
-fn truncate_wal() -> anyhow::Result<()> {
+/// Delete retained WALs (and their snapshots) older than `retention`.
+pub fn prune_retained_wals(data_dir: &Path, retention: Duration) -> anyhow::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let cutoff = now.saturating_sub(retention.as_millis() as u64);
+    remove_retained(data_dir, |stamp| stamp < cutoff)
+}
+
+/// Rebuild the state the current snapshot was written from, using the newest
+/// retained pair: its snapshot (if the deployment had one back then) plus
+/// its WAL. The caller replays the current WAL on top. Returns an empty map
+/// if nothing usable was retained.
+fn recover_from_retained(
+    data_dir: &Path,
+    progress_every: usize,
+    strict: bool,
+    tenant: Option<&str>,
+) -> HashMap<String, HashMap<String, InMemoryIndex>> {
+    let stamp = match retained_stamps(data_dir) {
+        Ok(stamps) => stamps.last().copied(),
+        Err(e) => {
+            tracing::error!("failed to list retained WALs: {:?}", e);
+            None
+        }
+    };
+    let Some(stamp) = stamp else {
+        tracing::error!("no retained WAL to recover from, starting from empty state");
+        return HashMap::new();
+    };
+
+    let (base_path, wal_path) = retained_paths(data_dir, stamp);
+    let mut collections = match load_snapshot_file(&base_path, tenant) {
+        Ok(map) => map.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("retained snapshot is unreadable too: {:?}", e);
+            return HashMap::new();
+        }
+    };
+    if let Err(e) = replay_wal_file(&wal_path, &mut collections, progress_every, strict, tenant) {
+        tracing::error!("failed to replay retained WAL: {:?}", e);
+    }
+    tracing::warn!("recovered state from WAL retained at {}", wal_path.display());
+    collections
+}
+
+/// Move the whole WAL to `wal_path`: renamed when there is only
+/// `WAL_FILE`, otherwise the segments and `WAL_FILE` concatenated in order.
+fn retain_wal(data_dir: &Path, wal_path: &Path) -> anyhow::Result<()> {
+    let wal = data_dir.join(WAL_FILE);
+    let segments = wal_segments(data_dir)?;
+    if segments.is_empty() {
+        if wal.exists() {
+            fs::rename(&wal, wal_path)?;
+        }
+        return Ok(());
+    }
+
+    let mut out = BufWriter::new(File::create(wal_path)?);
+    for path in wal_files(data_dir)? {
+        if path.exists() {
+            std::io::copy(&mut File::open(&path)?, &mut out)?;
+        }
+    }
+    out.into_inner()?.sync_all()?;
+    for (_, path) in segments {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Empty the WAL once a snapshot covers it: sealed segments are deleted
+/// and `WAL_FILE` is truncated.
+fn truncate_wal(data_dir: &Path) -> anyhow::Result<()> {
+    for (_, path) in wal_segments(data_dir)? {
+        fs::remove_file(path)?;
+    }
     let file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
-        .open(WAL_FILE)?;
+        .open(data_dir.join(WAL_FILE))?;
     file.sync_all()?;
     Ok(())
 }
+
+///////////////////////////////////////
+// Reconciliation
+///////////////////////////////////////
+
+#[derive(Deserialize)]
+struct SnapshotInventory {
+    tenants: HashMap<String, HashMap<String, IgnoredAny>>,
+}
+
+/// The `(tenant, collection)` pairs that a restart would recreate from disk,
+/// i.e. the snapshot's collections with the WAL's creates/deletes applied.
+///
+/// Only names are tracked: vectors are skipped while parsing the snapshot and
+/// no index is built, so this is much cheaper than `load_collections`.
+pub fn disk_inventory(data_dir: &Path) -> anyhow::Result<BTreeSet<(String, String)>> {
+    let mut found = BTreeSet::new();
+
+    let snapshot_path = current_snapshot(data_dir);
+    if snapshot_path.exists() {
+        let inv: SnapshotInventory = serde_json::from_reader(open_snapshot(&snapshot_path)?)?;
+        for (tenant, cols) in inv.tenants {
+            for name in cols.into_keys() {
+                found.insert((tenant.clone(), name));
+            }
+        }
+    }
+
+    for wal_path in wal_files(data_dir)? {
+        if !wal_path.exists() {
+            continue;
+        }
+        let reader = BufReader::new(File::open(wal_path)?);
+        for line in reader.lines() {
+            let Ok(entry) = decode_line(line?.trim()) else {
+                continue;
+            };
+            match entry {
+                WalEntry::CreateCollection { tenant, name, .. } => {
+                    found.insert((tenant, name));
+                }
+                WalEntry::DeleteCollection { tenant, name } => {
+                    found.remove(&(tenant, name));
+                }
+                WalEntry::UpsertVector { .. }
+                | WalEntry::DeleteVector { .. }
+                | WalEntry::SetDimension { .. }
+                | WalEntry::SetCapacity { .. }
+                | WalEntry::UpdateMetadata { .. } => {}
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Tenants with at least one collection on disk: the manifest lazy
+/// startup loads instead of the collections themselves.
+pub fn disk_tenants(data_dir: &Path) -> anyhow::Result<BTreeSet<String>> {
+    Ok(disk_inventory(data_dir)?
+        .into_iter()
+        .map(|(tenant, _)| tenant)
+        .collect())
+}
+
+/// Remove leftovers that no code path reads back (an interrupted snapshot's
+/// temp file). Returns the paths removed.
+pub fn remove_stray_files(data_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut removed = Vec::new();
+    let tmp_path = data_dir.join("snapshot.json.tmp");
+    if tmp_path.exists() {
+        fs::remove_file(&tmp_path)?;
+        removed.push(tmp_path.display().to_string());
+    }
+    Ok(removed)
+}
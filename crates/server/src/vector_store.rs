@@ -0,0 +1,143 @@
+//! Memory-mapped storage for a collection's vector values
+//! (`OPENVDB_MMAP_VECTORS`).
+//!
+//! HNSW keeps its own copy of every vector for search, so by default each
+//! value lives on the heap twice. With mmap storage the ground-truth copy
+//! (used by exact scans, `count` with `min_score`, and snapshots) moves to a
+//! file under `<data_dir>/vectors/`, which roughly halves resident vector
+//! memory: the OS pages values in when they are read and can drop them
+//! again under memory pressure.
+//!
+//! Tradeoffs:
+//! - HNSW queries never touch the file, so their latency is unchanged.
+//! - Exact scans and snapshots read every value; on a cold page cache they
+//!   become disk-bound instead of memory-bound.
+//! - Each write copies the values into the mapping; growing the file
+//!   remaps it, which is amortized by doubling. Slots of deleted vectors
+//!   are not reused, so churn-heavy collections grow the file until the
+//!   next restart.
+//! - The file is a cache of the snapshot + WAL, which stay the source of
+//!   truth: it is rebuilt on startup, and it is deleted when the collection
+//!   is dropped from memory.
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use memmap2::MmapMut;
+
+const VALUE_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Directory under the data dir holding the backing files.
+pub const VECTORS_DIR: &str = "vectors";
+
+/// Fixed-size slots of `dim` f32s in a memory-mapped file, addressed by slot
+/// number (a collection uses its internal HNSW ids).
+pub struct MmapSlab {
+    path: PathBuf,
+    file: File,
+    map: Option<MmapMut>,
+    // Fixed by the first write.
+    dim: usize,
+    slots: usize,
+}
+
+impl MmapSlab {
+    /// Create a backing file with a name unique to this process and run.
+    pub fn create_in(dir: &Path) -> io::Result<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        fs::create_dir_all(dir)?;
+        let name = format!(
+            "{}-{}.f32",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        Self::create(dir.join(name))
+    }
+
+    pub fn create(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            map: None,
+            dim: 0,
+            slots: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store `values` in `slot`, growing the file as needed.
+    pub fn write(&mut self, slot: usize, values: &[f32]) -> io::Result<()> {
+        if self.dim == 0 {
+            self.dim = values.len();
+        }
+        if values.len() != self.dim || self.dim == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("slab holds {}-dim vectors, got {}", self.dim, values.len()),
+            ));
+        }
+        if slot >= self.slots {
+            self.grow(slot + 1)?;
+        }
+
+        let map = self.map.as_mut().expect("grow maps the file");
+        let start = slot * self.dim * VALUE_SIZE;
+        let bytes = &mut map[start..start + self.dim * VALUE_SIZE];
+        for (chunk, v) in bytes.chunks_exact_mut(VALUE_SIZE).zip(values) {
+            chunk.copy_from_slice(&v.to_ne_bytes());
+        }
+        Ok(())
+    }
+
+    /// Values stored in `slot`. Panics if the slot was never written.
+    pub fn read(&self, slot: usize) -> &[f32] {
+        assert!(slot < self.slots, "slot {} out of range", slot);
+        let map = self.map.as_ref().expect("slots > 0 implies a mapping");
+        let start = slot * self.dim * VALUE_SIZE;
+        let bytes = &map[start..start + self.dim * VALUE_SIZE];
+        // SAFETY: the mapping is page-aligned and every slot starts at a
+        // multiple of 4 bytes, so the slice is aligned for f32, and any bit
+        // pattern is a valid f32. The file is private to this process.
+        let (head, values, tail) = unsafe { bytes.align_to::<f32>() };
+        debug_assert!(head.is_empty() && tail.is_empty());
+        values
+    }
+
+    /// Resize the file to at least `min_slots` slots (doubling) and remap.
+    fn grow(&mut self, min_slots: usize) -> io::Result<()> {
+        let slots = min_slots.max(self.slots * 2).max(64);
+        self.file.set_len((slots * self.dim * VALUE_SIZE) as u64)?;
+        // SAFETY: the file was created (and truncated) by us in the data dir
+        // and nothing else writes to it.
+        self.map = Some(unsafe { MmapMut::map_mut(&self.file)? });
+        self.slots = slots;
+        Ok(())
+    }
+}
+
+impl Drop for MmapSlab {
+    fn drop(&mut self) {
+        // Unlinking is safe while mapped; the space is freed on unmap.
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Delete backing files left behind by a previous run.
+pub fn clear_vectors_dir(data_dir: &Path) -> io::Result<()> {
+    let dir = data_dir.join(VECTORS_DIR);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::io::Write;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{TestApp, API_KEY, OTHER_API_KEY};
+use openvdb_server::storage::{encode_entry, WalEntry, WAL_FILE};
+
+#[tokio::test]
+async fn admin_lists_collections_across_tenants() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    app.request_with_key(
+        Method::POST,
+        "/collections",
+        Some(json!({ "name": "images", "dimension": 4 })),
+        Some(OTHER_API_KEY),
+    )
+    .await;
+
+    // Tenant-scoped listing only sees its own collection.
+    let (_, body) = app.request(Method::GET, "/collections", None).await;
+    assert_eq!(body["collections"].as_array().unwrap().len(), 1);
+
+    let (status, body) = app.request(Method::GET, "/admin/collections", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["collections"],
+        json!([
+            { "tenant": OTHER_API_KEY, "name": "images", "dimension": 4, "vectors": 0, "index_type": "hnsw_cosine" },
+            { "tenant": API_KEY, "name": "docs", "dimension": 2, "vectors": 1, "index_type": "hnsw_cosine" },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn reconcile_detects_and_repairs_mismatches() {
+    let app = TestApp::new();
+    app.create_collection("kept", 2).await;
+    app.create_collection("orphan", 2).await;
+
+    // Simulate a crash between dropping in-memory state and logging the
+    // delete, plus a collection that never made it to the WAL.
+    {
+        let mut collections = app.state.collections.write().await;
+        let tenant_map = collections.get_mut(API_KEY).unwrap();
+        tenant_map.remove("orphan");
+        tenant_map.insert(
+            "unlogged".to_string(),
+            openvdb_server::index::InMemoryIndex::new(2),
+        );
+    }
+
+    let (status, body) = app.request(Method::GET, "/admin/reconcile", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["disk_only"], json!([{ "tenant": API_KEY, "collection": "orphan" }]));
+    assert_eq!(body["memory_only"], json!([{ "tenant": API_KEY, "collection": "unlogged" }]));
+    assert_eq!(body["repaired"], false);
+
+    let (status, body) = app.request(Method::POST, "/admin/reconcile", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["repaired"], true);
+
+    let (_, body) = app.request(Method::GET, "/admin/reconcile", None).await;
+    assert_eq!(body["disk_only"], json!([]));
+    assert_eq!(body["memory_only"], json!([]));
+
+    let app = app.restart();
+    let (_, body) = app.request(Method::GET, "/collections/unlogged", None).await;
+    assert_eq!(body["name"], "unlogged");
+    let (status, _) = app.request(Method::GET, "/collections/orphan", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn reconcile_ignores_upserts_into_unknown_collections() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    // Replay skips these, so the collection exists nowhere.
+    let entry = WalEntry::UpsertVector {
+        tenant: API_KEY.to_string(),
+        collection: "ghost".to_string(),
+        id: "a".to_string(),
+        values: vec![1.0, 0.0],
+        metadata: None,
+        written_at: None,
+        version: None,
+    };
+    let mut line = String::new();
+    encode_entry(&entry, &mut line).unwrap();
+    let mut wal = std::fs::OpenOptions::new()
+        .append(true)
+        .open(app.dir.path().join(WAL_FILE))
+        .unwrap();
+    wal.write_all(line.as_bytes()).unwrap();
+
+    let (_, body) = app.request(Method::GET, "/admin/reconcile", None).await;
+    assert_eq!(body["disk_only"], json!([]));
+    assert_eq!(body["memory_only"], json!([]));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use axum::http::Method;
+use common::TestApp;
+use serde_json::{json, Value};
+
+/// The audit log is written in the background: wait for `lines` records.
+async fn read_log(path: &Path, lines: usize) -> Vec<Value> {
+    for _ in 0..200 {
+        let records: Vec<Value> = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        if records.len() >= lines {
+            return records;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("audit log never reached {} records", lines);
+}
+
+#[tokio::test]
+async fn reads_and_writes_are_logged() {
+    let app = TestApp::with_config(|c| c.audit_log = Some(c.data_dir.join("audit.log")));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+    app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await;
+    app.request(Method::DELETE, "/collections/docs/vectors/a", None)
+        .await;
+
+    let records = read_log(&app.dir.path().join("audit.log"), 4).await;
+    let ops: Vec<_> = records
+        .iter()
+        .map(|r| (r["op"].as_str().unwrap(), r["ids"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(
+        ops,
+        [("create_collection", 0), ("upsert", 2), ("query", 1), ("delete", 1)]
+    );
+    assert!(records.iter().all(|r| r["collection"] == "docs"));
+    assert!(records.iter().all(|r| r["tenant"] == records[0]["tenant"]));
+    assert!(records[0]["ts"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn the_log_rotates_by_size() {
+    let app = TestApp::with_config(|c| {
+        c.audit_log = Some(c.data_dir.join("audit.log"));
+        c.audit_log_max_bytes = 200;
+    });
+    app.create_collection("docs", 2).await;
+    for _ in 0..10 {
+        app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await;
+    }
+
+    // Rotation flushes before renaming, so the rotated file is complete.
+    let rotated = app.dir.path().join("audit.log.1");
+    assert!(!read_log(&rotated, 1).await.is_empty());
+    assert!(std::fs::metadata(&rotated).unwrap().len() <= 200);
+}
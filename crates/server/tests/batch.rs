@@ -0,0 +1,128 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::TestApp;
+
+fn statuses(body: &Value) -> Vec<u64> {
+    body["results"]
+        .as_array()
+        .expect("results array")
+        .iter()
+        .map(|r| r["status"].as_u64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn upsert_all_success_is_200() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    let (status, body) = app
+        .upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["failed"], 0);
+    assert_eq!(statuses(&body), [200, 200]);
+}
+
+#[tokio::test]
+async fn upsert_all_fail_uses_item_status() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    let (status, body) = app
+        .upsert("docs", &[("a", vec![1.0], None), ("b", vec![0.0, 0.0], None)])
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["succeeded"], 0);
+    assert_eq!(statuses(&body), [400, 400]);
+    assert!(body["results"][0]["error"].as_str().unwrap().contains("dimension"));
+}
+
+#[tokio::test]
+async fn upsert_mixed_is_207_and_applies_valid_items() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    let (status, body) = app
+        .upsert("docs", &[("a", vec![1.0, 0.0], None), ("bad", vec![1.0], None)])
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["upserted"], 1);
+    assert_eq!(body["failed"], 1);
+    assert_eq!(statuses(&body), [200, 400]);
+    assert_eq!(body["results"][1]["id"], "bad");
+
+    let (_, info) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(info["vectors"], 1);
+}
+
+#[tokio::test]
+async fn batch_delete_reports_missing_ids() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/delete",
+            Some(json!({ "ids": ["a", "missing"] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(statuses(&body), [200, 404]);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/delete",
+            Some(json!({ "ids": ["missing", "also-missing"] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["failed"], 2);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/delete",
+            Some(json!({ "ids": ["b"] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 1);
+
+    // Deletes are in the WAL like single deletes.
+    let app = app.restart();
+    let (_, info) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(info["vectors"], 0);
+}
+
+#[tokio::test]
+async fn batch_create_mixes_created_and_conflicting() {
+    let app = TestApp::new();
+    app.create_collection("existing", 2).await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/batch",
+            Some(json!({ "collections": [
+                { "name": "new", "dimension": 3 },
+                { "name": "existing", "dimension": 2 },
+                { "name": "bad", "dimension": 2, "default_filter": "nope" },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(statuses(&body), [200, 409, 400]);
+
+    let (status, info) = app.request(Method::GET, "/collections/new", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(info["dimension"], 3);
+}
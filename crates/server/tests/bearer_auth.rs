@@ -0,0 +1,60 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, StatusCode},
+};
+use common::{TestApp, API_KEY, OTHER_API_KEY};
+use serde_json::Value;
+
+async fn list(app: &TestApp, headers: &[(header::HeaderName, &str)]) -> (StatusCode, Value) {
+    let mut builder = axum::http::Request::builder()
+        .method(Method::GET)
+        .uri("/collections");
+    for (name, value) in headers {
+        builder = builder.header(name, *value);
+    }
+    let (status, _, bytes) = app.send(builder.body(Body::empty()).unwrap()).await;
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn bearer_token_and_api_key_header_both_authenticate() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let x_api_key = header::HeaderName::from_static("x-api-key");
+
+    let (status, body) = list(&app, &[(x_api_key.clone(), API_KEY)]).await;
+    assert_eq!(status, StatusCode::OK);
+    let bearer = format!("Bearer {}", API_KEY);
+    let (status, via_bearer) = list(&app, &[(header::AUTHORIZATION, &bearer)]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(via_bearer, body);
+
+    // x-api-key wins when both are sent: the other tenant sees no collections.
+    let (status, body) = list(
+        &app,
+        &[(x_api_key, OTHER_API_KEY), (header::AUTHORIZATION, &bearer)],
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(body, via_bearer);
+
+    let (status, body) = list(&app, &[(header::AUTHORIZATION, "Bearer nope")]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "invalid_api_key");
+}
+
+#[tokio::test]
+async fn malformed_authorization_is_treated_as_missing() {
+    let app = TestApp::new();
+    let basic = format!("Basic {}", API_KEY);
+    for value in [basic.as_str(), "Bearer", "Bearer   ", API_KEY] {
+        let (status, body) = list(&app, &[(header::AUTHORIZATION, value)]).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", value);
+        assert_eq!(body["error"]["code"], "missing_api_key");
+    }
+    let (status, body) = list(&app, &[]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "missing_api_key");
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+const ADMIN_KEY: &str = "admin-key";
+
+fn app() -> TestApp {
+    TestApp::with_config(|c| c.admin_api_key = Some(ADMIN_KEY.into()))
+}
+
+#[tokio::test]
+async fn benchmark_reports_throughput_and_latency() {
+    let app = app();
+    let (status, body) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/benchmark",
+            Some(json!({ "vectors": 200, "dimension": 8, "queries": 50, "top_k": 5 })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["upserts"]["operations"], 200);
+    assert_eq!(body["queries"]["operations"], 50);
+    assert!(body["queries"]["ops_per_sec"].as_f64().unwrap() > 0.0);
+    let p50 = body["queries"]["p50_ms"].as_f64().unwrap();
+    let p99 = body["queries"]["p99_ms"].as_f64().unwrap();
+    assert!(p50 <= p99 && p99 <= body["queries"]["max_ms"].as_f64().unwrap());
+    assert!(body.get("cancelled").is_none());
+    assert!(body.get("bulk_upserts").is_none());
+
+    // The throwaway collection is gone.
+    let (_, list) = app
+        .request(Method::GET, "/admin/collections", None)
+        .await;
+    assert_eq!(list["collections"], json!([]));
+}
+
+#[tokio::test]
+async fn benchmark_compares_bulk_and_per_vector_upserts() {
+    let app = app();
+    let (status, body) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/benchmark",
+            Some(json!({ "vectors": 250, "dimension": 8, "queries": 0, "bulk_batch": 100 })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["upserts"]["operations"], 250);
+    // Counted in vectors, timed per batch (100, 100, 50).
+    let bulk = &body["bulk_upserts"];
+    assert_eq!(bulk["operations"], 250);
+    assert!(bulk["ops_per_sec"].as_f64().unwrap() > 0.0);
+    assert!(bulk["p50_ms"].as_f64().unwrap() <= bulk["max_ms"].as_f64().unwrap());
+
+    let (status, _) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/benchmark",
+            Some(json!({ "vectors": 10, "bulk_batch": 0 })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn benchmark_requires_the_admin_key() {
+    let app = app();
+    let (status, _) = app
+        .request(Method::POST, "/admin/benchmark", Some(json!({ "vectors": 10 })))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Disabled entirely without a configured admin key.
+    let app = TestApp::new();
+    let (status, _) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/benchmark",
+            Some(json!({ "vectors": 10 })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn benchmark_can_be_cancelled() {
+    let app = app();
+    let (status, _) = app
+        .request_with_key(Method::DELETE, "/admin/benchmark", None, Some(ADMIN_KEY))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let run = app.request_with_key(
+        Method::POST,
+        "/admin/benchmark",
+        Some(json!({ "vectors": 1_000_000, "dimension": 32, "queries": 1_000_000 })),
+        Some(ADMIN_KEY),
+    );
+    let cancel = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        app.request_with_key(Method::DELETE, "/admin/benchmark", None, Some(ADMIN_KEY))
+            .await
+    };
+    let ((status, body), (cancel_status, _)) = tokio::join!(run, cancel);
+    assert_eq!(cancel_status, StatusCode::ACCEPTED);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["cancelled"], true);
+    assert!(body["upserts"]["operations"].as_u64().unwrap() < 1_000_000);
+
+    // The slot is free again.
+    let (status, _) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/benchmark",
+            Some(json!({ "vectors": 10, "dimension": 4, "queries": 1 })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
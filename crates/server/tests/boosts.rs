@@ -0,0 +1,112 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+use openvdb_server::index::{apply_boosts, ScoreBoost, ScoredPoint};
+
+#[test]
+fn boosted_items_rank_higher() {
+    let point = |id: &str, score: f32, category: &str| ScoredPoint {
+        id: id.to_string(),
+        score,
+        distance: 1.0 - score,
+        metadata: Some(json!({ "category": category })),
+        values: None,
+    };
+    let mut points = vec![point("plain", 1.0, "a"), point("promoted", 0.8, "x")];
+    let boosts = [ScoreBoost {
+        filter: json!({ "category": "x" }).as_object().unwrap().clone(),
+        factor: 1.5,
+    }];
+
+    apply_boosts(&mut points, &boosts);
+
+    assert_eq!(points[0].id, "promoted");
+    assert!((points[0].score - 1.2).abs() < 1e-6);
+    assert_eq!(points[1].id, "plain");
+    assert_eq!(points[1].score, 1.0);
+}
+
+#[tokio::test]
+async fn boosts_apply_through_query() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[("promoted", vec![0.8, 0.6], Some(json!({ "category": "x" })))],
+    )
+    .await;
+
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 1,
+                "boosts": [{ "filter": { "category": "x" }, "factor": 1.5 }],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let matches = body["matches"].as_array().unwrap();
+    assert_eq!(matches[0]["id"], "promoted");
+    assert!((matches[0]["score"].as_f64().unwrap() - 1.2).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn boost_factor_must_be_positive() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    for factor in [0.0, -1.0] {
+        let (status, _) = app
+            .query(
+                "docs",
+                json!({
+                    "vector": [1.0, 0.0],
+                    "top_k": 2,
+                    "boosts": [{ "filter": { "category": "x" }, "factor": factor }],
+                }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn explain_reports_score_components() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("promoted", vec![0.8, 0.6], Some(json!({ "category": "x" }))),
+            ("plain", vec![1.0, 0.0], Some(json!({ "category": "a" }))),
+        ],
+    )
+    .await;
+
+    let query = |explain: bool| {
+        json!({
+            "vector": [1.0, 0.0],
+            "top_k": 2,
+            "boosts": [{ "filter": { "category": "x" }, "factor": 1.5 }],
+            "explain": explain,
+        })
+    };
+
+    let (status, body) = app.query("docs", query(true)).await;
+    assert_eq!(status, StatusCode::OK);
+    let matches = body["matches"].as_array().unwrap();
+    assert_eq!(matches[0]["id"], "promoted");
+    let components = &matches[0]["score_components"];
+    assert!((components["vector"].as_f64().unwrap() - 0.8).abs() < 1e-4);
+    assert_eq!(components["boost"], 1.5);
+    assert_eq!(matches[1]["id"], "plain");
+    assert_eq!(matches[1]["score_components"]["boost"], 1.0);
+
+    let (_, body) = app.query("docs", query(false)).await;
+    assert!(body["matches"][0].get("score_components").is_none());
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::TestApp;
+
+fn batch(n: usize) -> Value {
+    let vectors: Vec<Value> = (0..n)
+        .map(|i| {
+            let a = i as f32 * 0.01;
+            json!({ "id": format!("v{}", i), "values": [a.cos(), a.sin(), 1.0] })
+        })
+        .collect();
+    json!({ "vectors": vectors })
+}
+
+// Multi-threaded like the server's runtime, so the merge goes through
+// `block_in_place`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn parallel_bulk_insert_keeps_id_maps_consistent() {
+    let app = TestApp::with_config(|c| {
+        c.parallel_insert = true;
+        c.parallel_insert_min_batch = 1;
+        // The first snapshot would run on another worker, racing `restart`.
+        c.first_snapshot_after = 0;
+    });
+    app.create_collection("docs", 3).await;
+
+    let uri = "/collections/docs/vectors/upsert?bulk=true";
+    let (status, body) = app.request(Method::POST, uri, Some(batch(500))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["upserted"], 500);
+
+    // Re-upserting the same ids must overwrite, not add.
+    let (status, _) = app.request(Method::POST, uri, Some(batch(500))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(body["vectors"], 500);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 1.0], "top_k": 5 }))
+        .await;
+    assert!(!body["matches"].as_array().unwrap().is_empty());
+
+    let app = app.restart();
+    let (_, body) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(body["vectors"], 500);
+}
+
+#[tokio::test]
+async fn bulk_and_per_vector_upserts_give_the_same_results() {
+    let app = TestApp::new();
+    app.create_collection("bulk", 3).await;
+    app.create_collection("single", 3).await;
+    let data = batch(300);
+    let (status, _) = app
+        .request(Method::POST, "/collections/bulk/vectors/upsert?bulk=true", Some(data.clone()))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .request(Method::POST, "/collections/single/vectors/upsert", Some(data))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Exact scans over every vector: the graphs differ (HNSW levels are
+    // random), the stored vectors must not.
+    for angle in [0.0f32, 0.7, 1.9, 2.95] {
+        let request = json!({
+            "vector": [angle.cos(), angle.sin(), 1.0],
+            "top_k": 300,
+            "exact": true,
+        });
+        let (_, bulk) = app.query("bulk", request.clone()).await;
+        let (_, single) = app.query("single", request).await;
+        assert_eq!(bulk["matches"].as_array().unwrap().len(), 300);
+        assert_eq!(bulk["matches"], single["matches"], "angle {}", angle);
+    }
+
+    let (_, bulk) = app.request(Method::GET, "/collections/bulk/stats", None).await;
+    let (_, single) = app.request(Method::GET, "/collections/single/stats", None).await;
+    for field in ["vectors", "index_nodes", "dead_nodes"] {
+        assert_eq!(bulk[field], single[field], "{}", field);
+    }
+}
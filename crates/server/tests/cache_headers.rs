@@ -0,0 +1,58 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+async fn query(app: &TestApp, if_none_match: Option<&str>) -> (StatusCode, HeaderMap) {
+    let mut builder = app
+        .builder(Method::POST, "/collections/docs/query")
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(etag) = if_none_match {
+        builder = builder.header(header::IF_NONE_MATCH, etag);
+    }
+    let body = json!({ "vector": [1.0, 0.0], "top_k": 1 }).to_string();
+    let (status, headers, _) = app.send(builder.body(Body::from(body)).unwrap()).await;
+    (status, headers)
+}
+
+#[tokio::test]
+async fn unchanged_repeat_query_is_not_modified() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, headers) = query(&app, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+    let etag = headers[header::ETAG].to_str().unwrap().to_string();
+
+    let (status, headers) = query(&app, Some(&etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers[header::ETAG], etag.as_str());
+
+    // Any write to the collection invalidates the tag.
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+    let (status, headers) = query(&app, Some(&etag)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(headers[header::ETAG], etag.as_str());
+}
+
+#[tokio::test]
+async fn different_queries_get_different_tags() {
+    let app = TestApp::with_config(|c| c.query_cache_max_age_secs = Some(30));
+    app.create_collection("docs", 2).await;
+
+    let (_, first) = query(&app, None).await;
+    assert_eq!(first[header::CACHE_CONTROL], "max-age=30");
+
+    let req = app
+        .builder(Method::POST, "/collections/docs/query")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "vector": [0.0, 1.0], "top_k": 1 }).to_string()))
+        .unwrap();
+    let (_, second, _) = app.send(req).await;
+    assert_ne!(first[header::ETAG], second[header::ETAG]);
+}
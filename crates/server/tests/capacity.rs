@@ -0,0 +1,105 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn small_collection(app: &TestApp) {
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "capacity": 5 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let ids: Vec<String> = (0..5).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), vec![1.0, i as f32], None))
+        .collect();
+    app.upsert("docs", &batch).await;
+}
+
+async fn upsert(app: &TestApp, uri: &str, id: &str) -> (StatusCode, Value) {
+    app.request(
+        Method::POST,
+        uri,
+        Some(json!({ "vectors": [{ "id": id, "values": [0.5, 0.5] }] })),
+    )
+    .await
+}
+
+async fn stats(app: &TestApp) -> Value {
+    app.request(Method::GET, "/collections/docs/stats", None).await.1
+}
+
+#[tokio::test]
+async fn full_collection_rejects_upserts_gracefully() {
+    let app = TestApp::with_config(|c| c.auto_compact = false);
+    small_collection(&app).await;
+    let body = stats(&app).await;
+    assert_eq!(body["index_nodes"], 5);
+    assert_eq!(body["capacity"], 5);
+
+    let (status, body) = upsert(&app, "/collections/docs/vectors/upsert", "extra").await;
+    assert_eq!(status, StatusCode::INSUFFICIENT_STORAGE);
+    assert!(body["results"][0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("collection at capacity"));
+
+    // Overwrites add a node too, and bulk upserts are checked as a whole.
+    let (status, _) = upsert(&app, "/collections/docs/vectors/upsert?bulk=true", "v0").await;
+    assert_eq!(status, StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(stats(&app).await["vectors"], 5);
+}
+
+#[tokio::test]
+async fn reindex_with_larger_capacity_makes_room() {
+    let app = TestApp::with_config(|c| c.auto_compact = false);
+    small_collection(&app).await;
+
+    let (status, _) = app
+        .request(Method::POST, "/collections/docs/reindex?capacity=4", None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .request(Method::POST, "/collections/docs/reindex?capacity=10", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = upsert(&app, "/collections/docs/vectors/upsert", "extra").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = app.restart();
+    let body = stats(&app).await;
+    assert_eq!(body["capacity"], 10);
+    assert_eq!(body["vectors"], 6);
+}
+
+#[tokio::test]
+async fn capacity_above_the_server_maximum_is_rejected() {
+    let app = TestApp::new();
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "huge", "dimension": 3, "capacity": 100_000_000_000_000u64 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("at most 10000000"));
+
+    small_collection(&app).await;
+    for capacity in ["0", "100000000000000"] {
+        let uri = format!("/collections/docs/reindex?capacity={}", capacity);
+        let (status, _) = app.request(Method::POST, &uri, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", capacity);
+    }
+    assert_eq!(stats(&app).await["capacity"], 5);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, OTHER_API_KEY};
+
+#[tokio::test]
+async fn stats_for_every_collection_of_the_tenant() {
+    let app = TestApp::new();
+    app.create_collection("b", 2).await;
+    app.create_collection("a", 3).await;
+    app.upsert("b", &[("x", vec![1.0, 0.0], None), ("y", vec![0.0, 1.0], None)])
+        .await;
+
+    let (status, body) = app.request(Method::GET, "/collections/stats", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let stats = body["collections"].as_array().unwrap();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0]["name"], "a");
+    assert_eq!(stats[0]["dimension"], 3);
+    assert_eq!(stats[1]["name"], "b");
+    assert_eq!(stats[1]["vectors"], 2);
+
+    // Same shape as the per-collection endpoint.
+    let (_, single) = app.request(Method::GET, "/collections/b/stats", None).await;
+    assert_eq!(stats[1], single);
+
+    // Other tenants see only their own.
+    let (_, body) = app
+        .request_with_key(Method::GET, "/collections/stats", None, Some(OTHER_API_KEY))
+        .await;
+    assert_eq!(body["collections"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn memory_estimate_counts_dead_nodes_until_compaction() {
+    let app = TestApp::new();
+    app.create_collection("docs", 4).await;
+    app.upsert(
+        "docs",
+        &[("a", vec![1.0, 0.0, 0.0, 0.0], None), ("b", vec![0.0, 1.0, 0.0, 0.0], None)],
+    )
+    .await;
+    let memory = |stats: &serde_json::Value| stats["approx_memory_bytes"].as_u64().unwrap();
+
+    // Values twice (store + graph) plus 16 neighbour ids per node.
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    let node = 4 * 4 + 16 * 8;
+    assert_eq!(memory(&stats), 2 * 16 + 2 * node);
+
+    // An overwrite leaves a dead node behind until compaction.
+    app.upsert("docs", &[("a", vec![0.0, 0.0, 1.0, 0.0], None)]).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(memory(&stats), 2 * 16 + 3 * node);
+    app.request(Method::POST, "/collections/docs/compact", None).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(memory(&stats), 2 * 16 + 2 * node);
+}
@@ -0,0 +1,172 @@
+//! Shared helpers for the HTTP integration tests.
+//!
+//! `TestApp` builds the real router over a fresh `AppState` whose data dir is
+//! a tempdir, and drives it in-process with `tower::ServiceExt::oneshot`.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use axum::{
+    body::Body,
+    http::{request, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+use openvdb_server::{auth::Role, build_router, config::Config, state::AppState};
+
+pub const API_KEY: &str = "test-key";
+/// A second tenant, for isolation tests.
+pub const OTHER_API_KEY: &str = "other-key";
+/// A read-only key (its own tenant), for role tests.
+pub const READ_ONLY_API_KEY: &str = "read-key";
+
+pub struct TestApp {
+    pub state: AppState,
+    pub dir: TempDir,
+    router: Router,
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        Self::with_config(|_| {})
+    }
+
+    /// Start from `Config::default()` pointed at a fresh tempdir, letting the
+    /// caller tweak settings before the state is built.
+    pub fn with_config(configure: impl FnOnce(&mut Config)) -> Self {
+        let dir = tempfile::tempdir().expect("create tempdir");
+        let mut config = Config {
+            data_dir: dir.path().to_path_buf(),
+            ..Config::default()
+        };
+        configure(&mut config);
+        Self::start(config, dir)
+    }
+
+    /// Simulate a process restart: drop the in-memory state and rebuild it
+    /// from whatever the previous instance persisted in the same data dir.
+    pub fn restart(self) -> Self {
+        self.restart_with(|_| {})
+    }
+
+    /// `restart` with settings changed, as after editing the environment.
+    pub fn restart_with(self, configure: impl FnOnce(&mut Config)) -> Self {
+        let mut config = (*self.state.config).clone();
+        configure(&mut config);
+        drop(self.router);
+        drop(self.state);
+        Self::start(config, self.dir)
+    }
+
+    fn start(config: Config, dir: TempDir) -> Self {
+        let api_keys = HashMap::from([
+            (API_KEY.to_string(), Role::ReadWrite),
+            (OTHER_API_KEY.to_string(), Role::ReadWrite),
+            (READ_ONLY_API_KEY.to_string(), Role::ReadOnly),
+        ]);
+        let state = AppState::load(config, api_keys).expect("load state");
+        let router = build_router(state.clone());
+        Self { state, dir, router }
+    }
+
+    /// Send a request authenticated with `API_KEY`.
+    pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        self.request_with_key(method, uri, body, Some(API_KEY)).await
+    }
+
+    pub async fn request_with_key(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+        api_key: Option<&str>,
+    ) -> (StatusCode, Value) {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(key) = api_key {
+            builder = builder.header("x-api-key", key);
+        }
+        let req = match body {
+            Some(b) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(b.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .expect("build request");
+
+        let (status, _, bytes) = self.send(req).await;
+
+        let value = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+        };
+        (status, value)
+    }
+
+    /// Request builder pre-authenticated with `API_KEY`, for tests that need
+    /// custom headers or non-JSON bodies. Pair with `send`.
+    pub fn builder(&self, method: Method, uri: &str) -> request::Builder {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", API_KEY)
+    }
+
+    /// Send a raw request; returns status, headers and the body bytes.
+    pub async fn send(&self, req: Request<Body>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let resp = self.router.clone().oneshot(req).await.expect("router is infallible");
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .expect("read body");
+        (status, headers, bytes.to_vec())
+    }
+
+    pub async fn create_collection(&self, name: &str, dimension: usize) -> (StatusCode, Value) {
+        self.request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": name, "dimension": dimension })),
+        )
+        .await
+    }
+
+    /// Upsert `(id, values, metadata)` triples into `collection`.
+    pub async fn upsert(&self, collection: &str, vectors: &[(&str, Vec<f32>, Option<Value>)]) -> (StatusCode, Value) {
+        let vectors: Vec<Value> = vectors
+            .iter()
+            .map(|(id, values, metadata)| json!({ "id": id, "values": values, "metadata": metadata }))
+            .collect();
+        self.request(
+            Method::POST,
+            &format!("/collections/{}/vectors/upsert", collection),
+            Some(json!({ "vectors": vectors })),
+        )
+        .await
+    }
+
+    pub async fn query(&self, collection: &str, body: Value) -> (StatusCode, Value) {
+        self.request(
+            Method::POST,
+            &format!("/collections/{}/query", collection),
+            Some(body),
+        )
+        .await
+    }
+}
+
+/// Ids of the `matches` array in a query response, in rank order.
+pub fn match_ids(resp: &Value) -> Vec<String> {
+    resp["matches"]
+        .as_array()
+        .expect("matches array")
+        .iter()
+        .map(|m| m["id"].as_str().expect("match id").to_string())
+        .collect()
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::{json, Value};
+
+async fn seed(app: &TestApp, n: usize) {
+    app.create_collection("docs", 2).await;
+    let ids: Vec<String> = (0..n).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), vec![1.0, i as f32], None))
+        .collect();
+    app.upsert("docs", &batch).await;
+}
+
+async fn delete_all_but(app: &TestApp, n: usize, keep: usize) {
+    let ids: Vec<String> = (keep..n).map(|i| format!("v{}", i)).collect();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/delete",
+            Some(json!({ "ids": ids })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn stats(app: &TestApp) -> Value {
+    app.request(Method::GET, "/collections/docs/stats", None).await.1
+}
+
+#[tokio::test]
+async fn compact_drops_dead_nodes() {
+    let app = TestApp::with_config(|c| c.auto_compact = false);
+    seed(&app, 100).await;
+    delete_all_but(&app, 100, 20).await;
+
+    let before = stats(&app).await;
+    assert_eq!(before["index_nodes"], 100);
+    assert_eq!(before["dead_nodes"], 80);
+
+    let (status, body) = app
+        .request(Method::POST, "/collections/docs/compact", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["vectors"], 20);
+    assert_eq!(body["removed_nodes"], 80);
+
+    let after = stats(&app).await;
+    assert_eq!(after["vectors"], 20);
+    assert_eq!(after["index_nodes"], 20);
+    assert_eq!(after["dead_nodes"], 0);
+
+    // Every survivor is still found under its own id.
+    let (_, resp) = app
+        .query("docs", json!({ "vector": [1.0, 5.0], "top_k": 20 }))
+        .await;
+    let mut ids = match_ids(&resp);
+    ids.sort();
+    let mut expected: Vec<String> = (0..20).map(|i| format!("v{}", i)).collect();
+    expected.sort();
+    assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn overwrites_leave_dead_nodes() {
+    let app = TestApp::with_config(|c| c.auto_compact = false);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    app.upsert("docs", &[("a", vec![0.0, 1.0], None)]).await;
+
+    let body = stats(&app).await;
+    assert_eq!(body["index_nodes"], 2);
+    assert_eq!(body["dead_nodes"], 1);
+}
+
+#[tokio::test]
+async fn churn_triggers_automatic_compaction() {
+    let app = TestApp::new();
+    seed(&app, 100).await;
+    delete_all_but(&app, 100, 20).await;
+
+    for _ in 0..100 {
+        let body = stats(&app).await;
+        if body["index_nodes"] == 20 {
+            assert_eq!(body["dead_nodes"], 0);
+            assert_eq!(body["vectors"], 20);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("collection was not compacted: {}", stats(&app).await);
+}
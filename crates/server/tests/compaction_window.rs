@@ -0,0 +1,73 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use openvdb_server::config::{utc_minute_of_day, CompactionWindow};
+use openvdb_server::state::compact_due_collections;
+use serde_json::{json, Value};
+
+const DAY: u16 = 24 * 60;
+
+/// A window around the current time, or the one opposite it.
+fn window(open_now: bool) -> CompactionWindow {
+    let now = utc_minute_of_day();
+    let start = if open_now { now + DAY - 5 } else { now + 60 };
+    CompactionWindow {
+        start: start % DAY,
+        end: (start + 10) % DAY,
+    }
+}
+
+async fn churned(app: &TestApp) {
+    app.create_collection("docs", 2).await;
+    let ids: Vec<String> = (0..50).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), vec![1.0, i as f32], None))
+        .collect();
+    app.upsert("docs", &batch).await;
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/delete",
+            Some(json!({ "ids": &ids[10..] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn stats(app: &TestApp) -> Value {
+    app.request(Method::GET, "/collections/docs/stats", None).await.1
+}
+
+#[test]
+fn window_parses_and_wraps_midnight() {
+    let w: CompactionWindow = "22:00-02:00".parse().unwrap();
+    assert_eq!(w, CompactionWindow { start: 22 * 60, end: 2 * 60 });
+    assert!(w.contains(23 * 60));
+    assert!(w.contains(60));
+    assert!(!w.contains(12 * 60));
+    assert!("25:00-02:00".parse::<CompactionWindow>().is_err());
+}
+
+#[tokio::test]
+async fn compaction_waits_for_the_window() {
+    let closed = window(false);
+    let app = TestApp::with_config(|c| c.compaction_window = Some(closed));
+    churned(&app).await;
+
+    // Writes don't trigger a rebuild, and outside the window neither does
+    // the scheduler.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(stats(&app).await["dead_nodes"], 40);
+    assert_eq!(compact_due_collections(&app.state, closed).await, 0);
+    assert_eq!(stats(&app).await["dead_nodes"], 40);
+
+    assert_eq!(compact_due_collections(&app.state, window(true)).await, 1);
+    let body = stats(&app).await;
+    assert_eq!(body["index_nodes"], 10);
+    assert_eq!(body["dead_nodes"], 0);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::{json, Value};
+
+fn query(consistency: &str) -> Value {
+    json!({ "vector": [1.0, 0.0], "top_k": 5, "consistency": consistency })
+}
+
+#[tokio::test]
+async fn strong_queries_see_the_latest_write() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    assert_eq!(match_ids(&app.query("docs", query("strong")).await.1), ["a"]);
+
+    app.upsert("docs", &[("b", vec![0.9, 0.1], None)]).await;
+    assert_eq!(match_ids(&app.query("docs", query("strong")).await.1), ["a", "b"]);
+}
+
+#[tokio::test]
+async fn eventual_queries_may_lag_within_the_bound() {
+    let app = TestApp::with_config(|c| c.eventual_max_staleness_ms = 200);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    let (status, body) = app.query("docs", query("eventual")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a"]);
+
+    // Served the cached result: the new write isn't in it yet.
+    app.upsert("docs", &[("b", vec![0.9, 0.1], None)]).await;
+    assert_eq!(match_ids(&app.query("docs", query("eventual")).await.1), ["a"]);
+    assert_eq!(match_ids(&app.query("docs", query("strong")).await.1), ["a", "b"]);
+
+    // Past the bound it searches again.
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(match_ids(&app.query("docs", query("eventual")).await.1), ["a", "b"]);
+}
+
+#[tokio::test]
+async fn cached_results_keep_cache_headers_and_die_with_their_collection() {
+    let app = TestApp::with_config(|c| c.eventual_max_staleness_ms = 60_000);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let send = |body: Value, etag: Option<&str>| {
+        let mut req = app
+            .builder(Method::POST, "/collections/docs/query")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(etag) = etag {
+            req = req.header(header::IF_NONE_MATCH, etag);
+        }
+        app.send(req.body(Body::from(body.to_string())).unwrap())
+    };
+    let (_, first, _) = send(query("eventual"), None).await;
+    let (status, cached, _) = send(query("eventual"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cached[header::ETAG], first[header::ETAG]);
+    assert!(cached.contains_key(header::CACHE_CONTROL));
+    let etag = first[header::ETAG].to_str().unwrap();
+    let (status, _, _) = send(query("eventual"), Some(etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+    app.request(Method::DELETE, "/collections/docs", None).await;
+    let (status, _) = app.query("docs", query("eventual")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unknown_consistency_is_rejected() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (status, _) = app.query("docs", query("linearizable")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn query_count_by_filter_and_score() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], Some(json!({ "lang": "en" }))),
+            ("b", vec![0.0, 1.0], Some(json!({ "lang": "en" }))),
+            ("c", vec![0.9, 0.1], Some(json!({ "lang": "fr" }))),
+            ("d", vec![1.0, 0.1], None),
+        ],
+    )
+    .await;
+
+    let uri = "/collections/docs/query/count";
+
+    let (status, body) = app.request(Method::POST, uri, Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["count"], 4);
+
+    let (_, body) = app
+        .request(Method::POST, uri, Some(json!({ "filter": { "lang": "en" } })))
+        .await;
+    assert_eq!(body["count"], 2);
+
+    let (_, body) = app
+        .request(
+            Method::POST,
+            uri,
+            Some(json!({ "vector": [1.0, 0.0], "min_score": 0.9 })),
+        )
+        .await;
+    assert_eq!(body["count"], 3);
+
+    let (_, body) = app
+        .request(
+            Method::POST,
+            uri,
+            Some(json!({ "vector": [1.0, 0.0], "min_score": 0.9, "filter": { "lang": "en" } })),
+        )
+        .await;
+    assert_eq!(body["count"], 1);
+
+    let (status, _) = app
+        .request(Method::POST, uri, Some(json!({ "min_score": 0.5 })))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn count_agrees_with_filtered_queries() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], Some(json!({ "lang": "en", "tags": { "draft": true } }))),
+            ("b", vec![0.0, 1.0], Some(json!({ "lang": "en" }))),
+            ("c", vec![0.9, 0.1], Some(json!({ "lang": "fr" }))),
+            ("d", vec![1.0, 0.1], None),
+        ],
+    )
+    .await;
+    let uri = "/collections/docs/count";
+
+    let (status, body) = app.request(Method::POST, uri, Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "count": 4 }));
+
+    let filters = [
+        json!({ "lang": "en" }),
+        json!({ "tags": { "draft": true } }),
+        json!({ "lang": "de" }),
+    ];
+    for filter in filters {
+        let (_, count) = app
+            .request(Method::POST, uri, Some(json!({ "filter": filter })))
+            .await;
+        let (_, matches) = app
+            .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10, "filter": filter }))
+            .await;
+        assert_eq!(count["count"], common::match_ids(&matches).len());
+    }
+
+    let (status, _) = app
+        .request(Method::POST, uri, Some(json!({ "filter": ["lang"] })))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .request(Method::POST, "/collections/nope/count", Some(json!({})))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
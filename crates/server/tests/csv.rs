@@ -0,0 +1,50 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, StatusCode},
+};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn query_returns_csv_when_accepted() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[("a,1", vec![1.0, 0.0], Some(json!({ "title": "x" })))],
+    )
+    .await;
+
+    let req = app
+        .builder(Method::POST, "/collections/docs/query")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, "text/csv")
+        .body(Body::from(json!({ "vector": [1.0, 0.0], "top_k": 1 }).to_string()))
+        .unwrap();
+    let (status, headers, body) = app.send(req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/csv"));
+
+    let body = String::from_utf8(body).unwrap();
+    let mut lines = body.lines();
+    assert_eq!(lines.next(), Some("id,score,metadata_json"));
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("\"a,1\","), "row: {}", row);
+    assert!(row.ends_with(r#","{""title"":""x""}""#), "row: {}", row);
+}
+
+#[tokio::test]
+async fn query_defaults_to_json() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["matches"].is_array());
+}
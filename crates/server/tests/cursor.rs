@@ -0,0 +1,76 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{match_ids, TestApp};
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let vectors: Vec<(String, Vec<f32>)> = (0..20)
+        .map(|i| {
+            let angle = i as f32 * 0.05;
+            (format!("v{:02}", i), vec![angle.cos(), angle.sin()])
+        })
+        .collect();
+    let batch: Vec<_> = vectors
+        .iter()
+        .map(|(id, values)| (id.as_str(), values.clone(), None))
+        .collect();
+    app.upsert("docs", &batch).await;
+    app
+}
+
+#[tokio::test]
+async fn cursor_pages_through_results_in_order() {
+    let app = seeded().await;
+
+    let mut seen = Vec::new();
+    let mut cursor = Value::Null;
+    loop {
+        let (status, body) = app
+            .query(
+                "docs",
+                json!({ "vector": [1.0, 0.0], "top_k": 6, "cursor": cursor }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        seen.extend(match_ids(&body));
+        match body.get("cursor") {
+            Some(next) => cursor = next.clone(),
+            None => break,
+        }
+    }
+
+    let expected: Vec<String> = (0..20).map(|i| format!("v{:02}", i)).collect();
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn bad_cursors_are_rejected() {
+    let app = seeded().await;
+
+    for cursor in ["zz", "abc", "00"] {
+        let (status, _) = app
+            .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 2, "cursor": cursor }))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "cursor {:?}", cursor);
+    }
+
+    let (_, first) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 2 }))
+        .await;
+    let (status, _) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 2,
+                "cursor": first["cursor"],
+                "group_by": "kind",
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn missing_data_dir_is_created_and_reloaded_from() {
+    let app = TestApp::with_config(|c| c.data_dir = c.data_dir.join("instances/a"));
+    let data_dir = app.state.config.data_dir.clone();
+    assert!(data_dir.is_dir());
+
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "lang": "en" })))])
+        .await;
+    assert!(data_dir.join("wal.jsonl").exists());
+
+    // Both the WAL and a snapshot of it are read back from the same place.
+    let app = app.restart();
+    let (status, body) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["metadata"], json!({ "lang": "en" }));
+
+    app.state.write_snapshot().await.unwrap();
+    assert!(data_dir.join("snapshot.json").exists());
+    let app = app.restart();
+    let (status, _) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!app.dir.path().join("wal.jsonl").exists());
+}
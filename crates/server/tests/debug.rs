@@ -0,0 +1,53 @@
+mod common;
+
+use serde_json::json;
+
+use common::TestApp;
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let vectors: Vec<(String, Vec<f32>)> = (0..50)
+        .map(|i| {
+            let angle = i as f32 * 0.05;
+            (format!("v{}", i), vec![angle.cos(), angle.sin()])
+        })
+        .collect();
+    let batch: Vec<_> = vectors
+        .iter()
+        .map(|(id, values)| (id.as_str(), values.clone(), None))
+        .collect();
+    app.upsert("docs", &batch).await;
+    app
+}
+
+#[tokio::test]
+async fn debug_reports_visited_nodes() {
+    let app = seeded().await;
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 3, "debug": true }))
+        .await;
+    let visited = body["debug"]["visited"].as_u64().unwrap();
+    assert!(visited > 0 && visited <= 200, "visited {}", visited);
+    assert_eq!(body["debug"]["ef"], 64);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 3 }))
+        .await;
+    assert!(body.get("debug").is_none());
+}
+
+#[tokio::test]
+async fn debug_on_exact_query_counts_scanned_vectors() {
+    let app = seeded().await;
+
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 3, "exact": true, "debug": true }),
+        )
+        .await;
+    assert_eq!(body["debug"]["visited"], 50);
+    assert!(body["debug"].get("ef").is_none());
+}
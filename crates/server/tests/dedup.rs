@@ -0,0 +1,101 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn near_duplicates_of_other_ids_are_rejected() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "dedup_threshold": 0.01 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, body) = app
+        .upsert("docs", &[("b", vec![1.0, 0.001], None), ("c", vec![0.0, 1.0], None)])
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["results"][0]["status"], 409);
+    assert!(body["results"][0]["error"].as_str().unwrap().contains("'a'"));
+    assert_eq!(body["results"][1]["status"], 200);
+
+    // Overwriting the same id is not a duplicate.
+    let (status, _) = app.upsert("docs", &[("a", vec![1.0, 0.001], None)]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [{ "id": "d", "values": [0.001, 1.0] }] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"]["message"].as_str().unwrap().contains("'c'"));
+}
+
+#[tokio::test]
+async fn dedup_is_off_by_default() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (status, _) = app
+        .upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![1.0, 0.0], None)])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn thresholds_must_fit_the_metric() {
+    let app = TestApp::new();
+    for (metric, threshold, ok) in [
+        ("cosine", 2.0, true),
+        ("cosine", 2.5, false),
+        ("normalized_cosine", 3.0, false),
+        ("l2", 5.0, true),
+        ("l2", -1.0, false),
+        ("dot", 0.1, false),
+    ] {
+        let name = format!("{}-{}", metric, threshold);
+        let (status, body) = app
+            .request(
+                Method::POST,
+                "/collections",
+                Some(json!({
+                    "name": name,
+                    "dimension": 2,
+                    "metric": metric,
+                    "dedup_threshold": threshold,
+                })),
+            )
+            .await;
+        let expected = if ok { StatusCode::OK } else { StatusCode::BAD_REQUEST };
+        assert_eq!(status, expected, "{}: {}", name, body);
+    }
+}
+
+#[tokio::test]
+async fn l2_thresholds_are_euclidean_distances() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "metric": "l2", "dedup_threshold": 0.5 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("a", vec![1.0, 1.0], None)]).await;
+
+    let (status, body) = app
+        .upsert("docs", &[("b", vec![1.3, 1.3], None), ("c", vec![1.0, 1.6], None)])
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS, "{}", body);
+    assert_eq!(body["results"][0]["status"], 409);
+    assert_eq!(body["results"][1]["status"], 200);
+}
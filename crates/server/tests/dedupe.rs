@@ -0,0 +1,41 @@
+mod common;
+
+use std::collections::HashSet;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+#[tokio::test]
+async fn overwritten_id_is_returned_once() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let mut vectors: Vec<(String, Vec<f32>)> = (0..50)
+        .map(|i| (format!("v{}", i), vec![(i as f32 * 0.1).cos(), (i as f32 * 0.1).sin()]))
+        .collect();
+    vectors.push(("dup".into(), vec![1.0, 0.0]));
+    let batch: Vec<_> = vectors
+        .iter()
+        .map(|(id, v)| (id.as_str(), v.clone(), Some(json!({ "kind": "doc" }))))
+        .collect();
+    app.upsert("docs", &batch).await;
+    // Same id, new values: HNSW now holds two nodes for "dup".
+    app.upsert("docs", &[("dup", vec![1.0, 0.01], Some(json!({ "kind": "doc" })))])
+        .await;
+
+    for body in [
+        json!({ "vector": [1.0, 0.0], "top_k": 10 }),
+        json!({ "vector": [1.0, 0.0], "top_k": 10, "filter": { "kind": "doc" } }),
+    ] {
+        let (status, resp) = app.query("docs", body).await;
+        assert_eq!(status, StatusCode::OK);
+        let ids = match_ids(&resp);
+        // Whether HNSW finds "dup" at all is down to recall; what matters
+        // is that it never comes back twice.
+        let unique: HashSet<&String> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len(), "{:?}", ids);
+        assert!(ids.iter().filter(|id| *id == "dup").count() <= 1, "{:?}", ids);
+        assert_eq!(ids.len(), 10);
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{match_ids, TestApp};
+
+async fn seed(app: &TestApp) {
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "default_filter": { "org": "x" } })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    app.upsert(
+        "docs",
+        &[
+            ("x1", vec![1.0, 0.0], Some(json!({ "org": "x", "lang": "en" }))),
+            ("x2", vec![0.8, 0.2], Some(json!({ "org": "x", "lang": "fr" }))),
+            ("y1", vec![1.0, 0.01], Some(json!({ "org": "y", "lang": "en" }))),
+        ],
+    )
+    .await;
+}
+
+/// The matches are exactly `expected` (in any order), all from org x.
+fn assert_org_x_matches(body: &Value, expected: &[&str]) {
+    for m in body["matches"].as_array().expect("matches array") {
+        assert_eq!(m["metadata"]["org"], "x", "unexpected match {}", m);
+    }
+    let mut ids = match_ids(body);
+    ids.sort();
+    assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn default_filter_always_applies() {
+    let app = TestApp::new();
+    seed(&app).await;
+
+    let (status, body) = app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_org_x_matches(&body, &["x1", "x2"]);
+
+    // A query filter narrows further but can't widen the scope.
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10, "filter": { "lang": "en" } }))
+        .await;
+    assert_org_x_matches(&body, &["x1"]);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10, "filter": { "org": "y" } }))
+        .await;
+    assert!(match_ids(&body).is_empty());
+}
+
+#[tokio::test]
+async fn default_filter_survives_restart() {
+    let app = TestApp::new();
+    seed(&app).await;
+
+    let app = app.restart();
+    let (_, body) = app.query("docs", json!({ "vector": [1.0, 0.01], "top_k": 10 })).await;
+    assert_org_x_matches(&body, &["x1", "x2"]);
+
+    app.request(Method::POST, "/admin/snapshot", None).await;
+    let app = app.restart();
+    let (_, body) = app.query("docs", json!({ "vector": [1.0, 0.01], "top_k": 10 })).await;
+    assert_org_x_matches(&body, &["x1", "x2"]);
+}
+
+#[tokio::test]
+async fn default_filter_must_be_an_object() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "default_filter": "org" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
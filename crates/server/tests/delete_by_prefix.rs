@@ -0,0 +1,70 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use common::{TestApp, OTHER_API_KEY};
+
+async fn collection_names(app: &TestApp) -> Vec<String> {
+    let (_, body) = app.request(Method::GET, "/collections", None).await;
+    let mut names: Vec<String> = body["collections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn deletes_only_matching_collections() {
+    let app = TestApp::new();
+    for name in ["test_a", "test_b", "prod", "my_test_c"] {
+        app.create_collection(name, 2).await;
+    }
+    let (status, _) = app
+        .request_with_key(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "test_other_tenant", "dimension": 2 })),
+            Some(OTHER_API_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/delete-by-prefix",
+            Some(json!({ "prefix": "test_", "confirm": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["deleted"], json!(["test_a", "test_b"]));
+    assert_eq!(collection_names(&app).await, ["my_test_c", "prod"]);
+
+    // Other tenants are untouched, and the deletes survive a restart.
+    let (_, other) = app
+        .request_with_key(Method::GET, "/collections/test_other_tenant", None, Some(OTHER_API_KEY))
+        .await;
+    assert_eq!(other["name"], "test_other_tenant");
+    let app = app.restart();
+    assert_eq!(collection_names(&app).await, ["my_test_c", "prod"]);
+}
+
+#[tokio::test]
+async fn requires_confirmation() {
+    let app = TestApp::new();
+    app.create_collection("test_a", 2).await;
+
+    let (status, _): (StatusCode, Value) = app
+        .request(
+            Method::POST,
+            "/collections/delete-by-prefix",
+            Some(json!({ "prefix": "test_" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(collection_names(&app).await, ["test_a"]);
+}
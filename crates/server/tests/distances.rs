@@ -0,0 +1,79 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 2.0], None)],
+    )
+    .await;
+    app
+}
+
+#[tokio::test]
+async fn distance_between_stored_vectors() {
+    let app = seeded().await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/distances",
+            Some(json!({ "id_a": "a", "id_b": "b" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["metric"], "cosine");
+    assert!((body["distance"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+    assert!(body["similarity"].as_f64().unwrap().abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn distance_to_supplied_vector_matches_query_score() {
+    let app = seeded().await;
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/distances",
+            Some(json!({ "id": "a", "vector": [1.0, 1.0] })),
+        )
+        .await;
+    let (_, query) = app
+        .query("docs", json!({ "vector": [1.0, 1.0], "top_k": 2, "exact": true }))
+        .await;
+    let score = query["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["id"] == "a")
+        .unwrap()["score"]
+        .as_f64()
+        .unwrap();
+    assert!((body["similarity"].as_f64().unwrap() - score).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn missing_id_is_404() {
+    let app = seeded().await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/distances",
+            Some(json!({ "id_a": "a", "id_b": "zzz" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"]["message"].as_str().unwrap().contains("zzz"));
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/distances",
+            Some(json!({ "id": "a", "vector": [1.0, 0.0, 0.0] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
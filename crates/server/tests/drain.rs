@@ -0,0 +1,98 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use common::TestApp;
+use openvdb_server::{serve, DrainReport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+async fn seeded(configure: impl FnOnce(&mut openvdb_server::config::Config)) -> TestApp {
+    let app = TestApp::with_config(configure);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    app
+}
+
+/// Start `serve` on a free port; the returned sender triggers shutdown.
+async fn start(app: &TestApp) -> (SocketAddr, oneshot::Sender<()>, JoinHandle<DrainReport>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = oneshot::channel::<()>();
+    let state = app.state.clone();
+    let server = tokio::spawn(async move {
+        serve(listener, state, async {
+            let _ = rx.await;
+        })
+        .await
+        .unwrap()
+    });
+    (addr, tx, server)
+}
+
+/// A raw HTTP/1.1 query; returns the status line.
+async fn query(addr: SocketAddr) -> String {
+    let body = r#"{"vector":[1.0,0.0],"top_k":1}"#;
+    let request = format!(
+        "POST /collections/docs/query HTTP/1.1\r\nhost: test\r\nx-api-key: {}\r\n\
+         content-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        common::API_KEY,
+        body.len(),
+        body
+    );
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+async fn wait_in_flight(app: &TestApp, n: usize) {
+    for _ in 0..200 {
+        if app.state.in_flight_requests() == n {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("expected {} requests in flight", n);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn in_flight_requests_finish_before_shutdown() {
+    let app = seeded(|c| c.drain_timeout_secs = 10).await;
+    let (addr, shutdown, server) = start(&app).await;
+
+    // Park a query behind the collections lock, then ask for shutdown.
+    let lock = app.state.collections.write().await;
+    let client = tokio::spawn(query(addr));
+    wait_in_flight(&app, 1).await;
+    shutdown.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!server.is_finished());
+    drop(lock);
+
+    assert_eq!(server.await.unwrap(), DrainReport { drained: 1, cut: 0 });
+    assert_eq!(client.await.unwrap(), "HTTP/1.1 200 OK");
+    assert!(app.dir.path().join("snapshot.json").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn requests_past_the_drain_timeout_are_cut() {
+    let app = seeded(|c| {
+        c.drain_timeout_secs = 1;
+        c.snapshot_on_shutdown = false;
+    })
+    .await;
+    let (addr, shutdown, server) = start(&app).await;
+
+    let lock = app.state.collections.write().await;
+    let _client = tokio::spawn(query(addr));
+    wait_in_flight(&app, 1).await;
+    shutdown.send(()).unwrap();
+
+    assert_eq!(server.await.unwrap(), DrainReport { drained: 0, cut: 1 });
+    drop(lock);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::collections::HashSet;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+const DIM: usize = 16;
+
+/// Deterministic pseudo-random vectors (xorshift), so recall is reproducible.
+fn dataset(n: usize) -> Vec<(String, Vec<f32>)> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 10_000) as f32 / 5_000.0 - 1.0
+    };
+    (0..n)
+        .map(|i| (format!("v{}", i), (0..DIM).map(|_| next()).collect()))
+        .collect()
+}
+
+#[tokio::test]
+async fn larger_ef_search_recalls_at_least_as_many_neighbours() {
+    let app = TestApp::new();
+    app.create_collection("docs", DIM).await;
+    let data = dataset(2000);
+    let batch: Vec<_> = data
+        .iter()
+        .map(|(id, v)| (id.as_str(), v.clone(), None))
+        .collect();
+    for chunk in batch.chunks(500) {
+        app.upsert("docs", chunk).await;
+    }
+
+    let top_k = 10;
+    let (mut small_hits, mut large_hits) = (0, 0);
+    for (_, query) in data.iter().step_by(100) {
+        let (_, exact) = app
+            .query("docs", json!({ "vector": query, "top_k": top_k, "exact": true }))
+            .await;
+        let truth: HashSet<String> = match_ids(&exact).into_iter().collect();
+
+        let (status, small) = app
+            .query("docs", json!({ "vector": query, "top_k": top_k, "ef_search": top_k }))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, large) = app
+            .query("docs", json!({ "vector": query, "top_k": top_k, "ef_search": 500 }))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        small_hits += match_ids(&small).iter().filter(|id| truth.contains(*id)).count();
+        large_hits += match_ids(&large).iter().filter(|id| truth.contains(*id)).count();
+    }
+    assert!(
+        large_hits >= small_hits,
+        "ef_search 500 found {} true neighbours, ef_search {} found {}",
+        large_hits,
+        top_k,
+        small_hits
+    );
+}
+
+#[tokio::test]
+async fn ef_search_below_top_k_is_rejected() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 5, "ef_search": 4 }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn oversized_ef_search_and_top_k_are_rejected() {
+    let app = TestApp::with_config(|c| c.max_top_k = 100);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let huge = json!({ "vector": [1.0, 0.0], "top_k": 1, "ef_search": 100_000_000_000_000u64 });
+    let (status, body) = app.query("docs", huge).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"].as_str().unwrap().contains("at most 512"));
+    let (status, _) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 101 }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/estimate",
+            Some(json!({ "top_k": 1, "ef_search": 513 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 100, "ef_search": 512 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Recall@10 of default-breadth queries against `n` vectors, and the
+/// breadth they searched with.
+async fn default_ef_recall(n: usize) -> (f64, u64) {
+    let app = TestApp::with_config(|c| c.ef_search_min = 16);
+    app.create_collection("docs", DIM).await;
+    let data = dataset(n);
+    let batch: Vec<_> = data
+        .iter()
+        .map(|(id, v)| (id.as_str(), v.clone(), None))
+        .collect();
+    for chunk in batch.chunks(500) {
+        app.upsert("docs", chunk).await;
+    }
+
+    let top_k = 10;
+    let (mut hits, mut total, mut ef) = (0, 0, 0);
+    for (_, query) in data.iter().step_by(n / 20) {
+        let (_, exact) = app
+            .query("docs", json!({ "vector": query, "top_k": top_k, "exact": true }))
+            .await;
+        let truth: HashSet<String> = match_ids(&exact).into_iter().collect();
+        let (_, approx) = app
+            .query("docs", json!({ "vector": query, "top_k": top_k, "debug": true }))
+            .await;
+        ef = approx["debug"]["ef"].as_u64().unwrap();
+        hits += match_ids(&approx).iter().filter(|id| truth.contains(*id)).count();
+        total += truth.len();
+    }
+    (hits as f64 / total as f64, ef)
+}
+
+#[tokio::test]
+async fn default_ef_search_grows_with_the_collection() {
+    let (small_recall, small_ef) = default_ef_recall(300).await;
+    let (large_recall, large_ef) = default_ef_recall(3000).await;
+    // 8 * log2(n), above the configured minimum of 16.
+    assert_eq!(small_ef, 66);
+    assert_eq!(large_ef, 93);
+    assert!(small_recall >= 0.9, "recall {} at 300 vectors", small_recall);
+    assert!(large_recall >= 0.9, "recall {} at 3000 vectors", large_recall);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn empty_results_can_be_204() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "lang": "en" })))])
+        .await;
+
+    let no_match = json!({ "vector": [1.0, 0.0], "top_k": 1, "filter": { "lang": "fr" } });
+
+    // Default stays 200 with an empty array.
+    let (status, body) = app.query("docs", no_match.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["matches"], json!([]));
+
+    let mut opt_in = no_match;
+    opt_in["empty_as_204"] = json!(true);
+    let req = app
+        .builder(Method::POST, "/collections/docs/query")
+        .header("content-type", "application/json")
+        .body(opt_in.to_string().into())
+        .unwrap();
+    let (status, _, body) = app.send(req).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(body.is_empty());
+
+    // Non-empty results are unaffected by the flag.
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1, "empty_as_204": true }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["matches"][0]["id"], "a");
+}
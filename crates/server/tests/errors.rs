@@ -0,0 +1,25 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use openvdb_server::models::ErrorResponse;
+
+#[tokio::test]
+async fn errors_are_json_with_a_stable_code() {
+    let app = TestApp::new();
+    let (status, body) = app
+        .request(Method::GET, "/collections/missing", None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let err: ErrorResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(err.error.code, "not_found");
+    assert_eq!(err.error.message, "collection 'missing' not found");
+
+    // Auth failures have the same shape.
+    let (status, body) = app
+        .request_with_key(Method::GET, "/collections", None, Some("nope"))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let err: ErrorResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(err.error.code, "invalid_api_key");
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let ids: Vec<String> = (0..100).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let lang = if i % 4 == 0 { "de" } else { "en" };
+            (id.as_str(), vec![1.0, i as f32], Some(json!({ "lang": lang })))
+        })
+        .collect();
+    app.upsert("docs", &batch).await;
+    app
+}
+
+#[tokio::test]
+async fn estimate_reports_selectivity_and_expected_matches() {
+    let app = seeded().await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/estimate",
+            Some(json!({ "top_k": 10, "filter": { "lang": "de" } })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["vectors"], 100);
+    assert_eq!(body["filter_selectivity"], 0.25);
+    assert_eq!(body["expected_matches"], 10);
+    assert_eq!(body["exact"], false);
+    assert_eq!(body["latency_class"], "fast");
+
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/estimate",
+            Some(json!({ "top_k": 10, "filter": { "lang": "fr" } })),
+        )
+        .await;
+    assert_eq!(body["expected_matches"], 0);
+}
+
+#[tokio::test]
+async fn exact_estimate_scans_everything() {
+    let app = seeded().await;
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/estimate",
+            Some(json!({ "top_k": 5, "exact": true })),
+        )
+        .await;
+    assert_eq!(body["candidates"], 100);
+    assert_eq!(body["exact"], true);
+    assert!(body.get("filter_selectivity").is_none());
+}
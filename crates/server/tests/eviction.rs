@@ -0,0 +1,136 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{match_ids, TestApp, API_KEY, OTHER_API_KEY};
+
+async fn seed(app: &TestApp) {
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[("a", vec![1.0, 0.0], Some(json!({ "n": 1 }))), ("b", vec![0.0, 1.0], None)],
+    )
+    .await;
+    app.request_with_key(
+        Method::POST,
+        "/collections",
+        Some(json!({ "name": "other", "dimension": 3 })),
+        Some(OTHER_API_KEY),
+    )
+    .await;
+}
+
+async fn vector_count(app: &TestApp, key: &str, name: &str) -> u64 {
+    let (status, body) = app
+        .request_with_key(Method::GET, &format!("/collections/{}", name), None, Some(key))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    body["vectors"].as_u64().unwrap()
+}
+
+#[tokio::test]
+async fn evicted_tenant_reloads_on_next_request() {
+    let app = TestApp::new();
+    seed(&app).await;
+
+    // Recently used tenants stay.
+    assert_eq!(app.state.evict_idle_tenants(Duration::from_secs(3600)).await, 0);
+
+    assert_eq!(app.state.evict_idle_tenants(Duration::ZERO).await, 2);
+    assert!(app.state.collections.read().await.is_empty());
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a"]);
+    assert_eq!(body["matches"][0]["metadata"]["n"], 1);
+
+    // Only the tenant that made a request was reloaded.
+    assert!(app.state.is_resident(API_KEY));
+    assert!(!app.state.is_resident(OTHER_API_KEY));
+    assert_eq!(vector_count(&app, OTHER_API_KEY, "other").await, 0);
+    assert!(app.state.is_resident(OTHER_API_KEY));
+}
+
+#[tokio::test]
+async fn snapshot_keeps_evicted_tenants() {
+    let app = TestApp::new();
+    seed(&app).await;
+    app.state.evict_idle_tenants(Duration::ZERO).await;
+
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = app.restart();
+    assert_eq!(vector_count(&app, API_KEY, "docs").await, 2);
+    assert_eq!(vector_count(&app, OTHER_API_KEY, "other").await, 0);
+}
+
+#[tokio::test]
+async fn writes_after_reload_are_durable() {
+    let app = TestApp::new();
+    seed(&app).await;
+    app.request(Method::POST, "/admin/snapshot", None).await;
+    app.upsert("docs", &[("c", vec![1.0, 1.0], None)]).await;
+
+    app.state.evict_idle_tenants(Duration::ZERO).await;
+    app.upsert("docs", &[("d", vec![1.0, -1.0], None)]).await;
+    assert_eq!(vector_count(&app, API_KEY, "docs").await, 4);
+
+    let app = app.restart();
+    assert_eq!(vector_count(&app, API_KEY, "docs").await, 4);
+}
+
+#[tokio::test]
+async fn tenants_loaded_at_boot_are_not_idle() {
+    let app = TestApp::new();
+    seed(&app).await;
+
+    let app = app.restart();
+    assert_eq!(app.state.evict_idle_tenants(Duration::from_secs(3600)).await, 0);
+    assert!(app.state.is_resident(OTHER_API_KEY));
+
+    // So are tenants a snapshot reloads without a request from them.
+    app.state.evict_idle_tenants(Duration::ZERO).await;
+    app.request(Method::POST, "/admin/snapshot", None).await;
+    assert!(app.state.is_resident(OTHER_API_KEY));
+    assert_eq!(app.state.evict_idle_tenants(Duration::from_secs(3600)).await, 0);
+}
+
+// Other tenants keep writing, and sealing WAL segments, while an evicted
+// one is read back off-lock.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reload_races_other_tenants_writes() {
+    let app = TestApp::with_config(|c| {
+        c.wal_segment_bytes = Some(256);
+        c.first_snapshot_after = 0;
+    });
+    seed(&app).await;
+    let many: Vec<(String, Vec<f32>)> =
+        (0..200).map(|i| (format!("v{}", i), vec![i as f32, 1.0])).collect();
+    let many: Vec<_> = many.iter().map(|(id, v)| (id.as_str(), v.clone(), None)).collect();
+    app.upsert("docs", &many).await;
+
+    for _ in 0..5 {
+        app.state.evict_idle_tenants(Duration::ZERO).await;
+        let writes = async {
+            for i in 0..20 {
+                let body = json!({ "vectors": [{ "id": format!("o{}", i), "values": [1.0, 0.0, 0.0] }] });
+                app.request_with_key(
+                    Method::POST,
+                    "/collections/other/vectors/upsert",
+                    Some(body),
+                    Some(OTHER_API_KEY),
+                )
+                .await;
+            }
+        };
+        let (count, ()) = tokio::join!(vector_count(&app, API_KEY, "docs"), writes);
+        assert_eq!(count, 202);
+    }
+    assert_eq!(vector_count(&app, OTHER_API_KEY, "other").await, 20);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+const DIM: usize = 8;
+
+/// Deterministic pseudo-random vectors (LCG), so failures reproduce.
+fn vectors(n: usize) -> Vec<(String, Vec<f32>)> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((seed >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+    };
+    (0..n)
+        .map(|i| (format!("v{}", i), (0..DIM).map(|_| next()).collect()))
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot / (na * nb)
+}
+
+#[tokio::test]
+async fn exact_matches_brute_force_and_approximate_is_close() {
+    let app = TestApp::new();
+    app.create_collection("docs", DIM).await;
+    let data = vectors(500);
+    let batch: Vec<(&str, Vec<f32>, Option<serde_json::Value>)> = data
+        .iter()
+        .map(|(id, v)| (id.as_str(), v.clone(), None))
+        .collect();
+    let (status, _) = app.upsert("docs", &batch).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let query = vec![0.3, -0.1, 0.2, 0.4, -0.3, 0.1, 0.0, 0.25];
+    let mut expected: Vec<(f32, &str)> = data
+        .iter()
+        .map(|(id, v)| (cosine(&query, v), id.as_str()))
+        .collect();
+    expected.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let expected: Vec<&str> = expected.iter().take(10).map(|(_, id)| *id).collect();
+
+    let (status, exact) = app
+        .query("docs", json!({ "vector": query, "top_k": 10, "exact": true }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&exact), expected);
+    assert_eq!(exact["exact"], true);
+
+    let (status, approx) = app
+        .query("docs", json!({ "vector": query, "top_k": 10 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    // 500 points is plenty for HNSW to answer without the fallback.
+    assert_eq!(approx["exact"], false);
+    let approx = match_ids(&approx);
+    let recall = approx.iter().filter(|id| expected.contains(&id.as_str())).count();
+    assert!(recall >= 5, "approximate recall {}/10: {:?}", recall, approx);
+}
+
+#[tokio::test]
+async fn exact_query_respects_timeout() {
+    let app = TestApp::with_config(|c| c.query_timeout_ms = 0);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1, "exact": true }))
+        .await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+
+    // Approximate queries aren't subject to the scan timeout.
+    let (status, _) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
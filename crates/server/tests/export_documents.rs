@@ -0,0 +1,62 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn export(app: &TestApp, uri: &str) -> (StatusCode, Vec<Value>) {
+    let req = app.builder(Method::GET, uri).body(Body::empty()).unwrap();
+    let (status, headers, body) = app.send(req).await;
+    if status != StatusCode::OK {
+        return (status, Vec::new());
+    }
+    assert_eq!(headers["content-type"], "application/x-ndjson");
+    let lines = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (status, lines)
+}
+
+#[tokio::test]
+async fn export_streams_every_document_in_id_order() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let ids: Vec<String> = (0..2500).map(|i| format!("v{:04}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .map(|id| (id.as_str(), vec![1.0, 0.5], Some(json!({ "document": format!("text {}", id) }))))
+        .collect();
+    app.upsert("docs", &batch).await;
+
+    let (status, lines) = export(&app, "/collections/docs/export/documents").await;
+    assert_eq!(status, StatusCode::OK);
+    let exported: Vec<&str> = lines.iter().map(|l| l["id"].as_str().unwrap()).collect();
+    assert_eq!(exported, ids);
+    assert_eq!(lines[7]["document"], "text v0007");
+    assert!(lines[7].get("values").is_none());
+}
+
+#[tokio::test]
+async fn export_reads_the_chosen_field() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], Some(json!({ "body": "alpha", "n": 1 }))),
+            ("b", vec![0.0, 1.0], None),
+        ],
+    )
+    .await;
+
+    let (_, lines) = export(&app, "/collections/docs/export/documents?field=body").await;
+    assert_eq!(lines[0]["document"], "alpha");
+    assert_eq!(lines[0]["metadata"]["n"], 1);
+    assert_eq!(lines[1]["document"], Value::Null);
+
+    let (status, _) = export(&app, "/collections/missing/export/documents").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
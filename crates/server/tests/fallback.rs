@@ -0,0 +1,31 @@
+mod common;
+
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+/// hnsw_rs regularly loses points in tiny multi-layer graphs; the exact
+/// fallback must turn that into complete, correctly ordered results.
+#[tokio::test]
+async fn small_collections_return_complete_results() {
+    let app = TestApp::new();
+
+    for i in 0..40 {
+        let name = format!("docs{}", i);
+        app.create_collection(&name, 3).await;
+        app.upsert(
+            &name,
+            &[
+                ("a", vec![1.0, 0.0, 0.0], None),
+                ("b", vec![0.0, 1.0, 0.0], None),
+                ("c", vec![0.9, 0.1, 0.0], None),
+            ],
+        )
+        .await;
+
+        let (_, body) = app
+            .query(&name, json!({ "vector": [1.0, 0.0, 0.0], "top_k": 3 }))
+            .await;
+        assert_eq!(match_ids(&body), vec!["a", "c", "b"], "collection {}", name);
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+#[tokio::test]
+async fn selective_filter_still_fills_top_k() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    // 1 in 50 vectors is "rare", and they all sit far from the query, so
+    // a fixed over-fetch would miss most of them.
+    let ids: Vec<String> = (0..1000).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let rare = i % 50 == 0;
+            let x = if rare { -1.0 } else { 1.0 };
+            let kind = if rare { "rare" } else { "common" };
+            (id.as_str(), vec![x, i as f32 / 1000.0], Some(json!({ "kind": kind })))
+        })
+        .collect();
+    app.upsert("docs", &batch).await;
+
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 10, "filter": { "kind": "rare" } }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body).len(), 10);
+    for m in body["matches"].as_array().unwrap() {
+        assert_eq!(m["metadata"]["kind"], "rare");
+    }
+}
+
+#[tokio::test]
+async fn nested_and_multi_key_filters() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            (
+                "a",
+                vec![1.0, 0.0],
+                Some(json!({ "lang": "en", "author": { "name": "ann", "age": 30 } })),
+            ),
+            ("b", vec![1.0, 0.1], Some(json!({ "lang": "de", "author": { "name": "ann" } }))),
+            ("c", vec![1.0, 0.2], Some(json!({ "lang": "en", "author": { "name": "bob" } }))),
+            ("d", vec![1.0, 0.3], Some(json!({ "lang": "en" }))),
+        ],
+    )
+    .await;
+
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 4,
+                "filter": { "lang": "en", "author": { "name": "ann" } }
+            }),
+        )
+        .await;
+    assert_eq!(match_ids(&body), ["a"]);
+
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 4,
+                "filter": { "author": { "name": "ann" } }
+            }),
+        )
+        .await;
+    assert_eq!(match_ids(&body), ["a", "b"]);
+}
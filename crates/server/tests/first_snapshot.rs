@@ -0,0 +1,34 @@
+mod common;
+
+use std::time::Duration;
+
+use common::TestApp;
+
+async fn wait_for(path: &std::path::Path) -> bool {
+    for _ in 0..50 {
+        if path.exists() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn fresh_deployment_snapshots_at_first_milestone() {
+    let app = TestApp::with_config(|c| c.first_snapshot_after = 3);
+    let snapshot = app.dir.path().join("snapshot.json");
+
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!snapshot.exists(), "snapshot taken before the milestone");
+
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+    assert!(wait_for(&snapshot).await, "no snapshot after the milestone");
+    assert_eq!(app.state.wal_writes(), 3);
+
+    // Once a snapshot exists the trigger stays off, even after a restart.
+    let app = app.restart();
+    assert!(!app.state.record_wal_writes(10));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn get_returns_stored_values_and_metadata() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![0.5, 0.25], Some(json!({ "lang": "en" })))])
+        .await;
+
+    let (status, body) = app
+        .request(Method::GET, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "id": "a", "values": [0.5, 0.25], "metadata": { "lang": "en" }, "version": 1 })
+    );
+
+    let (_, body) = app
+        .request(
+            Method::GET,
+            "/collections/docs/vectors/a?include_timestamps=true",
+            None,
+        )
+        .await;
+    assert_eq!(body["created_at"], body["updated_at"]);
+}
+
+#[tokio::test]
+async fn get_of_unknown_or_deleted_id_is_404() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app
+        .request(Method::GET, "/collections/docs/vectors/nope", None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = app
+        .request(Method::DELETE, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .request(Method::GET, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn include_norm_reports_the_stored_norm() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![3.0, 4.0], None)]).await;
+
+    let (_, body) = app
+        .request(Method::GET, "/collections/docs/vectors/a?include_norm=true", None)
+        .await;
+    assert_eq!(body["norm"], 5.0);
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 1.0], "top_k": 1, "include_norm": true }))
+        .await;
+    assert_eq!(body["matches"][0]["norm"], 5.0);
+
+    // Off by default.
+    let (_, body) = app.query("docs", json!({ "vector": [1.0, 1.0], "top_k": 1 })).await;
+    assert!(body["matches"][0].get("norm").is_none());
+}
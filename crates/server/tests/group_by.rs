@@ -0,0 +1,62 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+async fn seed() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("x1", vec![1.0, 0.0], Some(json!({ "author": "x" }))),
+            ("x2", vec![1.0, 0.05], Some(json!({ "author": "x" }))),
+            ("x3", vec![1.0, 0.1], Some(json!({ "author": "x" }))),
+            ("y1", vec![1.0, 0.3], Some(json!({ "author": "y" }))),
+            ("n1", vec![1.0, 0.4], None),
+            ("z1", vec![1.0, 0.6], Some(json!({ "author": "z" }))),
+        ],
+    )
+    .await;
+    app
+}
+
+#[tokio::test]
+async fn group_by_keeps_best_per_group_in_score_order() {
+    let app = seed().await;
+
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 4, "group_by": "author", "exact": true }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    // Ungrouped points (no author) are not limited.
+    assert_eq!(match_ids(&body), ["x1", "y1", "n1", "z1"]);
+
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0], "top_k": 3,
+                "group_by": "author", "group_size": 2, "exact": true,
+            }),
+        )
+        .await;
+    assert_eq!(match_ids(&body), ["x1", "x2", "y1"]);
+}
+
+#[tokio::test]
+async fn group_size_zero_is_rejected() {
+    let app = seed().await;
+    let (status, _) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 3, "group_by": "author", "group_size": 0 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+
+#[tokio::test]
+async fn heavy_tasks_run_one_at_a_time() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    // Stand-in for a long snapshot: hold the permit.
+    let running = app.state.try_begin_heavy_task("test").unwrap();
+    for uri in [
+        "/admin/snapshot",
+        "/collections/docs/compact",
+        "/collections/docs/reindex",
+    ] {
+        let (status, body) = app.request(Method::POST, uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", uri);
+        assert_eq!(body["error"]["message"], "maintenance in progress");
+    }
+
+    // Background snapshots queue instead.
+    let state = app.state.clone();
+    let queued = tokio::spawn(async move { state.write_snapshot().await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!queued.is_finished());
+    drop(running);
+    let summary = queued.await.unwrap().unwrap();
+    assert_eq!(summary.vectors, 1);
+
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn concurrent_snapshots_never_overlap() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (first, second) = tokio::join!(
+        app.request(Method::POST, "/admin/snapshot", None),
+        app.request(Method::POST, "/admin/snapshot", None),
+    );
+    // Either the second found the first running, or they ran back to back.
+    let statuses = [first.0, second.0];
+    assert!(statuses.contains(&StatusCode::OK));
+    assert!(statuses
+        .iter()
+        .all(|s| [StatusCode::OK, StatusCode::CONFLICT].contains(s)));
+}
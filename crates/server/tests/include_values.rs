@@ -0,0 +1,28 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn matches_carry_values_only_when_asked() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "lang": "en" })))])
+        .await;
+
+    let (_, body) = app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await;
+    assert!(body["matches"][0].get("values").is_none());
+
+    // Every search path: HNSW, filtered and exact.
+    for extra in [json!({}), json!({ "filter": { "lang": "en" } }), json!({ "exact": true })] {
+        let mut request = json!({ "vector": [1.0, 0.0], "top_k": 1, "include_values": true });
+        request
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let (status, body) = app.query("docs", request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["matches"][0]["values"], json!([1.0, 0.0]));
+    }
+}
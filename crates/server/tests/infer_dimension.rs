@@ -0,0 +1,72 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+async fn dimension(app: &TestApp) -> u64 {
+    let (_, body) = app.request(Method::GET, "/collections/docs", None).await;
+    body["dimension"].as_u64().unwrap()
+}
+
+#[tokio::test]
+async fn first_upsert_sets_dimension() {
+    let app = TestApp::new();
+    let (status, _) = app.create_collection("docs", 0).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dimension(&app).await, 0);
+
+    // Nothing to match yet, whatever the query's length.
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(match_ids(&body).is_empty());
+
+    let (status, _) = app.upsert("docs", &[("a", vec![1.0, 0.0, 0.0], None)]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dimension(&app).await, 3);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(match_ids(&body), ["a"]);
+}
+
+#[tokio::test]
+async fn inferred_dimension_is_locked_and_persisted() {
+    let app = TestApp::new();
+    app.create_collection("docs", 0).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app.upsert("docs", &[("b", vec![1.0, 0.0, 0.0], None)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let app = app.restart();
+    assert_eq!(dimension(&app).await, 2);
+    let (status, _) = app.upsert("docs", &[("b", vec![1.0, 0.0, 0.0], None)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn bulk_upsert_infers_dimension() {
+    let app = TestApp::new();
+    app.create_collection("docs", 0).await;
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [
+                { "id": "a", "values": [1.0, 0.0, 0.0, 0.0] },
+                { "id": "b", "values": [0.0, 1.0, 0.0, 0.0] },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(dimension(&app).await, 4);
+
+    let app = app.restart();
+    assert_eq!(dimension(&app).await, 4);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn upsert(app: &TestApp, uri: &str, ids: &[&str]) -> (StatusCode, Value) {
+    let vectors: Vec<_> = ids
+        .iter()
+        .map(|id| json!({ "id": id, "values": [1.0, 0.0] }))
+        .collect();
+    app.request(Method::POST, uri, Some(json!({ "vectors": vectors })))
+        .await
+}
+
+#[tokio::test]
+async fn insert_only_rejects_duplicates() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "audit", "dimension": 2, "mode": "insert_only" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let single = "/collections/audit/vectors/upsert";
+    let bulk = "/collections/audit/vectors/upsert?bulk=true";
+    assert_eq!(upsert(&app, single, &["a"]).await.0, StatusCode::OK);
+    let (status, body) = upsert(&app, single, &["a"]).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["results"][0]["error"].as_str().unwrap().contains("insert-only"));
+
+    assert_eq!(upsert(&app, bulk, &["b", "a"]).await.0, StatusCode::CONFLICT);
+    assert_eq!(upsert(&app, bulk, &["c", "c"]).await.0, StatusCode::CONFLICT);
+    assert_eq!(upsert(&app, bulk, &["b", "c"]).await.0, StatusCode::OK);
+
+    // The mode is part of the persisted config.
+    let app = app.restart();
+    assert_eq!(upsert(&app, single, &["b"]).await.0, StatusCode::CONFLICT);
+    assert_eq!(upsert(&app, single, &["d"]).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn upsert_mode_still_overwrites() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let uri = "/collections/docs/vectors/upsert";
+    assert_eq!(upsert(&app, uri, &["a"]).await.0, StatusCode::OK);
+    assert_eq!(upsert(&app, uri, &["a"]).await.0, StatusCode::OK);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{match_ids, TestApp, API_KEY, OTHER_API_KEY};
+
+/// Data split across snapshot and WAL, for two tenants.
+async fn seeded(configure: impl FnOnce(&mut openvdb_server::config::Config)) -> TestApp {
+    let app = TestApp::with_config(configure);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "v": 1 })))]).await;
+    app.request(Method::POST, "/admin/snapshot", None).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "v": 2 }))), ("b", vec![0.0, 1.0], None)])
+        .await;
+    app.request_with_key(
+        Method::POST,
+        "/collections",
+        Some(json!({ "name": "other", "dimension": 3 })),
+        Some(OTHER_API_KEY),
+    )
+    .await;
+    app.restart()
+}
+
+#[tokio::test]
+async fn cold_tenant_is_loaded_on_first_request() {
+    let app = seeded(|c| c.lazy_tenant_load = true).await;
+    assert!(app.state.collections.read().await.is_empty());
+    assert!(!app.state.is_resident(API_KEY));
+    assert!(!app.state.is_resident(OTHER_API_KEY));
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 2, "exact": true }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a", "b"]);
+    // The WAL update on top of the snapshot wins.
+    assert_eq!(body["matches"][0]["metadata"]["v"], 2);
+
+    assert!(app.state.is_resident(API_KEY));
+    assert!(!app.state.is_resident(OTHER_API_KEY));
+
+    // Cross-tenant views load everything first.
+    let (_, body) = app.request(Method::GET, "/admin/collections", None).await;
+    assert_eq!(body["collections"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn slow_load_answers_503_then_succeeds() {
+    let app = seeded(|c| {
+        c.lazy_tenant_load = true;
+        c.tenant_load_timeout_ms = 50;
+    })
+    .await;
+
+    // Hold the lock so the load can't finish in time.
+    let guard = app.state.collections.write().await;
+    let (status, _) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    drop(guard);
+
+    // The load keeps going in the background.
+    for _ in 0..50 {
+        if app.state.is_resident(API_KEY) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(app.state.is_resident(API_KEY));
+    let (status, body) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["vectors"], 2);
+}
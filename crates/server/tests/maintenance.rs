@@ -0,0 +1,82 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+const ADMIN_KEY: &str = "admin-key";
+
+fn app() -> TestApp {
+    TestApp::with_config(|c| c.admin_api_key = Some(ADMIN_KEY.into()))
+}
+
+async fn set_maintenance(app: &TestApp, enabled: bool) {
+    let (status, body) = app
+        .request_with_key(
+            Method::POST,
+            "/admin/maintenance",
+            Some(json!({ "enabled": enabled })),
+            Some(ADMIN_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], enabled);
+}
+
+async fn upsert(app: &TestApp, id: &str) -> StatusCode {
+    app.upsert("docs", &[(id, vec![1.0, 0.0], None)]).await.0
+}
+
+#[tokio::test]
+async fn maintenance_blocks_writes_and_allows_reads() {
+    let app = app();
+    app.create_collection("docs", 2).await;
+    assert_eq!(upsert(&app, "a").await, StatusCode::OK);
+
+    set_maintenance(&app, true).await;
+    assert_eq!(upsert(&app, "b").await, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        app.create_collection("other", 2).await.0,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    let (status, _) = app
+        .request(Method::DELETE, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 5 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(common::match_ids(&body), ["a"]);
+    let (status, _) = app
+        .request(Method::GET, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Still on after a restart, until explicitly cleared.
+    let app = app.restart();
+    assert_eq!(upsert(&app, "b").await, StatusCode::SERVICE_UNAVAILABLE);
+    let (_, body) = app
+        .request_with_key(Method::GET, "/admin/maintenance", None, Some(ADMIN_KEY))
+        .await;
+    assert_eq!(body["enabled"], true);
+
+    set_maintenance(&app, false).await;
+    assert_eq!(upsert(&app, "b").await, StatusCode::OK);
+    let app = app.restart();
+    assert_eq!(upsert(&app, "c").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn only_admins_toggle_maintenance() {
+    let app = app();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/admin/maintenance",
+            Some(json!({ "enabled": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
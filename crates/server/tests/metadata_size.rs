@@ -0,0 +1,57 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn oversized_metadata_is_rejected() {
+    let app = TestApp::with_config(|c| c.max_metadata_bytes = Some(32));
+    app.create_collection("docs", 2).await;
+
+    let big = json!({ "text": "x".repeat(64) });
+    let (status, body) = app
+        .upsert(
+            "docs",
+            &[
+                ("small", vec![1.0, 0.0], Some(json!({ "k": "v" }))),
+                ("big", vec![0.0, 1.0], Some(big.clone())),
+            ],
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["results"][1]["status"], 400);
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [{ "id": "big", "values": [0.0, 1.0], "metadata": big }] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 1);
+}
+
+#[tokio::test]
+async fn stats_track_metadata_bytes() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    // `{"k":"v"}` is 9 bytes.
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "k": "v" })))])
+        .await;
+    app.upsert("docs", &[("b", vec![0.0, 1.0], Some(json!({ "k": "v" })))])
+        .await;
+    // Overwriting replaces the old metadata's bytes.
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["metadata_bytes"], 9);
+
+    app.request(Method::DELETE, "/collections/docs/vectors/b", None).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["metadata_bytes"], 0);
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+async fn create_normalized(app: &TestApp) {
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "metric": "normalized_cosine" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+fn score_of(body: &serde_json::Value, id: &str) -> f64 {
+    body["matches"]
+        .as_array()
+        .expect("matches array")
+        .iter()
+        .find(|m| m["id"] == id)
+        .unwrap_or_else(|| panic!("{} missing from {}", id, body))["score"]
+        .as_f64()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn normalized_cosine_reports_cosine_scores() {
+    let app = TestApp::new();
+    create_normalized(&app).await;
+    app.upsert("docs", &[("a", vec![3.0, 4.0], None), ("b", vec![0.0, 10.0], None)])
+        .await;
+
+    // Neither side is unit length; scores must still be plain cosine.
+    let (status, body) = app
+        .query("docs", json!({ "vector": [2.0, 0.0], "top_k": 2 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!((score_of(&body, "a") - 0.6).abs() < 1e-5);
+    assert!(score_of(&body, "b").abs() < 1e-5);
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_type"], "hnsw_normalized_cosine");
+}
+
+#[tokio::test]
+async fn metric_survives_snapshot_and_restart() {
+    let app = TestApp::new();
+    create_normalized(&app).await;
+    app.upsert("docs", &[("a", vec![3.0, 4.0], None)]).await;
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let snapshot = std::fs::read_to_string(app.dir.path().join("snapshot.json")).unwrap();
+    let snapshot: serde_json::Value = serde_json::from_str(&snapshot).unwrap();
+    let docs = &snapshot["tenants"][common::API_KEY]["docs"];
+    assert_eq!(docs["config"]["metric"], "normalized_cosine");
+    assert_eq!(docs["normalized"], true);
+    let stored = docs["vectors"][0]["values"].as_array().unwrap();
+    assert!((stored[0].as_f64().unwrap() - 0.6).abs() < 1e-6);
+
+    let app = app.restart();
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_type"], "hnsw_normalized_cosine");
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert!((score_of(&body, "a") - 0.6).abs() < 1e-5);
+}
+
+#[tokio::test]
+async fn unknown_metric_is_rejected() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "metric": "hamming" })),
+        )
+        .await;
+    assert!(status.is_client_error());
+}
+
+async fn create_with_metric(app: &TestApp, metric: &str) {
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "metric": metric })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn dot_ranks_by_inner_product() {
+    let app = TestApp::new();
+    create_with_metric(&app, "dot").await;
+    // "long" points slightly away from the query but is much longer, so it
+    // wins on inner product and loses on cosine.
+    app.upsert(
+        "docs",
+        &[("short", vec![1.0, 0.0], None), ("long", vec![4.0, 3.0], None)],
+    )
+    .await;
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 2 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(common::match_ids(&body), ["long", "short"]);
+    assert!((score_of(&body, "long") - 4.0).abs() < 1e-4);
+    assert!((score_of(&body, "short") - 1.0).abs() < 1e-4);
+    assert_eq!(body["metric"], "dot");
+
+    let app = app.restart();
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_type"], "hnsw_dot");
+    let (_, body) = app
+        .query("docs", json!({ "vector": [-1.0, 0.0], "top_k": 2, "exact": true }))
+        .await;
+    assert_eq!(common::match_ids(&body), ["short", "long"]);
+    assert!((score_of(&body, "long") + 4.0).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn l2_scores_are_negated_distances() {
+    let app = TestApp::new();
+    create_with_metric(&app, "l2").await;
+    app.upsert(
+        "docs",
+        &[("near", vec![1.0, 1.0], None), ("far", vec![10.0, 10.0], None)],
+    )
+    .await;
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 2.0], "top_k": 2 }))
+        .await;
+    assert_eq!(common::match_ids(&body), ["near", "far"]);
+    assert!((score_of(&body, "near") + 1.0).abs() < 1e-4);
+    assert_eq!(body["metric"], "l2");
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_type"], "hnsw_l2");
+
+    let (status, _) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 2.0], "top_k": 2, "score_mode": "angular" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// `field` of every match, in rank order.
+fn column(body: &serde_json::Value, field: &str) -> Vec<f64> {
+    body["matches"]
+        .as_array()
+        .expect("matches array")
+        .iter()
+        .map(|m| m[field].as_f64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn distance_follows_rank() {
+    for metric in ["l2", "cosine"] {
+        let app = TestApp::new();
+        create_with_metric(&app, metric).await;
+        let ids: Vec<String> = (0..20).map(|i| format!("v{}", i)).collect();
+        let batch: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), vec![1.0, i as f32 * 0.3], None))
+            .collect();
+        app.upsert("docs", &batch).await;
+
+        let (_, body) = app
+            .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10 }))
+            .await;
+        let distances = column(&body, "distance");
+        let scores = column(&body, "score");
+        assert_eq!(distances.len(), 10, "{}", metric);
+        assert!(distances.windows(2).all(|w| w[0] <= w[1]), "{}: {:?}", metric, distances);
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]), "{}: {:?}", metric, scores);
+    }
+
+    // Cosine scores stay `1 - distance`.
+    let (_, body) = {
+        let app = TestApp::new();
+        create_with_metric(&app, "cosine").await;
+        app.upsert("docs", &[("a", vec![3.0, 4.0], None)]).await;
+        app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await
+    };
+    let m = &body["matches"][0];
+    let (score, distance) = (m["score"].as_f64().unwrap(), m["distance"].as_f64().unwrap());
+    assert!((score - 0.6).abs() < 1e-5);
+    assert!((score + distance - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn default_metric_parses_from_its_env_names() {
+    use openvdb_server::index::Metric;
+    assert_eq!("l2".parse(), Ok(Metric::L2));
+    assert_eq!("Dot".parse(), Ok(Metric::Dot));
+    assert_eq!("normalized_cosine".parse(), Ok(Metric::NormalizedCosine));
+    assert!("manhattan".parse::<Metric>().is_err());
+}
+
+#[tokio::test]
+async fn collections_without_a_metric_get_the_server_default() {
+    let app = TestApp::with_config(|c| c.default_metric = openvdb_server::index::Metric::L2);
+    let cases = [
+        (json!({ "name": "plain", "dimension": 2 }), "hnsw_l2"),
+        (json!({ "name": "cosine", "dimension": 2, "metric": "cosine" }), "hnsw_cosine"),
+        (
+            json!({ "name": "normalized", "dimension": 2, "normalize": true }),
+            "hnsw_normalized_cosine",
+        ),
+    ];
+    for (request, index_type) in cases {
+        let uri = format!("/collections/{}/stats", request["name"].as_str().unwrap());
+        let (status, _) = app.request(Method::POST, "/collections", Some(request)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, stats) = app.request(Method::GET, &uri, None).await;
+        assert_eq!(stats["index_type"], index_type);
+    }
+
+    app.upsert("plain", &[("a", vec![1.0, 0.0], None)]).await;
+    let (_, body) = app.query("plain", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await;
+    assert_eq!(body["metric"], "l2");
+}
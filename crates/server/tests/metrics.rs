@@ -0,0 +1,42 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+fn sample(body: &str, series: &str) -> f64 {
+    body.lines()
+        .find_map(|l| l.strip_prefix(series).and_then(|rest| rest.strip_prefix(' ')))
+        .unwrap_or_else(|| panic!("{} missing from:\n{}", series, body))
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn metrics_report_lock_wait_and_hold_by_mode() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    app.query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 })).await;
+
+    let (status, headers, body) = app
+        .send(app.builder(Method::GET, "/metrics").body(Default::default()).unwrap())
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers[axum::http::header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let body = String::from_utf8(body).unwrap();
+
+    for metric in ["wait", "hold"] {
+        for mode in ["read", "write"] {
+            let name = format!("openvdb_collections_lock_{}_seconds", metric);
+            let count = sample(&body, &format!("{}_count{{mode=\"{}\"}}", name, mode));
+            assert!(count >= 1.0, "{} {} count {}", name, mode, count);
+            let inf = sample(&body, &format!("{}_bucket{{mode=\"{}\",le=\"+Inf\"}}", name, mode));
+            assert_eq!(inf, count);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::collections::HashSet;
+
+use axum::http::StatusCode;
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+/// `near` vectors clustered around +x and `far` ones pointing away from it,
+/// with deterministic (xorshift) jitter.
+fn dataset(near: usize, far: usize) -> Vec<(String, Vec<f32>)> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 10_000) as f32 / 50_000.0 - 0.1
+    };
+    (0..near + far)
+        .map(|i| {
+            let x = if i < near { 1.0 } else { -0.5 + next() };
+            (format!("v{}", i), vec![x, next(), next(), next()])
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn min_score_returns_every_match_above_the_threshold() {
+    let app = TestApp::new();
+    app.create_collection("docs", 4).await;
+    let data = dataset(30, 270);
+    let batch: Vec<_> = data
+        .iter()
+        .map(|(id, v)| (id.as_str(), v.clone(), None))
+        .collect();
+    app.upsert("docs", &batch).await;
+
+    // top_k leaves room for more than the cluster: the threshold decides.
+    let request = json!({ "vector": [1.0, 0.0, 0.0, 0.0], "top_k": 50, "min_score": 0.8 });
+    let (status, body) = app.query("docs", request.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|m| m["score"].as_f64().unwrap() >= 0.8));
+
+    let mut exact = request;
+    exact["exact"] = json!(true);
+    let (_, truth) = app.query("docs", exact).await;
+    let truth: HashSet<String> = match_ids(&truth).into_iter().collect();
+    let found: HashSet<String> = match_ids(&body).into_iter().collect();
+    assert_eq!(truth.len(), 30);
+    // HNSW can leave the odd node unreachable, so allow for a miss or two.
+    assert!(found.is_subset(&truth));
+    assert!(found.len() >= 28, "found {} of 30", found.len());
+
+    // top_k still caps a threshold query.
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 0.0, 0.0], "top_k": 5, "min_score": 0.8 }))
+        .await;
+    assert_eq!(match_ids(&body).len(), 5);
+}
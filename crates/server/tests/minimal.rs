@@ -0,0 +1,63 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, StatusCode},
+};
+use serde_json::{json, Value};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn minimal_profile_returns_only_ids_and_scores() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], Some(json!({ "title": "x" }))),
+            ("b", vec![0.0, 1.0], None),
+        ],
+    )
+    .await;
+
+    let query = json!({
+        "vector": [1.0, 0.0],
+        "top_k": 1,
+        "debug": true,
+        "include_timestamps": true,
+    });
+    let req = app
+        .builder(Method::POST, "/collections/docs/query")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, "application/json; profile=minimal")
+        .body(Body::from(query.to_string()))
+        .unwrap();
+    let (status, headers, body) = app.send(req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("application/json"));
+
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["matches"], json!([{ "id": "a", "score": 1.0 }]));
+    let keys: Vec<&String> = body.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["cursor", "matches"]);
+
+    // The full representation of the same query carries everything else.
+    let (_, full) = app.query("docs", query).await;
+    assert_eq!(full["matches"][0]["metadata"], json!({ "title": "x" }));
+    assert!(full["debug"].is_object());
+}
+
+#[tokio::test]
+async fn get_vector_omits_absent_metadata() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, body) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.get("metadata").is_none(), "body: {}", body);
+}
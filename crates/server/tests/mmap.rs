@@ -0,0 +1,67 @@
+mod common;
+
+use std::path::Path;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+fn vector_files(data_dir: &Path) -> usize {
+    std::fs::read_dir(data_dir.join("vectors")).map_or(0, |d| d.count())
+}
+
+#[tokio::test]
+async fn mmap_store_serves_exact_queries_and_snapshots() {
+    let app = TestApp::with_config(|c| c.mmap_vectors = true);
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], None),
+            ("b", vec![0.0, 1.0], None),
+            ("c", vec![0.6, 0.8], None),
+        ],
+    )
+    .await;
+    // Overwrites rewrite the vector's slot in place.
+    app.upsert("docs", &[("b", vec![-1.0, 0.0], None)]).await;
+    assert_eq!(vector_files(app.dir.path()), 1);
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 3, "exact": true }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a", "c", "b"]);
+    assert!((body["matches"][2]["score"].as_f64().unwrap() + 1.0).abs() < 1e-5);
+
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/count",
+            Some(json!({ "vector": [1.0, 0.0], "min_score": 0.5 })),
+        )
+        .await;
+    assert_eq!(body["count"], 2);
+
+    // Snapshots read the values back out of the file.
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let app = app.restart();
+    assert_eq!(vector_files(app.dir.path()), 1);
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 3, "exact": true }))
+        .await;
+    assert_eq!(match_ids(&body), ["a", "c", "b"]);
+}
+
+#[tokio::test]
+async fn dropping_a_collection_removes_its_file() {
+    let app = TestApp::with_config(|c| c.mmap_vectors = true);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    assert_eq!(vector_files(app.dir.path()), 1);
+
+    app.request(Method::DELETE, "/collections/docs", None).await;
+    assert_eq!(vector_files(app.dir.path()), 0);
+}
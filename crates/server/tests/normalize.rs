@@ -0,0 +1,48 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn normalized_collections_store_unit_vectors() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "normalize": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("a", vec![3.0, 4.0], None), ("b", vec![0.0, 2.0], None)])
+        .await;
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_type"], "hnsw_normalized_cosine");
+
+    // Scores are still cosine similarities, whatever the query's length.
+    let (_, body) = app.query("docs", json!({ "vector": [6.0, 8.0], "top_k": 2 })).await;
+    assert_eq!(common::match_ids(&body), ["a", "b"]);
+    assert!((body["matches"][0]["score"].as_f64().unwrap() - 1.0).abs() < 1e-5);
+    assert!((body["matches"][1]["score"].as_f64().unwrap() - 0.8).abs() < 1e-5);
+
+    // The original values are not kept.
+    let (_, body) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    let values: Vec<f64> = serde_json::from_value(body["values"].clone()).unwrap();
+    assert!((values[0] - 0.6).abs() < 1e-6 && (values[1] - 0.8).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn normalize_needs_a_cosine_metric() {
+    let app = TestApp::new();
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "metric": "l2", "normalize": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]["message"].as_str().unwrap().contains("cosine"));
+}
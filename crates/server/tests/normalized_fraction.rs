@@ -0,0 +1,46 @@
+mod common;
+
+use axum::http::Method;
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn stats_report_the_share_of_unit_length_upserts() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert!(stats.get("normalized_fraction").is_none());
+
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![3.0, 4.0], None)])
+        .await;
+    app.request(
+        Method::POST,
+        "/collections/docs/vectors/upsert?bulk=true",
+        Some(json!({ "vectors": [
+            { "id": "c", "values": [0.6, 0.8] },
+            { "id": "d", "values": [0.0, 0.9995] },
+        ] })),
+    )
+    .await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["normalized_fraction"], 0.75);
+
+    // Compaction rebuilds the index but keeps the counts.
+    app.request(Method::POST, "/collections/docs/compact", None).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["normalized_fraction"], 0.75);
+}
+
+#[tokio::test]
+async fn only_cosine_collections_report_it() {
+    let app = TestApp::new();
+    app.request(
+        Method::POST,
+        "/collections",
+        Some(json!({ "name": "docs", "dimension": 2, "metric": "l2" })),
+    )
+    .await;
+    app.upsert("docs", &[("a", vec![3.0, 4.0], None)]).await;
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert!(stats.get("normalized_fraction").is_none());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+#[tokio::test]
+async fn overwritten_id_appears_once_with_its_new_score() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+    // Move `a` away from the query; its old node stays in the graph.
+    app.upsert("docs", &[("a", vec![-1.0, 0.1], None)]).await;
+
+    let request = json!({ "vector": [1.0, 0.0], "top_k": 10 });
+    let (status, body) = app.query("docs", request.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["b", "a"]);
+
+    let mut exact = request;
+    exact["exact"] = json!(true);
+    let (_, truth) = app.query("docs", exact).await;
+    assert_eq!(body["matches"][1]["score"], truth["matches"][1]["score"]);
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_nodes"], 3);
+    assert_eq!(stats["dead_nodes"], 1);
+}
+
+#[tokio::test]
+async fn stale_nodes_near_the_query_do_not_hide_live_matches() {
+    let app = TestApp::with_config(|c| c.auto_compact = false);
+    app.create_collection("docs", 2).await;
+    let live: Vec<_> = (0..20)
+        .map(|i| {
+            let angle = 0.5 + i as f32 * 0.1;
+            (format!("v{}", i), vec![angle.cos(), angle.sin()])
+        })
+        .collect();
+    let live: Vec<_> = live.iter().map(|(id, v)| (id.as_str(), v.clone(), None)).collect();
+    app.upsert("docs", &live).await;
+    // Far more than `4 * top_k` stale nodes right next to the query.
+    for i in 0..60 {
+        app.upsert("docs", &[("x", vec![1.0, i as f32 * 0.001], None)]).await;
+    }
+    app.upsert("docs", &[("x", vec![-1.0, 0.0], None)]).await;
+
+    let request = json!({ "vector": [1.0, 0.0], "top_k": 10 });
+    let (status, body) = app.query("docs", request.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut exact = request;
+    exact["exact"] = json!(true);
+    let (_, truth) = app.query("docs", exact).await;
+    assert_eq!(match_ids(&truth).len(), 10);
+    assert_eq!(match_ids(&body), match_ids(&truth));
+}
+
+#[tokio::test]
+async fn duplicate_ids_within_a_bulk_batch_keep_the_last() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [
+                { "id": "a", "values": [1.0, 0.0] },
+                { "id": "b", "values": [0.0, 1.0] },
+                { "id": "a", "values": [-1.0, 0.1] },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10 }))
+        .await;
+    assert_eq!(match_ids(&body), ["b", "a"]);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+async fn upsert_json(app: &TestApp, uri: &str, vectors: serde_json::Value) -> StatusCode {
+    let (status, _) = app
+        .request(Method::POST, uri, Some(json!({ "vectors": vectors })))
+        .await;
+    status
+}
+
+#[tokio::test]
+async fn pending_vector_is_stored_then_upgraded() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("full", vec![1.0, 0.0], Some(json!({ "stage": "done" })))])
+        .await;
+    let status = upsert_json(
+        &app,
+        "/collections/docs/vectors/upsert",
+        json!([{ "id": "doc", "metadata": { "stage": "raw" } }]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Readable by id and countable by filter, but not searchable.
+    let (status, body) = app
+        .request(Method::GET, "/collections/docs/vectors/doc", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pending"], true);
+    assert_eq!(body["values"], json!([]));
+    let (_, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/count",
+            Some(json!({ "filter": { "stage": "raw" } })),
+        )
+        .await;
+    assert_eq!(body["count"], 1);
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 5 }))
+        .await;
+    assert_eq!(match_ids(&body), ["full"]);
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 2);
+    assert_eq!(stats["pending_vectors"], 1);
+
+    // Survives a restart from the WAL.
+    let app = app.restart();
+    let (_, body) = app
+        .request(Method::GET, "/collections/docs/vectors/doc", None)
+        .await;
+    assert_eq!(body["pending"], true);
+
+    app.upsert("docs", &[("doc", vec![0.0, 1.0], Some(json!({ "stage": "embedded" })))])
+        .await;
+    let (_, body) = app
+        .query("docs", json!({ "vector": [0.0, 1.0], "top_k": 1 }))
+        .await;
+    assert_eq!(match_ids(&body), ["doc"]);
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["pending_vectors"], 0);
+}
+
+#[tokio::test]
+async fn pending_state_survives_snapshot() {
+    let app = TestApp::new();
+    app.create_collection("docs", 0).await;
+    let status = upsert_json(
+        &app,
+        "/collections/docs/vectors/upsert?bulk=true",
+        json!([{ "id": "a", "values": [] }, { "id": "b", "values": [1.0, 0.0] }]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let app = app.restart();
+
+    let (_, a) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(a["pending"], true);
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["dimension"], 2);
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 5, "exact": true }))
+        .await;
+    assert_eq!(match_ids(&body), ["b"]);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("x", vec![1.0, 0.0], Some(json!({ "lang": "en" }))),
+            ("y", vec![0.0, 1.0], Some(json!({ "lang": "de" }))),
+        ],
+    )
+    .await;
+    app
+}
+
+#[tokio::test]
+async fn batch_returns_results_in_input_order() {
+    let app = seeded().await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/batch",
+            Some(json!({ "queries": [
+                { "vector": [0.0, 1.0], "top_k": 1 },
+                { "vector": [1.0, 0.0], "top_k": 1 },
+                { "vector": [1.0, 0.0], "top_k": 2, "filter": { "lang": "de" } },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 3);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(match_ids(&results[0]), ["y"]);
+    assert_eq!(match_ids(&results[1]), ["x"]);
+    assert_eq!(match_ids(&results[2]), ["y"]);
+    assert_eq!(results[0]["status"], 200);
+}
+
+#[tokio::test]
+async fn wrong_dimension_fails_only_its_item() {
+    let app = seeded().await;
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/query/batch",
+            Some(json!({ "queries": [
+                { "vector": [1.0, 0.0, 0.0], "top_k": 1 },
+                { "vector": [1.0, 0.0], "top_k": 1 },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["failed"], 1);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["status"], 400);
+    assert!(results[0]["error"].as_str().unwrap().contains("dimension"));
+    assert!(results[0].get("matches").is_none());
+    assert_eq!(match_ids(&results[1]), ["x"]);
+}
+
+#[tokio::test]
+async fn batch_on_missing_collection_is_404() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/nope/query/batch",
+            Some(json!({ "queries": [{ "vector": [1.0], "top_k": 1 }] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::{TestApp, API_KEY};
+
+#[tokio::test]
+async fn saturated_collection_rejects_while_others_serve() {
+    let app = TestApp::with_config(|c| c.collection_query_concurrency = Some(1));
+    for name in ["busy", "idle"] {
+        app.create_collection(name, 2).await;
+        app.upsert(name, &[("a", vec![1.0, 0.0], None)]).await;
+    }
+    let query = json!({ "vector": [1.0, 0.0], "top_k": 1 });
+
+    {
+        // Stand in for a long-running query holding busy's only slot.
+        let collections = app.state.read_collections().await;
+        let _permit = collections[API_KEY]["busy"].try_acquire_query(1).unwrap();
+
+        let (status, _) = app.query("busy", query.clone()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, _) = app.query("idle", query.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // The slot is released with the permit.
+    let (status, _) = app.query("busy", query).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, StatusCode};
+use common::TestApp;
+use serde_json::Value;
+
+/// `(event, data)` pairs of an SSE body.
+fn events(body: &[u8]) -> Vec<(String, Value)> {
+    String::from_utf8_lossy(body)
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let field = |name: &str| {
+                block
+                    .lines()
+                    .find_map(|l| l.strip_prefix(name))
+                    .unwrap()
+                    .trim()
+                    .to_string()
+            };
+            (field("event:"), serde_json::from_str(&field("data:")).unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn matches_stream_as_events_then_the_stream_ends() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], None),
+            ("b", vec![0.7, 0.7], None),
+            ("c", vec![0.0, 1.0], None),
+        ],
+    )
+    .await;
+
+    let req = app
+        .builder(Method::GET, "/collections/docs/query/sse?vector=1,0.1&top_k=2")
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers, body) = app.send(req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "text/event-stream");
+
+    let events = events(&body);
+    let names: Vec<&str> = events.iter().map(|(e, _)| e.as_str()).collect();
+    assert_eq!(names, ["match", "match", "done"]);
+    assert_eq!(events[0].1["id"], "a");
+    assert_eq!(events[1].1["id"], "b");
+    assert_eq!(events[2].1["matches"], 2);
+}
+
+#[tokio::test]
+async fn bad_parameters_fail_before_the_stream_starts() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    for (uri, expected) in [
+        ("/collections/docs/query/sse?vector=1,x&top_k=2", StatusCode::BAD_REQUEST),
+        ("/collections/nope/query/sse?vector=1,0&top_k=2", StatusCode::NOT_FOUND),
+    ] {
+        let req = app.builder(Method::GET, uri).body(Body::empty()).unwrap();
+        assert_eq!(app.send(req).await.0, expected, "{}", uri);
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+fn app() -> TestApp {
+    TestApp::with_config(|c| {
+        c.max_collections_per_tenant = Some(2);
+        c.max_vectors_per_collection = Some(3);
+    })
+}
+
+async fn upsert(app: &TestApp, uri: &str, ids: &[&str]) -> (StatusCode, Value) {
+    let vectors: Vec<_> = ids
+        .iter()
+        .map(|id| json!({ "id": id, "values": [1.0, 0.0] }))
+        .collect();
+    app.request(Method::POST, uri, Some(json!({ "vectors": vectors })))
+        .await
+}
+
+#[tokio::test]
+async fn collections_per_tenant_are_limited() {
+    let app = app();
+    assert_eq!(app.create_collection("a", 2).await.0, StatusCode::OK);
+    assert_eq!(app.create_collection("b", 2).await.0, StatusCode::OK);
+    let (status, body) = app.create_collection("c", 2).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(body["error"]["message"].as_str().unwrap().contains("limit of 2 collections"));
+
+    let (_, stats) = app.request(Method::GET, "/collections/a/stats", None).await;
+    assert_eq!(stats["tenant_collections"], 2);
+    assert_eq!(stats["max_collections"], 2);
+}
+
+#[tokio::test]
+async fn vectors_per_collection_are_limited_per_batch() {
+    let app = app();
+    app.create_collection("docs", 2).await;
+    let single = "/collections/docs/vectors/upsert";
+    let bulk = "/collections/docs/vectors/upsert?bulk=true";
+
+    // A bulk batch that would overshoot is refused as a whole.
+    let (status, _) = upsert(&app, bulk, &["a", "b", "c", "d"]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(upsert(&app, bulk, &["a", "b"]).await.0, StatusCode::OK);
+
+    // Per-vector upserts fill up to the limit; overwrites don't count.
+    let (status, body) = upsert(&app, single, &["a", "c", "d"]).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["results"][2]["status"], 403);
+    assert_eq!(upsert(&app, bulk, &["a", "b", "c"]).await.0, StatusCode::OK);
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 3);
+    assert_eq!(stats["max_vectors"], 3);
+}
+
+async fn usage(app: &TestApp, method: Method, uri: &str, body: Option<Value>) -> HeaderMap {
+    let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+    let req = app
+        .builder(method, uri)
+        .header("content-type", "application/json")
+        .body(body)
+        .unwrap();
+    app.send(req).await.1
+}
+
+#[tokio::test]
+async fn responses_report_quota_usage() {
+    let app = app();
+    let headers = usage(&app, Method::POST, "/collections", Some(json!({ "name": "a", "dimension": 2 }))).await;
+    assert_eq!(headers["x-collections-used"], "1");
+    assert_eq!(headers["x-collections-limit"], "2");
+    let headers = usage(&app, Method::GET, "/collections", None).await;
+    assert_eq!(headers["x-collections-used"], "1");
+
+    let vectors = json!({ "vectors": [{ "id": "x", "values": [1.0, 0.0] }] });
+    let headers = usage(&app, Method::POST, "/collections/a/vectors/upsert", Some(vectors)).await;
+    assert_eq!(headers["x-vectors-used"], "1");
+    assert_eq!(headers["x-vectors-limit"], "3");
+    let vectors = json!({ "vectors": [{ "id": "y", "values": [0.0, 1.0] }] });
+    let uri = "/collections/a/vectors/upsert?bulk=true";
+    let headers = usage(&app, Method::POST, uri, Some(vectors)).await;
+    assert_eq!(headers["x-vectors-used"], "2");
+
+    let headers = usage(&app, Method::DELETE, "/collections/a", None).await;
+    assert_eq!(headers["x-collections-used"], "0");
+}
+
+#[tokio::test]
+async fn no_quota_headers_without_limits() {
+    let app = TestApp::new();
+    let headers = usage(&app, Method::POST, "/collections", Some(json!({ "name": "a", "dimension": 2 }))).await;
+    assert!(!headers.contains_key("x-collections-used"));
+    let vectors = json!({ "vectors": [{ "id": "x", "values": [1.0, 0.0] }] });
+    let headers = usage(&app, Method::POST, "/collections/a/vectors/upsert", Some(vectors)).await;
+    assert!(!headers.contains_key("x-vectors-used"));
+    let headers = usage(&app, Method::GET, "/collections", None).await;
+    assert!(!headers.contains_key("x-collections-limit"));
+}
+
+#[tokio::test]
+async fn replay_ignores_limits() {
+    let app = TestApp::new();
+    app.create_collection("a", 2).await;
+    app.create_collection("b", 2).await;
+    upsert(&app, "/collections/a/vectors/upsert", &["x", "y"]).await;
+
+    let app = app.restart_with(|c| {
+        c.max_collections_per_tenant = Some(1);
+        c.max_vectors_per_collection = Some(1);
+    });
+    let (_, stats) = app.request(Method::GET, "/collections/a/stats", None).await;
+    assert_eq!(stats["vectors"], 2);
+    assert_eq!(stats["tenant_collections"], 2);
+}
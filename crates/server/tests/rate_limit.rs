@@ -0,0 +1,91 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, StatusCode};
+use common::{TestApp, OTHER_API_KEY};
+
+#[tokio::test]
+async fn keys_over_the_limit_get_429() {
+    let app = TestApp::with_config(|c| {
+        c.rate_limit_rps = Some(1.0);
+        c.rate_limit_burst = Some(3.0);
+    });
+
+    let mut statuses = Vec::new();
+    for _ in 0..6 {
+        let req = app.builder(Method::GET, "/collections").body(Body::empty()).unwrap();
+        let (status, headers, _) = app.send(req).await;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            assert_eq!(headers[header::RETRY_AFTER], "1");
+        }
+        statuses.push(status);
+    }
+    assert_eq!(&statuses[..3], [StatusCode::OK; 3]);
+    assert_eq!(&statuses[3..], [StatusCode::TOO_MANY_REQUESTS; 3]);
+
+    // Other keys have their own bucket, and health isn't limited.
+    let (status, _) = app
+        .request_with_key(Method::GET, "/collections", None, Some(OTHER_API_KEY))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(app.request(Method::GET, "/health", None).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn responses_carry_the_remaining_budget() {
+    let app = TestApp::with_config(|c| {
+        c.rate_limit_rps = Some(0.01);
+        c.rate_limit_burst = Some(3.0);
+    });
+
+    let mut remaining = Vec::new();
+    for _ in 0..4 {
+        let req = app.builder(Method::GET, "/collections").body(Body::empty()).unwrap();
+        let (_, headers, _) = app.send(req).await;
+        assert_eq!(headers["x-ratelimit-limit"], "3");
+        remaining.push(headers["x-ratelimit-remaining"].to_str().unwrap().to_string());
+    }
+    assert_eq!(remaining, ["2", "1", "0", "0"]);
+
+    // Unknown keys have no bucket to report.
+    let (status, headers, _) = app
+        .send(
+            axum::http::Request::builder()
+                .uri("/collections")
+                .header("x-api-key", "nope")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(!headers.contains_key("x-ratelimit-remaining"));
+}
+
+#[tokio::test]
+async fn no_rate_limit_headers_when_disabled() {
+    let app = TestApp::new();
+    let req = app.builder(Method::GET, "/collections").body(Body::empty()).unwrap();
+    let (status, headers, _) = app.send(req).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!headers.contains_key("x-ratelimit-limit"));
+    assert!(!headers.contains_key("x-ratelimit-remaining"));
+}
+
+#[tokio::test]
+async fn health_and_metrics_carry_no_rate_limit_headers() {
+    let app = TestApp::with_config(|c| {
+        c.rate_limit_rps = Some(1.0);
+        c.rate_limit_burst = Some(3.0);
+    });
+    // The key has a bucket to report by now.
+    let req = app.builder(Method::GET, "/collections").body(Body::empty()).unwrap();
+    assert!(app.send(req).await.1.contains_key("x-ratelimit-remaining"));
+
+    for uri in ["/health", "/metrics"] {
+        let req = app.builder(Method::GET, uri).body(Body::empty()).unwrap();
+        let (status, headers, _) = app.send(req).await;
+        assert_eq!(status, StatusCode::OK, "{uri}");
+        assert!(!headers.contains_key("x-ratelimit-limit"), "{uri}");
+        assert!(!headers.contains_key("x-ratelimit-remaining"), "{uri}");
+    }
+}
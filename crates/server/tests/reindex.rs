@@ -0,0 +1,91 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+fn vector(i: usize) -> Vec<f32> {
+    let angle = i as f32 * 0.01;
+    vec![angle.cos(), angle.sin(), 1.0]
+}
+
+/// Near, but off, the circle of `vector`: no written vector aliases a
+/// seeded one (angles 2π apart), so the nearest neighbour is unambiguous.
+fn new_vector(i: usize) -> Vec<f32> {
+    let mut v = vector(i);
+    v[2] = 0.5;
+    v
+}
+
+#[tokio::test]
+async fn reindex_keeps_writes_made_during_the_rebuild() {
+    let app = TestApp::new();
+    app.create_collection("docs", 3).await;
+    let ids: Vec<String> = (0..2000).map(|i| format!("v{}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), vector(i), None))
+        .collect();
+    app.upsert("docs", &batch).await;
+
+    let reindex = app.request(Method::POST, "/collections/docs/reindex", None);
+    let writes = async {
+        for i in 0..50 {
+            let id = format!("new{}", i);
+            app.upsert("docs", &[(id.as_str(), new_vector(i), None)]).await;
+            app.request(Method::DELETE, &format!("/collections/docs/vectors/v{}", i), None)
+                .await;
+            tokio::task::yield_now().await;
+        }
+    };
+    let ((status, body), ()) = tokio::join!(reindex, writes);
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["vectors"].as_u64().unwrap() >= 1950);
+
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 2000);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": new_vector(49), "top_k": 1 }))
+        .await;
+    assert_eq!(match_ids(&body), ["new49"]);
+    let (_, body) = app
+        .query("docs", json!({ "vector": vector(0), "top_k": 1, "exact": true }))
+        .await;
+    assert_ne!(match_ids(&body), ["v0"]);
+}
+
+#[tokio::test]
+async fn reindex_of_missing_collection_is_404() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request(Method::POST, "/collections/nope/reindex", None)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn index_work_runs_on_a_single_thread_pool() {
+    let app = TestApp::with_config(|c| {
+        c.index_threads = 1;
+        c.parallel_insert = true;
+        c.parallel_insert_min_batch = 1;
+    });
+    app.create_collection("docs", 3).await;
+    let body = json!({
+        "vectors": (0..100)
+            .map(|i| json!({ "id": format!("v{}", i), "values": vector(i) }))
+            .collect::<Vec<_>>()
+    });
+    let (status, _) = app
+        .request(Method::POST, "/collections/docs/vectors/upsert?bulk=true", Some(body))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .request(Method::POST, "/collections/docs/reindex", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["vectors"], 100);
+}
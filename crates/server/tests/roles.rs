@@ -0,0 +1,76 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp, READ_ONLY_API_KEY};
+use openvdb_server::auth::Role;
+use openvdb_server::index::InMemoryIndex;
+use openvdb_server::state::parse_api_keys;
+use serde_json::{json, Value};
+
+async fn as_reader(app: &TestApp, method: Method, uri: &str, body: Option<Value>) -> StatusCode {
+    app.request_with_key(method, uri, body, Some(READ_ONLY_API_KEY))
+        .await
+        .0
+}
+
+#[test]
+fn keys_parse_with_optional_roles() {
+    let keys = parse_api_keys("plain, ro-key:read_only,boss:admin , bad:owner");
+    assert_eq!(keys.len(), 3);
+    assert_eq!(keys["plain"], Role::ReadWrite);
+    assert_eq!(keys["ro-key"], Role::ReadOnly);
+    assert_eq!(keys["boss"], Role::Admin);
+}
+
+#[tokio::test]
+async fn read_only_keys_cannot_write() {
+    let app = TestApp::new();
+    let writes = [
+        (Method::POST, "/collections", json!({ "name": "docs", "dimension": 2 })),
+        (
+            Method::POST,
+            "/collections/docs/vectors/upsert",
+            json!({ "vectors": [{ "id": "a", "values": [1.0, 0.0] }] }),
+        ),
+        (Method::POST, "/collections/docs/vectors/delete", json!({ "ids": ["a"] })),
+    ];
+    for (method, uri, body) in writes {
+        assert_eq!(as_reader(&app, method, uri, Some(body)).await, StatusCode::FORBIDDEN, "{}", uri);
+    }
+    for uri in ["/collections/docs", "/collections/docs/vectors/a"] {
+        assert_eq!(as_reader(&app, Method::DELETE, uri, None).await, StatusCode::FORBIDDEN);
+    }
+}
+
+#[tokio::test]
+async fn read_only_keys_can_read() {
+    let app = TestApp::new();
+    {
+        let mut index = InMemoryIndex::new(2);
+        index.upsert("a".into(), vec![1.0, 0.0], None).unwrap();
+        let mut collections = app.state.collections.write().await;
+        collections
+            .entry(READ_ONLY_API_KEY.to_string())
+            .or_default()
+            .insert("docs".into(), index);
+    }
+
+    let (status, body) = app
+        .request_with_key(
+            Method::POST,
+            "/collections/docs/query",
+            Some(json!({ "vector": [1.0, 0.0], "top_k": 1 })),
+            Some(READ_ONLY_API_KEY),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a"]);
+    for uri in [
+        "/collections",
+        "/collections/docs",
+        "/collections/docs/stats",
+        "/collections/docs/vectors/a",
+    ] {
+        assert_eq!(as_reader(&app, Method::GET, uri, None).await, StatusCode::OK, "{}", uri);
+    }
+}
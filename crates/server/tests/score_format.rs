@@ -0,0 +1,20 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn scores_are_rounded_to_configured_decimals() {
+    let app = TestApp::with_config(|c| c.score_decimals = Some(2));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![0.8, 0.6], None)]).await;
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.1], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    // cos = 0.86 / sqrt(1.01) = 0.85574...
+    assert_eq!(body["matches"][0]["score"].to_string(), "0.86");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let ids: Vec<String> = (0..25).map(|i| format!("v{:02}", i)).collect();
+    let batch: Vec<_> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), vec![1.0, i as f32], Some(json!({ "n": i }))))
+        .collect();
+    app.upsert("docs", &batch).await;
+    app
+}
+
+fn ids(page: &Value) -> Vec<String> {
+    page["vectors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn scroll_pages_through_every_vector_in_id_order() {
+    let app = seeded().await;
+    let mut seen = Vec::new();
+    let mut uri = "/collections/docs/vectors?limit=10".to_string();
+    loop {
+        let (status, page) = app.request(Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(page["vectors"][0].get("values").is_none());
+        seen.extend(ids(&page));
+        match page["next_cursor"].as_str() {
+            Some(cursor) => uri = format!("/collections/docs/vectors?limit=10&cursor={}", cursor),
+            None => break,
+        }
+    }
+    let expected: Vec<String> = (0..25).map(|i| format!("v{:02}", i)).collect();
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn scroll_tolerates_deletes_between_pages() {
+    let app = seeded().await;
+    let (_, first) = app
+        .request(Method::GET, "/collections/docs/vectors?limit=5", None)
+        .await;
+    assert_eq!(ids(&first).last().unwrap(), "v04");
+
+    // Delete the cursor's own id and the next one.
+    for id in ["v04", "v05"] {
+        let uri = format!("/collections/docs/vectors/{}", id);
+        app.request(Method::DELETE, &uri, None).await;
+    }
+    let uri = format!(
+        "/collections/docs/vectors?limit=2&include_values=true&cursor={}",
+        first["next_cursor"].as_str().unwrap()
+    );
+    let (_, second) = app.request(Method::GET, &uri, None).await;
+    assert_eq!(ids(&second), ["v06", "v07"]);
+    assert_eq!(second["vectors"][0]["metadata"], json!({ "n": 6 }));
+    assert_eq!(second["vectors"][0]["values"], json!([1.0, 6.0]));
+}
+
+#[tokio::test]
+async fn scroll_rejects_bad_cursor() {
+    let app = seeded().await;
+    let (status, _) = app
+        .request(Method::GET, "/collections/docs/vectors?cursor=zz", None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use common::TestApp;
+use serde_json::json;
+
+/// Collects everything the subscriber writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn slow_queries_are_logged_without_their_vector() {
+    let logs = Captured::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    // `#[tokio::test]` runs on one thread, so this covers the handlers.
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app = TestApp::with_config(|c| c.slow_query_ms = Some(0));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![0.123456, 0.654321], None)]).await;
+    app.query(
+        "docs",
+        json!({ "vector": [0.123456, 0.654321], "top_k": 1, "ef_search": 32, "filter": {} }),
+    )
+    .await;
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .find(|l| l.contains("slow query"))
+        .expect("no slow query logged");
+    assert!(line.contains("collection 'docs'"), "{}", line);
+    assert!(line.contains("top_k 1, ef_search Some(32), filter true"), "{}", line);
+    assert!(!line.contains("0.123456"), "{}", line);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{match_ids, TestApp};
+
+#[tokio::test]
+async fn health_is_unauthenticated() {
+    let app = TestApp::new();
+    let (status, body) = app.request_with_key(Method::GET, "/health", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+}
+
+#[tokio::test]
+async fn rejects_missing_and_unknown_api_keys() {
+    let app = TestApp::new();
+    let (status, _) = app.request_with_key(Method::GET, "/collections", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app
+        .request_with_key(Method::GET, "/collections", None, Some("nope"))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn collection_lifecycle() {
+    let app = TestApp::new();
+
+    let (status, body) = app.create_collection("docs", 3).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dimension"], 3);
+
+    let (status, _) = app.create_collection("docs", 3).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = app.request(Method::GET, "/collections", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["collections"][0]["name"], "docs");
+
+    let (status, body) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["vectors"], 0);
+
+    let (status, _) = app.request(Method::DELETE, "/collections/docs", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn upsert_query_and_delete() {
+    let app = TestApp::new();
+    app.create_collection("docs", 3).await;
+
+    let (status, body) = app
+        .upsert(
+            "docs",
+            &[
+                ("a", vec![1.0, 0.0, 0.0], Some(json!({ "lang": "en" }))),
+                ("b", vec![0.0, 1.0, 0.0], Some(json!({ "lang": "fr" }))),
+                ("c", vec![0.9, 0.1, 0.0], Some(json!({ "lang": "en" }))),
+            ],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["upserted"], 3);
+
+    // HNSW is approximate and can miss points even in tiny graphs, so these
+    // assertions only check properties that hold for any recall.
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 0.0], "top_k": 2 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let ids = match_ids(&body);
+    assert!(ids.len() <= 2);
+    assert_eq!(ids.first().map(String::as_str), Some("a"));
+
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [0.0, 1.0, 0.0], "top_k": 3, "filter": { "lang": "en" } }),
+        )
+        .await;
+    assert!(match_ids(&body).iter().all(|id| id == "a" || id == "c"));
+
+    let (status, body) = app
+        .request(Method::DELETE, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["deleted"], true);
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0, 0.0], "top_k": 3 }))
+        .await;
+    assert!(!match_ids(&body).contains(&"a".to_string()));
+}
+
+#[tokio::test]
+async fn bulk_upsert_is_all_or_nothing() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [
+                { "id": "a", "values": [1.0, 0.0] },
+                { "id": "b", "values": [1.0] },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [
+                { "id": "a", "values": [1.0, 0.0] },
+                { "id": "b", "values": [0.0, 1.0] },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["upserted"], 2);
+}
+
+#[tokio::test]
+async fn state_survives_restart_via_wal_and_snapshot() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+
+    let app = app.restart();
+    let (_, body) = app.request(Method::GET, "/collections/docs", None).await;
+    assert_eq!(body["vectors"], 2);
+}
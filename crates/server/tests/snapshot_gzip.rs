@@ -0,0 +1,62 @@
+mod common;
+
+use axum::http::Method;
+use common::TestApp;
+use serde_json::json;
+
+async fn seeded(gzip_level: Option<u32>) -> TestApp {
+    let app = TestApp::with_config(|c| c.snapshot_gzip_level = gzip_level);
+    app.create_collection("docs", 3).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![0.25, -1.5, 3.0], Some(json!({ "lang": "en" }))),
+            ("b", vec![1e-7, 2.0, -0.125], None),
+        ],
+    )
+    .await;
+    app
+}
+
+#[tokio::test]
+async fn compressed_snapshots_reload_identically() {
+    let app = seeded(Some(6)).await;
+    let (_, before) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    app.state.write_snapshot().await.unwrap();
+
+    let dir = app.dir.path();
+    let gz = std::fs::read(dir.join("snapshot.json.gz")).unwrap();
+    assert_eq!(gz[..2], [0x1f, 0x8b]);
+    assert!(!dir.join("snapshot.json").exists());
+    assert!(!dir.join("snapshot.json.tmp").exists());
+    assert_eq!(std::fs::metadata(dir.join("wal.jsonl")).unwrap().len(), 0);
+
+    let app = app.restart();
+    let (_, after) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(after, before);
+    let (_, b) = app.request(Method::GET, "/collections/docs/vectors/b", None).await;
+    assert_eq!(b["values"], json!([1e-7, 2.0, -0.125]));
+}
+
+#[tokio::test]
+async fn switching_formats_leaves_one_snapshot() {
+    let app = seeded(None).await;
+    app.state.write_snapshot().await.unwrap();
+    let dir = app.dir.path().to_path_buf();
+    assert!(dir.join("snapshot.json").exists());
+
+    // A plain snapshot still loads once compression is turned on.
+    let app = app.restart_with(|c| c.snapshot_gzip_level = Some(1));
+    let (_, body) = app
+        .query("docs", json!({ "vector": [0.25, -1.5, 3.0], "top_k": 1 }))
+        .await;
+    assert_eq!(common::match_ids(&body), ["a"]);
+    app.state.write_snapshot().await.unwrap();
+    assert!(dir.join("snapshot.json.gz").exists() && !dir.join("snapshot.json").exists());
+
+    let app = app.restart_with(|c| c.snapshot_gzip_level = None);
+    app.state.write_snapshot().await.unwrap();
+    assert!(dir.join("snapshot.json").exists() && !dir.join("snapshot.json.gz").exists());
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 2);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::Method;
+use serde_json::{json, Value};
+
+use common::{TestApp, OTHER_API_KEY};
+use openvdb_server::state::spawn_snapshotter;
+use openvdb_server::storage::SnapshotSummary;
+
+fn wal_len(app: &TestApp) -> u64 {
+    std::fs::metadata(app.dir.path().join("wal.jsonl")).map_or(0, |m| m.len())
+}
+
+#[tokio::test]
+async fn periodic_snapshot_truncates_the_wal() {
+    let app = TestApp::with_config(|c| c.first_snapshot_after = 0);
+    spawn_snapshotter(app.state.clone(), Duration::from_millis(20));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+
+    let snapshot = app.dir.path().join("snapshot.json");
+    for _ in 0..100 {
+        if snapshot.exists() && wal_len(&app) == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(snapshot.exists(), "no periodic snapshot");
+    assert_eq!(wal_len(&app), 0);
+
+    let app = app.restart();
+    let (_, body) = app
+        .query("docs", serde_json::json!({ "vector": [1.0, 0.0], "top_k": 2 }))
+        .await;
+    assert_eq!(common::match_ids(&body), ["a", "b"]);
+}
+
+#[tokio::test]
+async fn overlapping_snapshots_never_race() {
+    let app = TestApp::with_config(|c| c.first_snapshot_after = 0);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let runs: Vec<_> = (0..8)
+        .map(|_| {
+            let state = app.state.clone();
+            tokio::spawn(async move { state.write_snapshot().await })
+        })
+        .collect();
+    for run in runs {
+        let summary = run.await.unwrap().expect("snapshot failed");
+        assert_eq!(
+            summary,
+            SnapshotSummary {
+                tenants: 1,
+                collections: 1,
+                vectors: 1,
+                history: None,
+            }
+        );
+    }
+    assert!(!app.dir.path().join("snapshot.json.tmp").exists());
+
+    // A periodic cycle that finds one running skips instead of waiting.
+    let _running = app.state.lock_snapshots().await;
+    assert!(app.state.try_write_snapshot().await.is_none());
+}
+
+#[tokio::test]
+async fn dry_run_measures_without_writing() {
+    let app = TestApp::with_config(|c| c.first_snapshot_after = 0);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+    let wal_before = wal_len(&app);
+
+    let (status, body) = app
+        .request(axum::http::Method::POST, "/admin/snapshot?dry_run=true", None)
+        .await;
+    assert_eq!(status, axum::http::StatusCode::OK);
+    let estimate = &body["estimate"];
+    assert_eq!(estimate["tenants"], 1);
+    assert_eq!(estimate["collections"], 1);
+    assert_eq!(estimate["vectors"], 2);
+    assert!(!app.dir.path().join("snapshot.json").exists());
+    assert_eq!(wal_len(&app), wal_before);
+
+    // The estimate is the exact size of the snapshot written next.
+    app.state.write_snapshot().await.expect("snapshot failed");
+    let written = std::fs::metadata(app.dir.path().join("snapshot.json")).unwrap().len();
+    assert_eq!(estimate["bytes"], written);
+}
+
+#[tokio::test]
+async fn dry_run_measures_evicted_tenants_on_disk() {
+    let app = TestApp::with_config(|c| c.first_snapshot_after = 0);
+    app.create_collection("docs", 2).await;
+    let other = |method: Method, uri: &'static str, body: Option<Value>| {
+        app.request_with_key(method, uri, body, Some(OTHER_API_KEY))
+    };
+    let upsert = |vectors: Value| {
+        other(Method::POST, "/collections/other/vectors/upsert", Some(json!({ "vectors": vectors })))
+    };
+    other(Method::POST, "/collections", Some(json!({ "name": "other", "dimension": 2 }))).await;
+    other(Method::POST, "/collections", Some(json!({ "name": "empty", "dimension": 3 }))).await;
+    upsert(json!([{ "id": "a", "values": [1.0, 0.0], "metadata": { "n": 1 } }])).await;
+    app.state.write_snapshot().await.expect("snapshot failed");
+
+    // WAL on top of the snapshot: new, overwritten, updated and deleted vectors.
+    upsert(json!([{ "id": "b", "values": [0.0, 1.0] }, { "id": "c", "values": [0.5, 0.5] }]))
+        .await;
+    upsert(json!([{ "id": "b", "values": [0.1, 0.9], "metadata": { "tag": "x" } }])).await;
+    other(Method::PATCH, "/collections/other/vectors/a", Some(json!({ "metadata": { "n": 22 } })))
+        .await;
+    other(Method::DELETE, "/collections/other/vectors/c", None).await;
+    app.state.evict_idle_tenants(Duration::ZERO).await;
+
+    let (_, body) = app.request(Method::POST, "/admin/snapshot?dry_run=true", None).await;
+    let estimate = &body["estimate"];
+    // The request loads its own tenant, but not the other one.
+    assert!(!app.state.is_resident(OTHER_API_KEY), "dry run reloaded a tenant");
+    assert_eq!(estimate["tenants"], 2);
+    assert_eq!(estimate["collections"], 3);
+    assert_eq!(estimate["vectors"], 2);
+
+    app.state.write_snapshot().await.expect("snapshot failed");
+    let written = std::fs::metadata(app.dir.path().join("snapshot.json")).unwrap().len();
+    assert_eq!(estimate["bytes"], written);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+/// Identical vectors, so every score ties and `sort_by` decides the order.
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert(
+        "docs",
+        &[
+            ("a", vec![1.0, 0.0], Some(json!({ "ts": 10, "name": "delta" }))),
+            ("b", vec![1.0, 0.0], Some(json!({ "ts": 30, "name": "alpha" }))),
+            ("c", vec![1.0, 0.0], None),
+            ("d", vec![1.0, 0.0], Some(json!({ "ts": 20, "name": "charlie" }))),
+            ("e", vec![1.0, 0.0], Some(json!({ "ts": true, "name": 5 }))),
+        ],
+    )
+    .await;
+    app
+}
+
+#[tokio::test]
+async fn ties_sort_by_numeric_field_with_missing_last() {
+    let app = seeded().await;
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 5,
+                "sort_by": { "field": "ts", "order": "desc" }
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["b", "d", "a", "c", "e"]);
+}
+
+#[tokio::test]
+async fn ties_sort_by_string_field() {
+    let app = seeded().await;
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 5, "sort_by": { "field": "name" } }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    // Numbers sort before strings; "c" has no metadata at all.
+    assert_eq!(match_ids(&body), ["e", "b", "d", "a", "c"]);
+}
+
+#[tokio::test]
+async fn score_still_comes_first() {
+    let app = seeded().await;
+    app.upsert("docs", &[("z", vec![0.0, 1.0], Some(json!({ "ts": 99 })))])
+        .await;
+    let (_, body) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 6,
+                "sort_by": { "field": "ts", "order": "desc" }
+            }),
+        )
+        .await;
+    assert_eq!(match_ids(&body).last().unwrap(), "z");
+}
+
+#[tokio::test]
+async fn sort_by_rejects_cursor() {
+    let app = seeded().await;
+    let (_, first) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 2 }))
+        .await;
+    let (status, _) = app
+        .query(
+            "docs",
+            json!({
+                "vector": [1.0, 0.0],
+                "top_k": 2,
+                "cursor": first["cursor"],
+                "sort_by": { "field": "ts" }
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
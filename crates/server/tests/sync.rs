@@ -0,0 +1,33 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use openvdb_server::config::WalSync;
+use serde_json::json;
+
+#[tokio::test]
+async fn admin_sync_makes_unsynced_writes_durable() {
+    let app = TestApp::with_config(|c| c.wal_sync = WalSync::Os);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, body) = app.request(Method::POST, "/admin/sync", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["success"], true);
+
+    let app = app.restart();
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&body), ["a"]);
+}
+
+#[tokio::test]
+async fn admin_sync_requires_api_key() {
+    let app = TestApp::new();
+    let (status, _) = app
+        .request_with_key(Method::POST, "/admin/sync", None, None)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
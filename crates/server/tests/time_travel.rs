@@ -0,0 +1,56 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::{json, Value};
+
+async fn snapshot(app: &TestApp) -> u64 {
+    let (status, body) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    body["history"].as_u64().expect("history timestamp")
+}
+
+async fn query_at(app: &TestApp, stamp: u64) -> (StatusCode, Value) {
+    let uri = format!("/collections/docs/query?snapshot={}", stamp);
+    app.request(Method::POST, &uri, Some(json!({ "vector": [0.0, 1.0], "top_k": 5 })))
+        .await
+}
+
+#[tokio::test]
+async fn query_sees_the_collection_as_of_a_snapshot() {
+    let app = TestApp::with_config(|c| c.snapshot_history = 2);
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    let before = snapshot(&app).await;
+
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+    let (_, live) = app
+        .query("docs", json!({ "vector": [0.0, 1.0], "top_k": 5 }))
+        .await;
+    assert_eq!(match_ids(&live), ["b", "a"]);
+
+    let (status, past) = query_at(&app, before).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(match_ids(&past), ["a"]);
+
+    // The history survives a restart; only the newest two are kept.
+    let app = app.restart();
+    let (_, past) = query_at(&app, before).await;
+    assert_eq!(match_ids(&past), ["a"]);
+    let latest = snapshot(&app).await;
+    let (_, now) = query_at(&app, latest).await;
+    assert_eq!(match_ids(&now), ["b", "a"]);
+    snapshot(&app).await;
+    let (status, _) = query_at(&app, before).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn history_is_off_by_default() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (_, body) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert!(body.get("history").is_none());
+    let (status, _) = query_at(&app, 1).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn times(app: &TestApp, id: &str) -> (u64, u64) {
+    let (status, body) = app
+        .query(
+            "docs",
+            json!({ "vector": [1.0, 0.0], "top_k": 10, "include_timestamps": true }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let m = body["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["id"] == id)
+        .unwrap();
+    (m["created_at"].as_u64().unwrap(), m["updated_at"].as_u64().unwrap())
+}
+
+#[tokio::test]
+async fn created_at_is_stable_across_upserts() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    let (created, updated) = times(&app, "a").await;
+    assert_eq!(created, updated);
+
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    app.upsert("docs", &[("a", vec![0.9, 0.1], Some(json!({ "v": 2 })))]).await;
+    let (created_again, updated_again) = times(&app, "a").await;
+    assert_eq!(created_again, created);
+    assert!(updated_again > updated);
+
+    // Replayed from the WAL...
+    let app = app.restart();
+    assert_eq!(times(&app, "a").await, (created, updated_again));
+
+    // ...and loaded from a snapshot.
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let app = app.restart();
+    assert_eq!(times(&app, "a").await, (created, updated_again));
+}
+
+#[tokio::test]
+async fn timestamps_are_omitted_unless_requested() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(body["matches"][0].get("created_at"), None::<&Value>);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn patch_replaces_metadata_and_survives_restart() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], Some(json!({ "lang": "en" })))])
+        .await;
+
+    let (status, body) = app
+        .request(
+            Method::PATCH,
+            "/collections/docs/vectors/a",
+            Some(json!({ "metadata": { "lang": "de", "reviewed": true } })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "id": "a", "version": 2 }));
+
+    // Values and searchability are untouched; filters see the new metadata.
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 1, "filter": { "lang": "de" } }))
+        .await;
+    assert_eq!(common::match_ids(&body), ["a"]);
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["index_nodes"], 1);
+
+    let app = app.restart();
+    let (_, body) = app
+        .request(Method::GET, "/collections/docs/vectors/a", None)
+        .await;
+    assert_eq!(body["values"], json!([1.0, 0.0]));
+    assert_eq!(body["metadata"], json!({ "lang": "de", "reviewed": true }));
+    assert_eq!(body["version"], 2);
+}
+
+#[tokio::test]
+async fn patch_of_missing_vector_is_404() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (status, _) = app
+        .request(
+            Method::PATCH,
+            "/collections/docs/vectors/nope",
+            Some(json!({ "metadata": { "lang": "de" } })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
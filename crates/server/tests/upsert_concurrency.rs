@@ -0,0 +1,36 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, API_KEY, OTHER_API_KEY};
+use serde_json::json;
+
+#[tokio::test]
+async fn a_busy_tenant_does_not_block_others() {
+    let app = TestApp::with_config(|c| c.tenant_upsert_concurrency = Some(1));
+    app.create_collection("docs", 2).await;
+    app.request_with_key(
+        Method::POST,
+        "/collections",
+        Some(json!({ "name": "docs", "dimension": 2 })),
+        Some(OTHER_API_KEY),
+    )
+    .await;
+    let upsert = |key| {
+        app.request_with_key(
+            Method::POST,
+            "/collections/docs/vectors/upsert",
+            Some(json!({ "vectors": [{ "id": "a", "values": [1.0, 0.0] }] })),
+            Some(key),
+        )
+    };
+
+    // A request in flight holds the tenant's only slot.
+    let slot = app.state.try_begin_upsert(API_KEY).unwrap();
+    let (status, body) = upsert(API_KEY).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"]["code"], "too_many_requests");
+    assert_eq!(upsert(OTHER_API_KEY).await.0, StatusCode::OK);
+
+    drop(slot);
+    assert_eq!(upsert(API_KEY).await.0, StatusCode::OK);
+}
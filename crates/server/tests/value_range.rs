@@ -0,0 +1,55 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+async fn create(app: &TestApp, extra: serde_json::Value) -> StatusCode {
+    let mut body = json!({ "name": "docs", "dimension": 2, "value_range": [-1.0, 1.0] });
+    body.as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    app.request(Method::POST, "/collections", Some(body)).await.0
+}
+
+#[tokio::test]
+async fn out_of_range_values_are_rejected_with_index() {
+    let app = TestApp::new();
+    assert_eq!(create(&app, json!({})).await, StatusCode::OK);
+
+    let (status, body) = app.upsert("docs", &[("a", vec![0.5, 7.0], None)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = body["results"][0]["error"].as_str().unwrap();
+    assert!(error.contains("index 1"), "{}", error);
+
+    // The range is persisted with the collection.
+    let app = app.restart();
+    let (status, _) = app.upsert("docs", &[("a", vec![-3.0, 0.5], None)]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app.upsert("docs", &[("a", vec![-1.0, 0.5], None)]).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn out_of_range_values_can_be_clamped() {
+    let app = TestApp::new();
+    assert_eq!(create(&app, json!({ "out_of_range": "clamp" })).await, StatusCode::OK);
+
+    let (status, _) = app.upsert("docs", &[("a", vec![5.0, 1.0], None)]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Stored as [1, 1]: an exact match for the diagonal.
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 1.0], "top_k": 1, "exact": true }))
+        .await;
+    let score = body["matches"][0]["score"].as_f64().unwrap();
+    assert!((score - 1.0).abs() < 1e-5, "score {}", score);
+}
+
+#[tokio::test]
+async fn invalid_range_is_rejected() {
+    let app = TestApp::new();
+    let status = create(&app, json!({ "value_range": [1.0, -1.0] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn upsert(app: &TestApp, uri: &str, vector: Value) -> (StatusCode, Value) {
+    app.request(Method::POST, uri, Some(json!({ "vectors": [vector] })))
+        .await
+}
+
+async fn version(app: &TestApp, id: &str) -> Value {
+    let uri = format!("/collections/docs/vectors/{}", id);
+    app.request(Method::GET, &uri, None).await.1["version"].clone()
+}
+
+const UPSERT: &str = "/collections/docs/vectors/upsert";
+
+#[tokio::test]
+async fn stale_if_version_is_a_conflict() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    assert_eq!(version(&app, "a").await, 1);
+
+    let (status, _) = upsert(
+        &app,
+        UPSERT,
+        json!({ "id": "a", "values": [0.0, 1.0], "if_version": 1 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(version(&app, "a").await, 2);
+
+    // A second writer that also read version 1 loses.
+    let (status, body) = upsert(
+        &app,
+        UPSERT,
+        json!({ "id": "a", "values": [1.0, 1.0], "if_version": 1 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["results"][0]["status"], 409);
+    let (_, stored) = app.request(Method::GET, "/collections/docs/vectors/a", None).await;
+    assert_eq!(stored["values"], json!([0.0, 1.0]));
+    assert_eq!(stored["version"], 2);
+
+    // Version 0 means "create only".
+    let (status, _) = upsert(&app, UPSERT, json!({ "id": "a", "values": [1.0, 0.0], "if_version": 0 }))
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = upsert(&app, UPSERT, json!({ "id": "b", "values": [1.0, 0.0], "if_version": 0 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn bulk_upsert_conflict_rejects_the_batch() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections/docs/vectors/upsert?bulk=true",
+            Some(json!({ "vectors": [
+                { "id": "b", "values": [0.0, 1.0] },
+                { "id": "a", "values": [0.0, 1.0], "if_version": 7 },
+            ] })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = app.request(Method::GET, "/collections/docs/vectors/b", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(version(&app, "a").await, 1);
+}
+
+#[tokio::test]
+async fn versions_survive_restart_and_snapshot() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    for _ in 0..3 {
+        app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    }
+    let app = app.restart();
+    assert_eq!(version(&app, "a").await, 3);
+
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    let app = app.restart();
+    assert_eq!(version(&app, "a").await, 4);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use openvdb_server::auth::Role;
+use openvdb_server::config::Config;
+use openvdb_server::state::AppState;
+
+async fn seeded() -> TestApp {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    for (id, v) in [("a", [1.0, 0.0]), ("b", [0.0, 1.0]), ("c", [1.0, 1.0])] {
+        app.upsert("docs", &[(id, v.to_vec(), None)]).await;
+    }
+    app
+}
+
+fn load(app: &TestApp, strict: bool) -> anyhow::Result<AppState> {
+    let config = Config {
+        data_dir: app.dir.path().to_path_buf(),
+        wal_strict: strict,
+        ..Config::default()
+    };
+    AppState::load(config, HashMap::from([(common::API_KEY.to_string(), Role::ReadWrite)]))
+}
+
+async fn ids(state: &AppState) -> Vec<String> {
+    let collections = state.read_collections().await;
+    let mut ids: Vec<String> = ["a", "b", "c"]
+        .into_iter()
+        .filter(|id| collections[common::API_KEY]["docs"].contains(id))
+        .map(String::from)
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// Flip one byte inside the JSON of WAL line `line` (0-based).
+fn corrupt_line(app: &TestApp, line: usize) {
+    let path = app.dir.path().join("wal.jsonl");
+    let mut wal = fs::read(&path).unwrap();
+    let start: usize = wal
+        .split_inclusive(|&b| b == b'\n')
+        .take(line)
+        .map(|l| l.len())
+        .sum();
+    wal[start + 20] ^= 0x01;
+    fs::write(&path, wal).unwrap();
+}
+
+#[tokio::test]
+async fn strict_mode_refuses_to_boot_on_mid_file_corruption() {
+    let app = seeded().await;
+    corrupt_line(&app, 2);
+
+    let err = load(&app, true).err().expect("strict load should fail");
+    assert!(err.to_string().contains("WAL line 3"), "{}", err);
+
+    // Lenient mode skips just the damaged entry.
+    let state = load(&app, false).unwrap();
+    assert_eq!(ids(&state).await, ["a", "c"]);
+}
+
+#[tokio::test]
+async fn torn_final_line_is_tolerated_and_trimmed() {
+    let app = seeded().await;
+    let path = app.dir.path().join("wal.jsonl");
+    let mut wal = fs::read(&path).unwrap();
+    let intact = wal.len();
+    wal.extend_from_slice(br#"1234abcd {"type":"upsert_vector","ten"#);
+    fs::write(&path, wal).unwrap();
+
+    let state = load(&app, true).unwrap();
+    assert_eq!(ids(&state).await, ["a", "b", "c"]);
+    assert_eq!(fs::metadata(&path).unwrap().len() as usize, intact);
+    drop(state);
+
+    // New appends start on a fresh line.
+    let app = app.restart();
+    app.upsert("docs", &[("d", vec![0.5, 0.5], None)]).await;
+    let state = load(&app, true).unwrap();
+    assert!(state.read_collections().await[common::API_KEY]["docs"].contains("d"));
+}
+
+#[tokio::test]
+async fn lines_without_checksums_still_replay() {
+    let app = TestApp::new();
+    let line = format!(
+        "{}\n",
+        serde_json::json!({
+            "type": "create_collection",
+            "tenant": common::API_KEY,
+            "name": "legacy",
+            "dimension": 2,
+        })
+    );
+    fs::write(app.dir.path().join("wal.jsonl"), line).unwrap();
+
+    let app = app.restart();
+    let (status, _) = app
+        .request(Method::GET, "/collections/legacy/stats", None)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
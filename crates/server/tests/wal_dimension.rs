@@ -0,0 +1,58 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use common::TestApp;
+use openvdb_server::auth::Role;
+use openvdb_server::config::Config;
+use openvdb_server::state::AppState;
+use openvdb_server::storage::{encode_entry, WalEntry};
+
+fn upsert(collection: &str, id: &str, values: Vec<f32>) -> WalEntry {
+    WalEntry::UpsertVector {
+        tenant: common::API_KEY.to_string(),
+        collection: collection.to_string(),
+        id: id.to_string(),
+        values,
+        metadata: None,
+        written_at: None,
+        version: None,
+    }
+}
+
+#[tokio::test]
+async fn replay_keeps_the_declared_dimension() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+
+    let mut lines = String::new();
+    for entry in [
+        upsert("docs", "wide", vec![1.0, 0.0, 0.0]),
+        upsert("docs", "b", vec![0.0, 1.0]),
+        upsert("ghost", "c", vec![1.0, 0.0, 0.0]),
+    ] {
+        encode_entry(&entry, &mut lines).unwrap();
+    }
+    let mut wal = OpenOptions::new()
+        .append(true)
+        .open(app.dir.path().join("wal.jsonl"))
+        .unwrap();
+    wal.write_all(lines.as_bytes()).unwrap();
+
+    let config = Config {
+        data_dir: app.dir.path().to_path_buf(),
+        ..Config::default()
+    };
+    let state = AppState::load(config, HashMap::from([(common::API_KEY.to_string(), Role::ReadWrite)]))
+        .unwrap();
+    let collections = state.read_collections().await;
+    let docs = &collections[common::API_KEY]["docs"];
+    assert_eq!(docs.dimension(), 2);
+    assert!(docs.contains("a") && docs.contains("b"));
+    assert!(!docs.contains("wide"));
+    // Upserts don't conjure up collections.
+    assert!(!collections[common::API_KEY].contains_key("ghost"));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::path::Path;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use serde_json::json;
+
+fn retained_wals(data_dir: &Path) -> Vec<String> {
+    std::fs::read_dir(data_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("wal.pre-snapshot."))
+        .collect()
+}
+
+async fn snapshot(app: &TestApp) {
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn corrupt_snapshot_is_recovered_from_retained_wal() {
+    let app = TestApp::with_config(|c| c.wal_retention_secs = Some(3600));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    snapshot(&app).await;
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+    snapshot(&app).await;
+    app.upsert("docs", &[("c", vec![0.7, 0.7], None)]).await;
+
+    // Only the WAL moved aside by the latest snapshot is kept.
+    assert_eq!(retained_wals(app.dir.path()).len(), 1);
+
+    std::fs::write(app.dir.path().join("snapshot.json"), b"{\"tenants\": {").unwrap();
+    let app = app.restart();
+
+    let (status, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 3 }))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut ids = match_ids(&body);
+    ids.sort();
+    assert_eq!(ids, ["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn wal_is_truncated_without_retention() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    snapshot(&app).await;
+
+    assert!(retained_wals(app.dir.path()).is_empty());
+    let wal = std::fs::read(app.dir.path().join("wal.jsonl")).unwrap();
+    assert!(wal.is_empty());
+}
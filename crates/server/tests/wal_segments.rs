@@ -0,0 +1,54 @@
+mod common;
+
+use axum::http::Method;
+use common::TestApp;
+use serde_json::{json, Value};
+
+fn segments(app: &TestApp) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(app.dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("wal.0"))
+        .collect();
+    names.sort();
+    names
+}
+
+async fn state(app: &TestApp) -> Vec<Value> {
+    let mut vectors = Vec::new();
+    for i in 0..40 {
+        let uri = format!("/collections/docs/vectors/v{}", i);
+        vectors.push(app.request(Method::GET, &uri, None).await.1);
+    }
+    vectors
+}
+
+#[tokio::test]
+async fn replay_across_segments_reconstructs_state() {
+    let app = TestApp::with_config(|c| c.wal_segment_bytes = Some(1024));
+    app.create_collection("docs", 2).await;
+    for i in 0..40 {
+        let id = format!("v{}", i);
+        let metadata = json!({ "n": i });
+        app.upsert("docs", &[(&id, vec![i as f32, 1.0], Some(metadata))]).await;
+    }
+    // Later segments overwrite and delete what earlier ones wrote.
+    app.upsert("docs", &[("v0", vec![-1.0, -1.0], None)]).await;
+    app.request(Method::DELETE, "/collections/docs/vectors/v1", None).await;
+
+    let sealed = segments(&app);
+    assert!(sealed.len() >= 2, "{:?}", sealed);
+    assert_eq!(sealed[..2], ["wal.0000.jsonl", "wal.0001.jsonl"]);
+    let before = state(&app).await;
+
+    let app = app.restart();
+    assert_eq!(state(&app).await, before);
+    let (_, stats) = app.request(Method::GET, "/collections/docs/stats", None).await;
+    assert_eq!(stats["vectors"], 39);
+
+    // A snapshot covers every segment, so they all go.
+    app.state.write_snapshot().await.unwrap();
+    assert!(segments(&app).is_empty());
+    let app = app.restart();
+    assert_eq!(state(&app).await, before);
+}
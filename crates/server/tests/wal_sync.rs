@@ -0,0 +1,87 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use common::{match_ids, TestApp};
+use openvdb_server::config::WalSync;
+use openvdb_server::state;
+use serde_json::json;
+
+async fn ids(app: &TestApp) -> Vec<String> {
+    let (_, body) = app
+        .query("docs", json!({ "vector": [1.0, 0.0], "top_k": 10 }))
+        .await;
+    let mut ids = match_ids(&body);
+    ids.sort();
+    ids
+}
+
+#[test]
+fn policies_parse_from_their_env_names() {
+    assert_eq!("always".parse(), Ok(WalSync::Always));
+    assert_eq!("interval".parse(), Ok(WalSync::Interval));
+    assert_eq!("os".parse(), Ok(WalSync::Os));
+    assert_eq!("never".parse(), Ok(WalSync::Os));
+    assert!("sometimes".parse::<WalSync>().is_err());
+}
+
+#[tokio::test]
+async fn interval_policy_syncs_in_the_background() {
+    let app = TestApp::with_config(|c| {
+        c.wal_sync = WalSync::Interval;
+        c.wal_sync_interval_ms = 10;
+    });
+    state::spawn_wal_flusher(app.state.clone(), Duration::from_millis(10));
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None), ("b", vec![0.0, 1.0], None)])
+        .await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let app = app.restart();
+    assert_eq!(ids(&app).await, ["a", "b"]);
+}
+
+#[tokio::test]
+async fn the_open_wal_follows_snapshot_rotation() {
+    // With retention the snapshot renames the WAL away instead of
+    // truncating it; later appends must go to the new file.
+    let app = TestApp::with_config(|c| {
+        c.wal_sync = WalSync::Always;
+        c.wal_retention_secs = Some(3600);
+    });
+    app.create_collection("docs", 2).await;
+    app.upsert("docs", &[("a", vec![1.0, 0.0], None)]).await;
+    let (status, _) = app.request(Method::POST, "/admin/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    app.upsert("docs", &[("b", vec![0.0, 1.0], None)]).await;
+
+    let wal = fs::read_to_string(app.dir.path().join("wal.jsonl")).unwrap();
+    assert_eq!(wal.lines().count(), 1);
+    let app = app.restart();
+    assert_eq!(ids(&app).await, ["a", "b"]);
+}
+
+#[tokio::test]
+async fn a_batch_is_appended_in_one_write() {
+    // Sealing after every append leaves one segment per write.
+    let app = TestApp::with_config(|c| {
+        c.wal_sync = WalSync::Always;
+        c.wal_segment_bytes = Some(1);
+    });
+    app.create_collection("docs", 2).await;
+    let (status, _) = app
+        .upsert(
+            "docs",
+            &[("a", vec![1.0, 0.0], None), ("bad", vec![1.0], None), ("b", vec![0.0, 1.0], None)],
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+
+    let batch = fs::read_to_string(app.dir.path().join("wal.0001.jsonl")).unwrap();
+    assert_eq!(batch.lines().count(), 2);
+    assert!(!app.dir.path().join("wal.0002.jsonl").exists());
+    let app = app.restart();
+    assert_eq!(ids(&app).await, ["a", "b"]);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+async fn collection(app: &TestApp, policy: &str) {
+    let (status, _) = app
+        .request(
+            Method::POST,
+            "/collections",
+            Some(json!({ "name": "docs", "dimension": 2, "zero_vectors": policy })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn upsert(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    let vectors = json!([
+        { "id": "a", "values": [1.0, 0.0] },
+        { "id": "zero", "values": [0.0, 0.0] },
+    ]);
+    app.request(Method::POST, uri, Some(json!({ "vectors": vectors })))
+        .await
+}
+
+async fn vectors(app: &TestApp) -> Value {
+    app.request(Method::GET, "/collections/docs/stats", None).await.1["vectors"].clone()
+}
+
+#[tokio::test]
+async fn reject_is_the_default() {
+    let app = TestApp::new();
+    app.create_collection("docs", 2).await;
+    let (status, body) = upsert(&app, "/collections/docs/vectors/upsert").await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["failed"], 1);
+    assert!(body.get("skipped").is_none());
+}
+
+#[tokio::test]
+async fn skip_drops_zero_vectors_and_counts_them() {
+    let app = TestApp::new();
+    collection(&app, "skip").await;
+    for uri in [
+        "/collections/docs/vectors/upsert",
+        "/collections/docs/vectors/upsert?bulk=true",
+    ] {
+        let (status, body) = upsert(&app, uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(body["succeeded"], 1);
+        assert_eq!(body["skipped"], 1);
+    }
+    assert_eq!(vectors(&app).await, 1);
+
+    // Queries still reject a zero vector.
+    let (status, _) = app
+        .query("docs", json!({ "vector": [0.0, 0.0], "top_k": 1 }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn normalize_to_epsilon_survives_restart() {
+    let app = TestApp::new();
+    collection(&app, "normalize_to_epsilon").await;
+    let (status, _) = upsert(&app, "/collections/docs/vectors/upsert").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = app.restart();
+    assert_eq!(vectors(&app).await, 2);
+    let (_, body) = app
+        .request(Method::GET, "/collections/docs/vectors/zero", None)
+        .await;
+    assert!(body["values"][0].as_f64().unwrap() > 0.0);
+}
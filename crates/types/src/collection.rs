@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Per-collection settings fixed at creation time. Persisted alongside the
+/// collection in the `CreateCollection` WAL entry and in snapshots.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CollectionConfig {
+    /// Metadata filter ANDed into every query against the collection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_filter: Option<Map<String, Value>>,
+    /// How vectors are stored and compared.
+    #[serde(default)]
+    pub metric: Metric,
+    /// Allowed range for individual vector values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_range: Option<ValueRange>,
+    /// Most HNSW nodes (live or dead) the collection may hold; the server
+    /// default when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<usize>,
+    /// Whether writes may overwrite an existing id.
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// What upserts do with all-zero vectors.
+    #[serde(default)]
+    pub zero_vectors: ZeroVectors,
+    /// Reject upserts within this distance (under `metric`) of a vector
+    /// stored under another id. Off when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_threshold: Option<f32>,
+}
+
+/// Bounds every vector component must fall in, to catch outliers from
+/// broken embedding pipelines at ingest.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ValueRange {
+    pub min: f32,
+    pub max: f32,
+    #[serde(default)]
+    pub out_of_range: OutOfRange,
+}
+
+impl ValueRange {
+    pub fn new(min: f32, max: f32, out_of_range: OutOfRange) -> Result<Self, String> {
+        if !(min.is_finite() && max.is_finite() && min <= max) {
+            return Err("value_range must be [min, max] with finite min <= max".into());
+        }
+        Ok(Self {
+            min,
+            max,
+            out_of_range,
+        })
+    }
+
+    /// Reject or clamp `values` in place, per `out_of_range`.
+    pub fn apply(&self, values: &mut [f32]) -> Result<(), String> {
+        for (i, v) in values.iter_mut().enumerate() {
+            if (self.min..=self.max).contains(v) {
+                continue;
+            }
+            if self.out_of_range == OutOfRange::Clamp && !v.is_nan() {
+                *v = v.clamp(self.min, self.max);
+                continue;
+            }
+            return Err(format!(
+                "value at index {} ({}) is outside value_range [{}, {}]",
+                i, v, self.min, self.max
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Similarity used by a collection. Scores are higher-is-better for every
+/// metric: cosine similarity for the cosine metrics, the inner product for
+/// `dot`, and the negated Euclidean distance for `l2`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Cosine distance over the vectors as given.
+    #[default]
+    Cosine,
+    /// Vectors (and queries) are L2-normalized up front so the index can
+    /// compare them with a plain dot product.
+    NormalizedCosine,
+    /// Euclidean distance.
+    L2,
+    /// Inner product over the vectors as given, for embeddings trained for
+    /// maximum inner product search.
+    Dot,
+}
+
+impl Metric {
+    pub fn normalizes(self) -> bool {
+        matches!(self, Metric::NormalizedCosine)
+    }
+
+    /// True when scores are cosine similarities.
+    pub fn is_cosine(self) -> bool {
+        matches!(self, Metric::Cosine | Metric::NormalizedCosine)
+    }
+}
+
+/// The names the API uses, for settings read from the environment.
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cosine" => Ok(Metric::Cosine),
+            "normalized_cosine" => Ok(Metric::NormalizedCosine),
+            "l2" => Ok(Metric::L2),
+            "dot" => Ok(Metric::Dot),
+            other => Err(format!(
+                "unknown metric {:?}, expected cosine, normalized_cosine, l2 or dot",
+                other
+            )),
+        }
+    }
+}
+
+/// What to do with a value outside `ValueRange`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutOfRange {
+    /// Reject the vector, naming the offending index.
+    #[default]
+    Reject,
+    /// Clamp the value into range and accept the vector.
+    Clamp,
+}
+
+/// How a collection treats a write to an id it already holds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// Overwrite it.
+    #[default]
+    Upsert,
+    /// Reject it with 409, for append-only (audit) collections.
+    InsertOnly,
+}
+
+/// How upserts treat an all-zero vector, which has no direction under the
+/// cosine metrics. Zero query vectors are always rejected.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZeroVectors {
+    /// Fail the vector.
+    #[default]
+    Reject,
+    /// Drop it without error; counted as `skipped` in the upsert response.
+    Skip,
+    /// Store it with every component set to `f32::EPSILON` instead.
+    NormalizeToEpsilon,
+}
+
+impl ZeroVectors {
+    /// Whether `values` should be dropped under this policy. Empty values
+    /// are a pending vector, not a zero one.
+    pub fn skips(self, values: &[f32]) -> bool {
+        self == Self::Skip && !values.is_empty() && values.iter().all(|v| *v == 0.0)
+    }
+}
@@ -0,0 +1,7 @@
+//! Types shared across crates: the openvdb HTTP API's requests and
+//! responses, collection settings, and the WAL record format, so clients,
+//! tools and tests can speak the server's wire and disk formats.
+
+pub mod collection;
+pub mod models;
+pub mod wal;
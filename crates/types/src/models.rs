@@ -0,0 +1,974 @@
+use serde::{Deserialize, Serialize};
+use http::StatusCode;
+use serde_json::Value;
+
+use crate::collection::{Metric, OutOfRange, WriteMode, ZeroVectors};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HealthResponse {
+    pub status: String,
+}
+
+// ---------- errors ----------
+
+/// Body of every error response: `{ "error": { "code", "message" } }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorBody {
+    /// Stable, machine-readable kind, e.g. `not_found` or `invalid_api_key`.
+    pub code: String,
+    /// Human-readable detail; its wording may change.
+    pub message: String,
+}
+
+// ---------- collections: create ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateCollectionsRequest {
+    pub collections: Vec<CreateCollectionRequest>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateCollectionRequest {
+    pub name: String,
+    pub dimension: usize,
+    /// Metadata filter applied to every query on this collection.
+    #[serde(default)]
+    pub default_filter: Option<Value>,
+    /// `cosine`, `normalized_cosine`, `l2` or `dot`. Unset = the server's
+    /// default, `cosine` unless configured otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<Metric>,
+    /// `[min, max]` allowed for each vector value.
+    #[serde(default)]
+    pub value_range: Option<[f32; 2]>,
+    /// `reject` (default) or `clamp` values outside `value_range`.
+    #[serde(default)]
+    pub out_of_range: OutOfRange,
+    /// Most vectors the collection's index may hold, counting nodes left
+    /// behind by deletes and overwrites until it is compacted. At most the
+    /// server's `max_capacity`.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// `upsert` (default) or `insert_only`.
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// `reject` (default), `skip` or `normalize_to_epsilon` all-zero vectors.
+    #[serde(default)]
+    pub zero_vectors: ZeroVectors,
+    /// Reject upserts this close (as in `QueryMatch.distance`) to another
+    /// id's vector with 409: cosine distance (1 - similarity, 0 to 2) for
+    /// the cosine metrics, Euclidean distance for `l2`. Not supported with
+    /// `dot`, which has no distance to speak of. Costs a search per upsert;
+    /// off by default.
+    #[serde(default)]
+    pub dedup_threshold: Option<f32>,
+    /// Store vectors unit-length and search them by dot product, for
+    /// collections upserted far more often than they're read back. Same as
+    /// `metric: normalized_cosine`, so only valid with a cosine metric; the
+    /// original values are not kept and reads return the normalized ones.
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateCollectionResponse {
+    pub name: String,
+    pub dimension: usize,
+}
+
+// ---------- vectors: upsert/query ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpsertRequest {
+    pub vectors: Vec<VectorData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VectorData {
+    pub id: String,
+    /// Empty or absent to store a pending vector: kept with its metadata
+    /// but not searchable until upserted again with values.
+    #[serde(default)]
+    pub values: Vec<f32>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+    /// Apply the write only if the stored vector is at this version (0 =
+    /// must not exist yet); otherwise fail with 409.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_version: Option<u64>,
+}
+
+/// Query-string options for the upsert endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpsertParams {
+    /// Validate the whole batch outside the lock, then merge it in one pass.
+    #[serde(default)]
+    pub bulk: bool,
+}
+
+/// Query-string options for `GET /collections/:name/vectors/:id`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GetVectorParams {
+    /// Add `created_at` / `updated_at`.
+    #[serde(default)]
+    pub include_timestamps: bool,
+    /// Add the stored vector's L2 `norm`.
+    #[serde(default)]
+    pub include_norm: bool,
+}
+
+/// Query-string options for `GET /collections/:name/vectors`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ScrollParams {
+    /// Page size (default 100, at most 1000).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub include_values: bool,
+}
+
+/// A page of stored vectors, in id order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrollResponse {
+    pub vectors: Vec<ScrolledVector>,
+    /// Pass as `cursor` for the next page; absent on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScrolledVector {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// Only with `include_values`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<f32>>,
+}
+
+/// Query-string options for `GET /collections/:name/export/documents`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DocumentExportParams {
+    /// Metadata field holding the source text (default `document`).
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+/// One NDJSON line of a document export: what a re-embedding pipeline
+/// needs to recompute a vector and upsert it under the same id.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportedDocument {
+    pub id: String,
+    /// `None` when the field is missing or not a string.
+    pub document: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetVectorResponse {
+    pub id: String,
+    /// Values as stored (unit length for `normalized_cosine`).
+    pub values: Vec<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// Bumped on every write; pass it as `if_version` to update safely.
+    pub version: u64,
+    /// Upserted without values, see `VectorData.values`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pending: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// L2 norm of `values`. Only with `include_norm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub norm: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpsertResponse {
+    /// Same as `succeeded`; kept for existing clients.
+    pub upserted: usize,
+    #[serde(flatten)]
+    pub batch: BatchResponse,
+    /// All-zero vectors dropped by the collection's `zero_vectors: skip`
+    /// policy; not counted in `succeeded` or `failed`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub skipped: usize,
+}
+
+impl UpsertResponse {
+    /// Response for an all-or-nothing bulk upsert, which has no per-item
+    /// results to report.
+    pub fn bulk(upserted: usize, skipped: usize) -> Self {
+        Self {
+            upserted,
+            batch: BatchResponse {
+                succeeded: upserted,
+                failed: 0,
+                results: Vec::new(),
+            },
+            skipped,
+        }
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Query-string options for `POST /collections/:name/query`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct QueryParams {
+    /// Query the collection as of this history snapshot (see
+    /// `SnapshotResponse::history`) instead of the live data.
+    #[serde(default)]
+    pub snapshot: Option<u64>,
+}
+
+/// Query string of `GET /collections/:name/query/sse`, a subset of
+/// `QueryRequest` that fits in a URL.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SseQueryParams {
+    /// Comma-separated numbers.
+    pub vector: String,
+    pub top_k: usize,
+    /// `QueryRequest.filter` as JSON.
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+}
+
+/// Data of the `done` event that ends a query event stream.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryStreamEnd {
+    pub matches: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryRequest {
+    pub vector: Vec<f32>,
+    pub top_k: usize,
+    /// Metadata equality constraints, ANDed. A nested object constrains only
+    /// the keys it names; a candidate without a filtered key is excluded.
+    #[serde(default)]
+    pub filter: Option<Value>,
+    #[serde(default)]
+    pub score_mode: ScoreMode,
+    /// Business-rule boosts applied to the base scores before ranking.
+    #[serde(default)]
+    pub boosts: Vec<QueryBoost>,
+    /// Skip HNSW and scan every vector: exact top_k, higher latency.
+    #[serde(default)]
+    pub exact: bool,
+    /// Metadata field to diversify on: at most `group_size` matches are
+    /// returned per distinct value.
+    #[serde(default)]
+    pub group_by: Option<String>,
+    #[serde(default = "default_group_size")]
+    pub group_size: usize,
+    /// Answer 204 No Content instead of 200 with an empty `matches` array.
+    #[serde(default)]
+    pub empty_as_204: bool,
+    /// Report how each match's score was composed (`score_components`).
+    #[serde(default)]
+    pub explain: bool,
+    /// Add a `debug` section with search statistics to the response.
+    #[serde(default)]
+    pub debug: bool,
+    /// `cursor` from the previous page's response, to continue after its
+    /// last match. Pages are computed afresh on each request, so writes to
+    /// the collection between pages can shift, repeat or skip results.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Add each match's `created_at` / `updated_at`.
+    #[serde(default)]
+    pub include_timestamps: bool,
+    /// Add each match's stored L2 `norm`, to check normalization.
+    #[serde(default)]
+    pub include_norm: bool,
+    /// Add each match's stored `values`, e.g. for re-ranking client-side.
+    #[serde(default)]
+    pub include_values: bool,
+    /// Order matches with equal scores by a metadata field (before the id
+    /// tiebreak). Cannot be combined with `cursor`.
+    #[serde(default)]
+    pub sort_by: Option<SortBy>,
+    /// HNSW search breadth, overriding the one derived from `top_k`. Higher
+    /// trades latency for recall; must be at least `top_k` and at most the
+    /// server's `ef_search_max`.
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+    /// Only return matches whose (pre-boost) score is at least this. The
+    /// threshold narrows the results and `top_k` still caps them: set a
+    /// generous `top_k` for "everything more similar than `min_score`".
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Whether the query must see every earlier write, see `Consistency`.
+    #[serde(default)]
+    pub consistency: Consistency,
+}
+
+fn default_group_size() -> usize {
+    1
+}
+
+impl QueryRequest {
+    /// Plain top-`top_k` query with every option at its default.
+    pub fn new(vector: Vec<f32>, top_k: usize) -> Self {
+        Self {
+            vector,
+            top_k,
+            filter: None,
+            score_mode: ScoreMode::default(),
+            boosts: Vec::new(),
+            exact: false,
+            group_by: None,
+            group_size: default_group_size(),
+            empty_as_204: false,
+            explain: false,
+            debug: false,
+            cursor: None,
+            include_timestamps: false,
+            include_norm: false,
+            include_values: false,
+            sort_by: None,
+            ef_search: None,
+            min_score: None,
+            consistency: Consistency::default(),
+        }
+    }
+}
+
+/// Multiply the score of candidates whose metadata matches `filter` (same
+/// equality semantics as `QueryRequest.filter`) by `factor` (> 0).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryBoost {
+    pub filter: Value,
+    pub factor: f32,
+}
+
+/// How `QueryMatch.score` is reported.
+///
+/// - `cosine` (default): the collection metric's score, higher is better
+///   (cosine similarity in `[-1, 1]` for the cosine metrics).
+/// - `angular`: angular distance `acos(clamp(cos_sim, -1, 1))` in radians,
+///   in `[0, pi]`, lower is better. Cosine metrics only. Result order is the
+///   same in both modes.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreMode {
+    #[default]
+    Cosine,
+    Angular,
+}
+
+/// Freshness a query asks for.
+///
+/// - `strong`: searches the collection under its lock, seeing every write
+///   acknowledged before the query arrived.
+/// - `eventual`: may be answered from a result cache, without taking the
+///   lock, with the result an identical `eventual` query (same collection,
+///   same body) got at most `OPENVDB_EVENTUAL_MAX_STALENESS_MS` ago, so
+///   writes made since are missing from it. Its `ETag` is that of the
+///   cached result. Only repeated queries benefit: any other query searches
+///   under the lock, as `strong` does.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Consistency {
+    #[default]
+    Strong,
+    Eventual,
+}
+
+/// Secondary sort key for `QueryRequest.sort_by`. Numbers sort before
+/// strings; matches where `field` is missing or of another type sort last
+/// in either order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SortBy {
+    pub field: String,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Body for `POST /collections/:name/query/count`.
+///
+/// With only a `filter`, counts matching vectors. Adding `vector` +
+/// `min_score` additionally requires the similarity to reach the threshold.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CountQueryRequest {
+    #[serde(default)]
+    pub vector: Option<Vec<f32>>,
+    #[serde(default)]
+    pub filter: Option<Value>,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+}
+
+/// Body for `POST /collections/:name/query/estimate`: the parts of a
+/// `QueryRequest` that drive its cost.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryEstimateRequest {
+    pub top_k: usize,
+    #[serde(default)]
+    pub filter: Option<Value>,
+    #[serde(default)]
+    pub exact: bool,
+    /// As `QueryRequest.ef_search`.
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryEstimateResponse {
+    /// Vectors in the collection.
+    pub vectors: usize,
+    /// Vectors the search would compare against the query.
+    pub candidates: usize,
+    /// Estimated fraction of vectors matching the filter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter_selectivity: Option<f64>,
+    /// Matches the query is expected to return.
+    pub expected_matches: usize,
+    /// True when the query would scan every vector instead of using HNSW.
+    pub exact: bool,
+    pub latency_class: LatencyClass,
+}
+
+/// Rough latency bucket, from the number of candidates.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyClass {
+    Fast,
+    Moderate,
+    Slow,
+}
+
+/// Body for `POST /collections/:name/count`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CountRequest {
+    /// Same equality constraints as `QueryRequest.filter`; absent counts
+    /// every vector.
+    #[serde(default)]
+    pub filter: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CountResponse {
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryMatch {
+    pub id: String,
+    pub score: f32,
+    /// Raw HNSW distance under the collection metric, lower is closer.
+    /// `score` is derived from it and may also include boosts.
+    #[serde(default)]
+    pub distance: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// Only with `explain`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_components: Option<ScoreComponents>,
+    /// First upsert of this id, in ms since the Unix epoch. Only with
+    /// `include_timestamps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Latest upsert of this id. Only with `include_timestamps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// L2 norm of the stored vector (1 for `normalized_cosine`). Only with
+    /// `include_norm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub norm: Option<f32>,
+    /// Stored values (unit-length for `normalized_cosine`). Only with
+    /// `include_values`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<f32>>,
+}
+
+/// Breakdown of a fused score: `score = vector * boost`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScoreComponents {
+    /// Similarity from the vector search alone.
+    pub vector: f32,
+    /// Product of the factors of every matching boost (1 if none matched).
+    pub boost: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryResponse {
+    pub matches: Vec<QueryMatch>,
+    /// True when the matches come from an exact scan (requested via
+    /// `exact` or as a fallback), false for an approximate HNSW search.
+    pub exact: bool,
+    /// Set when the HNSW search was unreliable and an exact scan was used.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exact_fallback: bool,
+    /// Only with `debug`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<QueryDebug>,
+    /// Set when the page is full: pass it back as `cursor` for the next one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// The collection's metric, which determines the scores' range. Scores
+    /// are higher-is-better under every metric (see `Metric`) unless
+    /// `score_mode` is `angular`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<Metric>,
+}
+
+impl QueryResponse {
+    /// Drop everything but ids, scores and the paging cursor.
+    pub fn minimal(self) -> MinimalQueryResponse {
+        MinimalQueryResponse {
+            matches: self
+                .matches
+                .into_iter()
+                .map(|m| MinimalMatch {
+                    id: m.id,
+                    score: m.score,
+                })
+                .collect(),
+            cursor: self.cursor,
+        }
+    }
+}
+
+/// `QueryResponse` as served for `Accept: application/json; profile=minimal`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MinimalQueryResponse {
+    pub matches: Vec<MinimalMatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MinimalMatch {
+    pub id: String,
+    pub score: f32,
+}
+
+/// Body for `POST /collections/:name/query/batch`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryBatchRequest {
+    pub queries: Vec<QueryRequest>,
+}
+
+/// One result per query, in input order. Shaped like `BatchResponse`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryBatchResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<QueryBatchItem>,
+}
+
+/// A query's `QueryResponse` fields, or its `error`, plus the HTTP status
+/// it would have had on its own.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryBatchItem {
+    pub status: u16,
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<QueryResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl QueryBatchItem {
+    pub fn from_result(result: Result<QueryResponse, (StatusCode, String)>) -> Self {
+        match result {
+            Ok(response) => Self {
+                status: StatusCode::OK.as_u16(),
+                response: Some(response),
+                error: None,
+            },
+            Err((status, error)) => Self {
+                status: status.as_u16(),
+                response: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Search statistics, for tuning `ef` against recall.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryDebug {
+    /// Vectors compared against the query: HNSW nodes visited, or every
+    /// candidate of an exact scan.
+    pub visited: usize,
+    /// Search breadth HNSW ran with; absent for exact scans.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ef: Option<usize>,
+}
+
+// ---------- collections: list/get ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectionSummary {
+    pub name: String,
+    pub dimension: usize,
+    pub vectors: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ListCollectionsResponse {
+    pub collections: Vec<CollectionSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetCollectionResponse {
+    pub name: String,
+    pub dimension: usize,
+    pub vectors: usize,
+}
+
+// ---------- collections: stats ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectionStatsResponse {
+    pub name: String,
+    pub dimension: usize,
+    pub vectors: usize,
+    pub index_type: String,
+    /// Serialized size of all stored metadata.
+    pub metadata_bytes: usize,
+    /// Rough RAM the collection holds: values, metadata and the HNSW graph
+    /// (dead nodes included). Leaves out map and allocator overhead.
+    #[serde(default)]
+    pub approx_memory_bytes: usize,
+    /// Vectors stored without values (included in `vectors`).
+    #[serde(default)]
+    pub pending_vectors: usize,
+    /// Nodes in the HNSW graph, including dead ones.
+    #[serde(default)]
+    pub index_nodes: usize,
+    /// Nodes left behind by deletes and overwrites until the collection is
+    /// compacted.
+    #[serde(default)]
+    pub dead_nodes: usize,
+    /// Most nodes the index may hold; `index_nodes` counts against it.
+    #[serde(default)]
+    pub capacity: usize,
+    /// Fraction of the vectors upserted since the collection was loaded that
+    /// arrived unit-length (norm within 1e-3 of 1), to spot an embedding
+    /// pipeline drifting. Cosine metrics only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_fraction: Option<f64>,
+    /// Most `vectors` the collection may hold, if limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_vectors: Option<usize>,
+    /// Collections the tenant has.
+    #[serde(default)]
+    pub tenant_collections: usize,
+    /// Most collections the tenant may have, if limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_collections: Option<usize>,
+}
+
+/// `GET /collections/stats`: every collection of the tenant, by name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AllCollectionStatsResponse {
+    pub collections: Vec<CollectionStatsResponse>,
+}
+
+/// Body for `POST /collections/:name/distances`: two stored vectors, or a
+/// stored vector and a supplied one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum DistanceRequest {
+    Pair { id_a: String, id_b: String },
+    Vector { id: String, vector: Vec<f32> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DistanceResponse {
+    pub metric: Metric,
+    /// Distance under `metric`, as HNSW sees it.
+    pub distance: f32,
+    /// The score a query would report for this pair.
+    pub similarity: f32,
+}
+
+/// Query-string options for `POST /collections/:name/reindex`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReindexParams {
+    /// Rebuild with this capacity instead of the current one.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+}
+
+/// Response for `POST /collections/:name/reindex`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReindexResponse {
+    /// Vectors the new index was built from.
+    pub vectors: usize,
+    /// Writes that arrived during the build and were applied before the swap.
+    pub replayed_writes: usize,
+}
+
+/// Response for `POST /collections/:name/compact`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompactResponse {
+    pub vectors: usize,
+    /// Dead HNSW nodes dropped by the rebuild.
+    pub removed_nodes: usize,
+}
+
+// ---------- admin: global collection inventory ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdminCollectionSummary {
+    pub tenant: String,
+    pub name: String,
+    pub dimension: usize,
+    pub vectors: usize,
+    pub index_type: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdminListCollectionsResponse {
+    pub collections: Vec<AdminCollectionSummary>,
+}
+
+// ---------- admin: disk/memory reconciliation ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectionRef {
+    pub tenant: String,
+    pub collection: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReconcileResponse {
+    /// Present on disk (snapshot + WAL) but not in memory.
+    pub disk_only: Vec<CollectionRef>,
+    /// Present in memory but a restart would not recreate it.
+    pub memory_only: Vec<CollectionRef>,
+    /// True when a repair rewrote disk state from memory.
+    pub repaired: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_files: Vec<String>,
+}
+
+// ---------- admin: benchmark ----------
+
+/// Synthetic load run against a throwaway collection.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkRequest {
+    #[serde(default = "default_benchmark_vectors")]
+    pub vectors: usize,
+    #[serde(default = "default_benchmark_dimension")]
+    pub dimension: usize,
+    #[serde(default = "default_benchmark_queries")]
+    pub queries: usize,
+    #[serde(default = "default_benchmark_top_k")]
+    pub top_k: usize,
+    /// Also load the same vectors through the staged bulk path
+    /// (`?bulk=true`) in batches of this size, to compare with `upserts`.
+    #[serde(default)]
+    pub bulk_batch: Option<usize>,
+}
+
+fn default_benchmark_vectors() -> usize {
+    10_000
+}
+
+fn default_benchmark_dimension() -> usize {
+    128
+}
+
+fn default_benchmark_queries() -> usize {
+    1_000
+}
+
+fn default_benchmark_top_k() -> usize {
+    10
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkResponse {
+    pub dimension: usize,
+    pub upserts: BenchmarkPhase,
+    /// With `bulk_batch`: `operations` and `ops_per_sec` count vectors, as
+    /// in `upserts`, while the latencies are per staged-and-merged batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bulk_upserts: Option<BenchmarkPhase>,
+    pub queries: BenchmarkPhase,
+    /// Stopped early by `DELETE /admin/benchmark`; the phases cover only
+    /// the operations that ran.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+/// Throughput and latency percentiles of one kind of operation.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BenchmarkPhase {
+    pub operations: usize,
+    pub ops_per_sec: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+// ---------- delete responses ----------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteVectorsRequest {
+    pub ids: Vec<String>,
+}
+
+/// Body of `PATCH /collections/:name/vectors/:id`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMetadataRequest {
+    /// Replaces the stored metadata as a whole; `null` clears it.
+    pub metadata: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateMetadataResponse {
+    pub id: String,
+    /// Version after the update.
+    pub version: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteVectorResponse {
+    pub deleted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteCollectionResponse {
+    pub deleted: bool,
+}
+
+/// Delete every collection of the tenant whose name starts with `prefix`.
+/// `confirm` must be true; it guards against an accidental mass delete.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteByPrefixRequest {
+    pub prefix: String,
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteByPrefixResponse {
+    /// Names of the deleted collections, sorted.
+    pub deleted: Vec<String>,
+}
+
+// ----------- maintenance ------------
+
+/// Body of `POST /admin/maintenance`, and its response.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Maintenance {
+    /// While set, every write is refused with 503; reads are unaffected.
+    pub enabled: bool,
+}
+
+// ----------- snapshot ------------
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotResponse {
+    pub success: bool,
+    pub message: String,
+    /// Pass as `?snapshot=` to query this snapshot later; only when
+    /// snapshot history is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<u64>,
+    /// Only for `?dry_run=true`, which writes nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SnapshotEstimate>,
+}
+
+/// Query-string options for `POST /admin/snapshot`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SnapshotParams {
+    /// Measure the snapshot instead of writing it; the WAL is left alone.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What a snapshot taken now would hold.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotEstimate {
+    pub tenants: usize,
+    pub collections: usize,
+    pub vectors: usize,
+    /// Size of the snapshot file.
+    pub bytes: u64,
+    /// Time spent serializing; a real snapshot adds the disk write on top.
+    pub elapsed_ms: u64,
+}
+
+// ---------- batch results ----------
+
+/// Shared body of every batch endpoint: one entry per input item, in order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<ItemStatus>,
+}
+
+/// Outcome of one batch item: its id (vector id or collection name), the
+/// HTTP status it would have had on its own, and an error message if any.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ItemStatus {
+    pub id: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ItemStatus {
+    pub fn ok(id: String) -> Self {
+        Self {
+            id,
+            status: StatusCode::OK.as_u16(),
+            error: None,
+        }
+    }
+
+    pub fn failed(id: String, status: StatusCode, error: String) -> Self {
+        Self {
+            id,
+            status: status.as_u16(),
+            error: Some(error),
+        }
+    }
+
+    pub fn from_result(id: String, result: Result<(), (StatusCode, String)>) -> Self {
+        match result {
+            Ok(()) => Self::ok(id),
+            Err((status, error)) => Self::failed(id, status, error),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
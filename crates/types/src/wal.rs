@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::collection::CollectionConfig;
+
+/// One line of the server's write-ahead log (`wal.jsonl`).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalEntry {
+    CreateCollection {
+        tenant: String,
+        name: String,
+        dimension: usize,
+        #[serde(default)]
+        config: CollectionConfig,
+    },
+    DeleteCollection {
+        tenant: String,
+        name: String,
+    },
+    UpsertVector {
+        tenant: String,
+        collection: String,
+        id: String,
+        values: Vec<f32>,
+        metadata: Option<Value>,
+        /// Write time in ms since the Unix epoch; absent in entries written
+        /// before vectors carried timestamps.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        written_at: Option<u64>,
+        /// Version of the vector after this write. Bulk upserts leave it out
+        /// (it is only known under the lock); replay then bumps the previous
+        /// version, which yields the same number.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<u64>,
+    },
+    DeleteVector {
+        tenant: String,
+        collection: String,
+        id: String,
+    },
+    /// Fixes the dimension of a collection created with `dimension: 0`,
+    /// written just before the upsert that inferred it.
+    SetDimension {
+        tenant: String,
+        collection: String,
+        dimension: usize,
+    },
+    /// Changes a collection's capacity, written when a reindex rebuilt it
+    /// with a new one.
+    SetCapacity {
+        tenant: String,
+        collection: String,
+        capacity: usize,
+    },
+    /// Replaces a vector's metadata, leaving its values alone.
+    UpdateMetadata {
+        tenant: String,
+        collection: String,
+        id: String,
+        metadata: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        written_at: Option<u64>,
+    },
+}
+
+impl WalEntry {
+    pub fn tenant(&self) -> &str {
+        match self {
+            WalEntry::CreateCollection { tenant, .. }
+            | WalEntry::DeleteCollection { tenant, .. }
+            | WalEntry::UpsertVector { tenant, .. }
+            | WalEntry::DeleteVector { tenant, .. }
+            | WalEntry::SetDimension { tenant, .. }
+            | WalEntry::SetCapacity { tenant, .. }
+            | WalEntry::UpdateMetadata { tenant, .. } => tenant,
+        }
+    }
+}
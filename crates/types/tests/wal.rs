@@ -0,0 +1,46 @@
+use fastdb_types::collection::{CollectionConfig, Metric};
+use fastdb_types::wal::WalEntry;
+use serde_json::json;
+
+#[test]
+fn wal_lines_keep_their_on_disk_shape() {
+    let line = json!({
+        "type": "create_collection",
+        "tenant": "t",
+        "name": "docs",
+        "dimension": 2,
+        "config": { "metric": "normalized_cosine" },
+    });
+    let entry: WalEntry = serde_json::from_value(line).unwrap();
+    match &entry {
+        WalEntry::CreateCollection { config, .. } => {
+            assert_eq!(config.metric, Metric::NormalizedCosine);
+        }
+        other => panic!("unexpected entry {:?}", other),
+    }
+    assert_eq!(entry.tenant(), "t");
+
+    // Entries written before collection configs existed still parse.
+    let old: WalEntry = serde_json::from_value(json!({
+        "type": "create_collection",
+        "tenant": "t",
+        "name": "docs",
+        "dimension": 2,
+    }))
+    .unwrap();
+    assert!(matches!(
+        old,
+        WalEntry::CreateCollection { config: CollectionConfig { value_range: None, .. }, .. }
+    ));
+
+    let upsert = WalEntry::UpsertVector {
+        tenant: "t".into(),
+        collection: "docs".into(),
+        id: "a".into(),
+        values: vec![1.0, 0.0],
+        metadata: None,
+        written_at: Some(1),
+        version: None,
+    };
+    assert_eq!(serde_json::to_value(&upsert).unwrap()["type"], "upsert_vector");
+}